@@ -0,0 +1,485 @@
+//! Bond perception and the molecular graph of a structure.
+
+mod templates;
+mod rings;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::structs::{PDB, Atom};
+use crate::spatial::SpatialIndex;
+use crate::ccd::ComponentDictionary;
+use self::templates::{residue_template, link_atoms};
+
+pub use self::rings::{Ring, RingSet};
+
+/// The order of a covalent bond
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BondOrder {
+    Single,
+    Double,
+    Triple,
+    Aromatic,
+}
+
+/// Where a bond was perceived from, in order of precedence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BondSource {
+    /// A CONECT record, or the bond table of a MOL2 file
+    Conect,
+    /// The template of a standard amino acid or nucleotide
+    Template,
+    /// A peptide, phosphodiester or disulfide bond between two standard residues
+    Link,
+    /// The covalent radii of the atoms
+    Distance,
+}
+
+/// A covalent bond, atoms are given by their index in the `PDB` with `a < b`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bond {
+    pub a: usize,
+    pub b: usize,
+    pub order: BondOrder,
+    pub source: BondSource,
+}
+
+impl Bond {
+    /// The other atom of the bond, `None` if `atom` is not part of it
+    pub fn partner(&self, atom: usize) -> Option<usize> {
+        if atom == self.a {
+            Some(self.b)
+        } else if atom == self.b {
+            Some(self.a)
+        } else {
+            None
+        }
+    }
+}
+
+/// Parameters of the bond perception
+#[derive(Debug, Clone, PartialEq)]
+pub struct BondParameters {
+    /// Two atoms are bonded if their distance is at most the sum of their
+    /// covalent radii plus this tolerance (Å)
+    pub tolerance: f64,
+    /// Minimum distance (Å) of two bonded atoms, closer pairs are overlapping atoms
+    pub min_distance: f64,
+    /// Whether CONECT records are used. The bonds of an atom with CONECT records
+    /// are then taken from the templates and the records only.
+    pub use_conect: bool,
+    /// Whether standard residues are bonded by their templates instead of distances
+    pub use_templates: bool,
+}
+
+impl Default for BondParameters {
+    fn default() -> Self {
+        BondParameters {
+            tolerance: 0.4,
+            min_distance: 0.4,
+            use_conect: true,
+            use_templates: true,
+        }
+    }
+}
+
+/// The atoms of a structure as nodes and their bonds as edges
+#[derive(Debug, Clone, PartialEq)]
+pub struct MolecularGraph {
+    bonds: Vec<Bond>,
+    neighbors: Vec<Vec<usize>>,
+    /// The bonds of each atom as indices into `bonds`
+    incident: Vec<Vec<usize>>,
+}
+
+impl MolecularGraph {
+    /// Creates the graph of `atom_count` atoms. Bonds are normalized to `a < b`,
+    /// the first of duplicated bonds is kept and self bonds or bonds to atoms
+    /// outside of the graph are dropped.
+    pub fn new(atom_count: usize, bonds: Vec<Bond>) -> MolecularGraph {
+        let mut graph = MolecularGraph {
+            bonds: Vec::new(),
+            neighbors: vec![Vec::new(); atom_count],
+            incident: vec![Vec::new(); atom_count],
+        };
+        let mut seen = HashSet::new();
+        for mut bond in bonds {
+            if bond.a > bond.b {
+                std::mem::swap(&mut bond.a, &mut bond.b);
+            }
+            if bond.a == bond.b || bond.b >= atom_count || !seen.insert((bond.a, bond.b)) {
+                continue;
+            }
+            graph.neighbors[bond.a].push(bond.b);
+            graph.neighbors[bond.b].push(bond.a);
+            graph.incident[bond.a].push(graph.bonds.len());
+            graph.incident[bond.b].push(graph.bonds.len());
+            graph.bonds.push(bond);
+        }
+        graph
+    }
+
+    pub fn atom_count(&self) -> usize {
+        self.neighbors.len()
+    }
+
+    pub fn bond_count(&self) -> usize {
+        self.bonds.len()
+    }
+
+    pub fn bonds(&self) -> impl DoubleEndedIterator<Item = &Bond> + '_ {
+        self.bonds.iter()
+    }
+
+    /// The bond between two atoms, `None` if they are not bonded
+    pub fn bond(&self, a: usize, b: usize) -> Option<&Bond> {
+        self.incident
+            .get(a)?
+            .iter()
+            .map(|i| &self.bonds[*i])
+            .find(|bond| bond.partner(a) == Some(b))
+    }
+
+    /// The bonds of an atom
+    pub fn atom_bonds(&self, atom: usize) -> impl Iterator<Item = &Bond> + '_ {
+        self.incident.get(atom).into_iter().flatten().map(move |i| &self.bonds[*i])
+    }
+
+    /// The atoms bonded to an atom, empty for atoms outside of the graph
+    pub fn neighbors(&self, atom: usize) -> &[usize] {
+        self.neighbors.get(atom).map(|n| n.as_slice()).unwrap_or(&[])
+    }
+
+    /// The number of bonds of an atom
+    pub fn degree(&self, atom: usize) -> usize {
+        self.neighbors(atom).len()
+    }
+
+    /// The atoms reachable from `start` in breadth-first order, `start` first
+    pub fn traverse(&self, start: usize) -> Vec<usize> {
+        if start >= self.atom_count() {
+            return Vec::new();
+        }
+        let mut visited = vec![false; self.atom_count()];
+        let mut order = vec![start];
+        let mut queue = VecDeque::from([start]);
+        visited[start] = true;
+        while let Some(atom) = queue.pop_front() {
+            for next in self.neighbors[atom].iter().copied() {
+                if !visited[next] {
+                    visited[next] = true;
+                    order.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+        order
+    }
+
+    /// The connected components (molecules) of the graph, each sorted, in order of their first atom
+    pub fn components(&self) -> Vec<Vec<usize>> {
+        let mut assigned = vec![false; self.atom_count()];
+        let mut components = Vec::new();
+        for start in 0..self.atom_count() {
+            if assigned[start] {
+                continue;
+            }
+            let mut component = self.traverse(start);
+            for atom in component.iter() {
+                assigned[*atom] = true;
+            }
+            component.sort_unstable();
+            components.push(component);
+        }
+        components
+    }
+
+    /// The atoms on a shortest path of bonds from `from` to `to`, both included,
+    /// `None` if they are not connected
+    pub fn shortest_path(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        if from >= self.atom_count() || to >= self.atom_count() {
+            return None;
+        }
+        let mut previous = vec![None; self.atom_count()];
+        let mut queue = VecDeque::from([from]);
+        previous[from] = Some(from);
+        while let Some(atom) = queue.pop_front() {
+            if atom == to {
+                let mut path = vec![to];
+                while *path.last().unwrap() != from {
+                    path.push(previous[*path.last().unwrap()].unwrap());
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for next in self.neighbors[atom].iter().copied() {
+                if previous[next].is_none() {
+                    previous[next] = Some(atom);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Perceives the covalent bonds of a structure. Standard amino acids and nucleotides
+/// are bonded by their templates and linked to the next residue of the chain by
+/// peptide or phosphodiester bonds, cysteines by disulfide bonds. All other atoms,
+/// including atoms of standard residues their template does not name, are bonded
+/// by their covalent radii; unmatched hydrogens go to the closest heavy atom.
+/// CONECT records take precedence over perceived bonds. Metals are only bonded
+/// by CONECT records and atoms of different alternate locations are never bonded.
+pub fn perceive_bonds(pdb: &PDB, parameters: &BondParameters) -> MolecularGraph {
+    perceive(pdb, &SpatialIndex::new(pdb), parameters, None)
+}
+
+/// Perceives the covalent bonds of a structure like `perceive_bonds`, with the
+/// components of the dictionary as templates. Their bond orders are used for ligands
+/// and modified residues are linked into the chain like the standard residues.
+pub fn perceive_bonds_with_components(pdb: &PDB, parameters: &BondParameters, components: &ComponentDictionary) -> MolecularGraph {
+    perceive(pdb, &SpatialIndex::new(pdb), parameters, Some(components))
+}
+
+pub(crate) fn perceive(
+    pdb: &PDB,
+    index: &SpatialIndex,
+    parameters: &BondParameters,
+    components: Option<&ComponentDictionary>,
+) -> MolecularGraph {
+    let atoms: Vec<&Atom> = pdb.atoms().collect();
+    let radii: Vec<Option<f64>> = atoms
+        .iter()
+        .map(|a| a.inferred_element().filter(|e| !e.is_metal()).map(|e| e.covalent_radius()))
+        .collect();
+    let compatible = |i: usize, j: usize| match (atoms[i].alt_location(), atoms[j].alt_location()) {
+        (Some(x), Some(y)) => x == y,
+        _ => true,
+    };
+    let in_range = |i: usize, j: usize| match (radii[i], radii[j]) {
+        (Some(r_i), Some(r_j)) => {
+            let distance = atoms[i].distance(atoms[j]);
+            distance >= parameters.min_distance && distance <= r_i + r_j + parameters.tolerance
+        },
+        _ => false,
+    };
+
+    let mut bonds: HashMap<(usize, usize), Bond> = HashMap::new();
+    let mut add = |i: usize, j: usize, order, source| {
+        let key = (i.min(j), i.max(j));
+        if i != j && compatible(i, j) {
+            bonds.entry(key).or_insert(Bond { a: key.0, b: key.1, order, source });
+        }
+    };
+
+    let mut conect_atoms = HashSet::new();
+    let mut conects = Vec::new();
+    if parameters.use_conect {
+        let by_serial: HashMap<usize, usize> = atoms.iter().enumerate().map(|(i, a)| (*a.serial_number(), i)).collect();
+        for (a, b) in pdb.conects() {
+            if let (Some(i), Some(j)) = (by_serial.get(a), by_serial.get(b)) {
+                conect_atoms.insert(*i);
+                conect_atoms.insert(*j);
+                conects.push((*i, *j));
+            }
+        }
+    }
+
+    let residues = pdb.residue_indices();
+    let res_name = |r: usize| atoms[residues[r].1[0]].res_name().as_str();
+    let component = |r: usize| components.and_then(|c| c.get(res_name(r)));
+    let template = |r: usize| match component(r) {
+        Some(component) => Some(component.bonds.iter().map(|b| (b.a.as_str(), b.b.as_str(), b.order)).collect()),
+        None => residue_template(res_name(r)),
+    };
+    let link = |r: usize| match component(r) {
+        Some(component) if component.is_peptide_linking() => Some(("C", "N")),
+        Some(component) if component.is_nucleotide_linking() => Some(("O3'", "P")),
+        Some(_) => None,
+        None => link_atoms(res_name(r)),
+    };
+    // atoms bonded by templates are only bonded by distance to other residues,
+    // and not at all to other polymer residues, atoms the template does not name
+    // are bonded by distance
+    let mut templated = vec![false; atoms.len()];
+    let mut polymer = vec![false; atoms.len()];
+    let mut residue_of = vec![0; atoms.len()];
+    for (r, (_, indices)) in residues.iter().enumerate() {
+        for i in indices.iter() {
+            residue_of[*i] = r;
+        }
+    }
+    let find = |r: usize, name: &str| -> Vec<usize> {
+        residues[r].1.iter().copied().filter(|i| atoms[*i].atom_name() == name).collect()
+    };
+    if parameters.use_templates {
+        for (r, (residue, indices)) in residues.iter().enumerate() {
+            let template: Vec<(&str, &str, BondOrder)> = match template(r) {
+                Some(template) => template,
+                None => continue,
+            };
+            let mut matched = HashSet::new();
+            for (x, y, order) in template {
+                for i in find(r, x) {
+                    for j in find(r, y) {
+                        add(i, j, order, BondSource::Template);
+                        matched.insert(i);
+                        matched.insert(j);
+                    }
+                }
+            }
+            // hydrogens are named inconsistently, unmatched ones are bonded to the closest heavy atom
+            let unmatched: Vec<usize> = indices.iter().copied().filter(|i| atoms[*i].is_hydrogen() && !matched.contains(i)).collect();
+            for h in unmatched {
+                let closest = indices
+                    .iter()
+                    .copied()
+                    .filter(|i| !atoms[*i].is_hydrogen() && compatible(h, *i) && in_range(h, *i))
+                    .min_by(|i, j| atoms[h].distance(atoms[*i]).total_cmp(&atoms[h].distance(atoms[*j])));
+                if let Some(heavy) = closest {
+                    add(h, heavy, BondOrder::Single, BondSource::Template);
+                    matched.insert(h);
+                }
+            }
+            for i in indices.iter() {
+                templated[*i] = matched.contains(i);
+                polymer[*i] = link(r).is_some();
+            }
+
+            if let Some((x, y)) = link(r) {
+                let next = residues.get(r + 1).filter(|(next, _)| next.chain_id == residue.chain_id);
+                if next.is_some() && link(r + 1) == Some((x, y)) {
+                    for i in find(r, x) {
+                        for j in find(r + 1, y).into_iter().filter(|j| in_range(i, *j)) {
+                            add(i, j, BondOrder::Single, BondSource::Link);
+                        }
+                    }
+                }
+            }
+            if res_name(r) == "CYS" {
+                for i in find(r, "SG") {
+                    for j in index.within_atom(atoms[i], 2.5) {
+                        if atoms[j].atom_name() == "SG" && atoms[j].res_name() == "CYS" && !indices.contains(&j) && in_range(i, j) {
+                            add(i, j, BondOrder::Single, BondSource::Link);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let max_radius = radii.iter().flatten().cloned().fold(0.0, f64::max);
+    for (i, j, _) in index.pairs_within(2.0 * max_radius + parameters.tolerance) {
+        let both_templated = templated[i] && templated[j] && (residue_of[i] == residue_of[j] || (polymer[i] && polymer[j]));
+        if both_templated || conect_atoms.contains(&i) || conect_atoms.contains(&j) {
+            continue;
+        }
+        if in_range(i, j) {
+            add(i, j, BondOrder::Single, BondSource::Distance);
+        }
+    }
+
+    for (i, j) in conects {
+        let key = (i.min(j), i.max(j));
+        let order = bonds.get(&key).map(|b| b.order).unwrap_or(BondOrder::Single);
+        bonds.insert(key, Bond { a: key.0, b: key.1, order, source: BondSource::Conect });
+    }
+
+    let mut bonds: Vec<Bond> = bonds.into_values().collect();
+    bonds.sort_by_key(|b| (b.a, b.b));
+    MolecularGraph::new(atoms.len(), bonds)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{atom, peptide};
+
+    #[test]
+    fn peptide_bonds_from_templates() {
+        let pdb = peptide(&[("ALA", -60.0, -45.0), ("SER", -60.0, -45.0), ("GLY", -60.0, -45.0)]);
+        let graph = perceive_bonds(&pdb, &BondParameters::default());
+        let index = |seq: usize, name: &str| pdb.atoms().position(|a| *a.res_seq() == seq && a.atom_name() == name).unwrap();
+        // ALA and SER have N-CA, CA-C, C=O and CA-CB, the GLY template has no CB
+        assert_eq!(graph.bond_count(), 4 + 4 + 3 + 2);
+        assert_eq!(graph.bond(index(1, "C"), index(2, "N")).unwrap().source, BondSource::Link);
+        assert_eq!(graph.bond(index(2, "C"), index(2, "O")).unwrap().order, BondOrder::Double);
+        assert_eq!(graph.components().len(), 1);
+        assert_eq!(graph.shortest_path(index(1, "CB"), index(2, "N")), Some(vec![index(1, "CB"), index(1, "CA"), index(1, "C"), index(2, "N")]));
+    }
+
+    #[test]
+    fn adenine_amino_group_and_unknown_atoms() {
+        let mut pdb = PDB::new();
+        pdb.add_atom(atom(1, "C5", "DA", 1, (0.0, 0.0, 0.0), "C"));
+        pdb.add_atom(atom(2, "C6", "DA", 1, (1.40, 0.0, 0.0), "C"));
+        pdb.add_atom(atom(3, "N6", "DA", 1, (2.07, 1.18, 0.0), "N"));
+        pdb.add_atom(atom(4, "N1", "DA", 1, (2.07, -1.18, 0.0), "N"));
+        // a methyl group the template does not know
+        pdb.add_atom(atom(5, "CM", "DA", 1, (-0.75, 1.30, 0.0), "C"));
+        let graph = perceive_bonds(&pdb, &BondParameters::default());
+        assert_eq!(graph.bond(1, 2).unwrap().source, BondSource::Template);
+        assert_eq!(graph.bond(1, 3).unwrap().order, BondOrder::Double);
+        assert_eq!(graph.bond(0, 4).unwrap().source, BondSource::Distance);
+        assert_eq!(graph.bond_count(), 4);
+    }
+
+    #[test]
+    fn alternate_locations_are_not_bonded() {
+        let mut pdb = PDB::new();
+        pdb.add_atom(atom(1, "C1", "EOH", 1, (0.0, 0.0, 0.0), "C"));
+        let mut a = atom(2, "C2", "EOH", 1, (1.52, 0.0, 0.0), "C");
+        a.set_alt_location("A").unwrap();
+        let mut b = atom(3, "C2", "EOH", 1, (1.0, 1.1, 0.0), "C");
+        b.set_alt_location("B").unwrap();
+        pdb.add_atom(a);
+        pdb.add_atom(b);
+        let graph = perceive_bonds(&pdb, &BondParameters::default());
+        assert_eq!(graph.bonds().map(|b| (b.a, b.b)).collect::<Vec<_>>(), vec![(0, 1), (0, 2)]);
+    }
+
+    #[test]
+    fn ligand_bonds_and_conect_precedence() {
+        let mut pdb = PDB::new();
+        // ethanol and a lone sodium ion
+        pdb.add_atom(atom(1, "C1", "EOH", 1, (0.0, 0.0, 0.0), "C"));
+        pdb.add_atom(atom(2, "C2", "EOH", 1, (1.52, 0.0, 0.0), "C"));
+        pdb.add_atom(atom(3, "O", "EOH", 1, (2.0, 1.35, 0.0), "O"));
+        pdb.add_atom(atom(4, "NA", "NA", 2, (4.0, 1.35, 0.0), "NA"));
+        let graph = perceive_bonds(&pdb, &BondParameters::default());
+        assert_eq!(graph.bonds().map(|b| (b.a, b.b)).collect::<Vec<_>>(), vec![(0, 1), (1, 2)]);
+        assert!(graph.bonds().all(|b| b.source == BondSource::Distance));
+        assert_eq!(graph.components(), vec![vec![0, 1, 2], vec![3]]);
+
+        // the CONECT records of C2 replace its perceived bonds
+        pdb.add_conect(2, 4).unwrap();
+        let graph = perceive_bonds(&pdb, &BondParameters::default());
+        assert_eq!(graph.bonds().map(|b| (b.a, b.b, b.source)).collect::<Vec<_>>(), vec![(1, 3, BondSource::Conect)]);
+        assert_eq!(graph.traverse(3), vec![3, 1]);
+    }
+
+    #[test]
+    fn bond_orders_from_components() {
+        use crate::ccd::{ChemicalComponent, ComponentBond};
+        let mut pdb = PDB::new();
+        // acetaldehyde
+        pdb.add_atom(atom(1, "C1", "ACD", 1, (0.0, 0.0, 0.0), "C"));
+        pdb.add_atom(atom(2, "C2", "ACD", 1, (1.5, 0.0, 0.0), "C"));
+        pdb.add_atom(atom(3, "O", "ACD", 1, (2.1, 1.05, 0.0), "O"));
+        let bond = |a: &str, b: &str, order| ComponentBond { a: a.to_owned(), b: b.to_owned(), order, aromatic: false };
+        let mut components = ComponentDictionary::new();
+        components.add(ChemicalComponent {
+            id: "ACD".to_owned(),
+            name: "ACETALDEHYDE".to_owned(),
+            kind: "NON-POLYMER".to_owned(),
+            parents: Vec::new(),
+            atoms: Vec::new(),
+            bonds: vec![bond("C1", "C2", BondOrder::Single), bond("C2", "O", BondOrder::Double)],
+        });
+
+        let graph = perceive_bonds_with_components(&pdb, &BondParameters::default(), &components);
+        let double = graph.bond(1, 2).unwrap();
+        assert_eq!((double.order, double.source), (BondOrder::Double, BondSource::Template));
+        assert_eq!(perceive_bonds(&pdb, &BondParameters::default()).bond(1, 2).unwrap().order, BondOrder::Single);
+    }
+}
@@ -0,0 +1,343 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use crate::structs::{PDB, Atom, Element, ResidueId};
+use crate::geometry::Vector3;
+use super::{Bond, BondOrder, BondSource, MolecularGraph};
+
+/// Maximum distance (Å) of a ring atom from the mean plane of a planar ring
+const MAX_RING_DEVIATION: f64 = 0.1;
+
+impl MolecularGraph {
+    /// The smallest set of smallest rings with at most `max_size` atoms, each in ring order,
+    /// smallest first. Larger rings, like the ones closed by disulfide bonds, are left out.
+    pub fn sssr(&self, max_size: usize) -> Vec<Vec<usize>> {
+        let core = self.ring_core();
+        let mut candidates = Vec::new();
+        let mut seen = HashSet::new();
+        for root in (0..self.atom_count()).filter(|i| core[*i]) {
+            // Horton's candidates: the shortest paths from the root to both ends of a bond
+            let mut parent = HashMap::new();
+            let mut depth = HashMap::from([(root, 0)]);
+            let mut visited = vec![root];
+            let mut queue = VecDeque::from([root]);
+            while let Some(atom) = queue.pop_front() {
+                if 2 * depth[&atom] + 1 > max_size {
+                    continue;
+                }
+                for next in self.neighbors(atom).iter().copied().filter(|n| core[*n]) {
+                    if !depth.contains_key(&next) {
+                        depth.insert(next, depth[&atom] + 1);
+                        parent.insert(next, atom);
+                        visited.push(next);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            let path = |mut atom: usize| {
+                let mut path = vec![atom];
+                while let Some(previous) = parent.get(&atom) {
+                    path.push(*previous);
+                    atom = *previous;
+                }
+                path
+            };
+            let bonds = visited.iter().flat_map(|u| self.neighbors(*u).iter().map(move |v| (*u, *v)));
+            for (u, v) in bonds.filter(|(u, v)| u < v) {
+                let (du, dv) = match (depth.get(&u), depth.get(&v)) {
+                    (Some(du), Some(dv)) => (*du, *dv),
+                    _ => continue,
+                };
+                if du + dv + 1 > max_size || parent.get(&u) == Some(&v) || parent.get(&v) == Some(&u) {
+                    continue;
+                }
+                let (to_u, to_v) = (path(u), path(v));
+                if to_u.iter().any(|a| *a != root && to_v.contains(a)) {
+                    continue;
+                }
+                let mut ring: Vec<usize> = to_u.into_iter().rev().collect();
+                ring.extend(to_v.into_iter().take(dv));
+                let mut bonds = self.ring_bonds(&ring);
+                bonds.sort_unstable();
+                if seen.insert(bonds.clone()) {
+                    candidates.push((ring, bonds));
+                }
+            }
+        }
+        candidates.sort_by(|(a, _), (b, _)| {
+            let (mut x, mut y) = (a.clone(), b.clone());
+            x.sort_unstable();
+            y.sort_unstable();
+            a.len().cmp(&b.len()).then(x.cmp(&y))
+        });
+
+        // the shortest candidates linearly independent over GF(2), as sets of bonds
+        let mut basis: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        let mut rings = Vec::new();
+        for (ring, mut bonds) in candidates {
+            while let Some(pivot) = bonds.last().copied() {
+                match basis.get(&pivot) {
+                    Some(row) => bonds = symmetric_difference(&bonds, row),
+                    None => break,
+                }
+            }
+            if let Some(pivot) = bonds.last().copied() {
+                basis.insert(pivot, bonds);
+                rings.push(ring);
+            }
+        }
+        rings
+    }
+
+    /// Whether each atom is left after repeatedly removing atoms with less than two bonds
+    fn ring_core(&self) -> Vec<bool> {
+        let mut degree: Vec<usize> = (0..self.atom_count()).map(|i| self.degree(i)).collect();
+        let mut core = vec![true; self.atom_count()];
+        let mut stack: Vec<usize> = (0..self.atom_count()).filter(|i| degree[*i] < 2).collect();
+        while let Some(atom) = stack.pop() {
+            if !core[atom] {
+                continue;
+            }
+            core[atom] = false;
+            for next in self.neighbors(atom).iter().copied().filter(|n| core[*n]) {
+                degree[next] -= 1;
+                if degree[next] < 2 {
+                    stack.push(next);
+                }
+            }
+        }
+        core
+    }
+
+    /// The indices of the bonds of a ring given in ring order
+    fn ring_bonds(&self, ring: &[usize]) -> Vec<usize> {
+        ring.iter()
+            .zip(ring.iter().cycle().skip(1))
+            .filter_map(|(a, b)| self.incident[*a].iter().copied().find(|i| self.bonds[*i].partner(*a) == Some(*b)))
+            .collect()
+    }
+}
+
+fn symmetric_difference(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let a: HashSet<usize> = a.iter().copied().collect();
+    let b: HashSet<usize> = b.iter().copied().collect();
+    let mut result: Vec<usize> = a.symmetric_difference(&b).copied().collect();
+    result.sort_unstable();
+    result
+}
+
+/// A ring of the molecular graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ring {
+    /// The atoms in ring order by their index in the `PDB`
+    pub atoms: Vec<usize>,
+    pub aromatic: bool,
+    pub center: Vector3,
+    /// The unit normal of the ring plane
+    pub normal: Vector3,
+    /// The largest distance (Å) of a ring atom from the ring plane
+    pub deviation: f64,
+}
+
+impl Ring {
+    /// `None` if the atoms are collinear
+    fn new(atoms: Vec<usize>, coords: &[Vector3]) -> Option<Ring> {
+        let points: Vec<Vector3> = atoms.iter().map(|i| coords[*i]).collect();
+        let center = points.iter().fold(Vector3::zero(), |sum, p| sum + *p) / points.len() as f64;
+        let normal = points
+            .iter()
+            .zip(points.iter().cycle().skip(1))
+            .fold(Vector3::zero(), |sum, (a, b)| sum + (*a - center).cross(&(*b - center)))
+            .normalize()?;
+        let deviation = points.iter().map(|p| (*p - center).dot(&normal).abs()).fold(0.0, f64::max);
+        Some(Ring { atoms, aromatic: false, center, normal, deviation })
+    }
+
+    pub fn len(&self) -> usize {
+        self.atoms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.atoms.is_empty()
+    }
+
+    pub fn contains(&self, atom: usize) -> bool {
+        self.atoms.contains(&atom)
+    }
+
+    /// The distance of `point` from the normal through the ring center
+    pub fn offset(&self, point: &Vector3) -> f64 {
+        let d = *point - self.center;
+        (d - self.normal * d.dot(&self.normal)).norm()
+    }
+}
+
+/// The rings of a structure with their aromaticity
+#[derive(Debug, Clone, PartialEq)]
+pub struct RingSet {
+    rings: Vec<Ring>,
+    residues: Vec<ResidueId>,
+}
+
+impl RingSet {
+    /// Finds the smallest set of smallest rings with at most `max_size` atoms.
+    /// A ring is aromatic if its bonds are aromatic or its Kekulé structure has 4n + 2
+    /// pi electrons. Rings bonded by distance alone have no bond orders, five and six
+    /// membered planar rings of carbon, nitrogen, oxygen and sulfur are aromatic then.
+    pub fn new(pdb: &PDB, graph: &MolecularGraph, max_size: usize) -> RingSet {
+        let atoms: Vec<&Atom> = pdb.atoms().collect();
+        let coords: Vec<Vector3> = atoms.iter().map(|a| a.coords()).collect();
+        let rings = graph
+            .sssr(max_size)
+            .into_iter()
+            .filter_map(|ring| Ring::new(ring, &coords))
+            .map(|mut ring| {
+                ring.aromatic = is_aromatic(&ring, graph, &atoms);
+                ring
+            })
+            .collect();
+        RingSet { rings, residues: atoms.iter().map(|a| a.residue_id()).collect() }
+    }
+
+    pub fn rings(&self) -> impl DoubleEndedIterator<Item = &Ring> + '_ {
+        self.rings.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rings.is_empty()
+    }
+
+    /// The rings with an atom in the residue
+    pub fn of_residue<'a>(&'a self, residue: &'a ResidueId) -> impl Iterator<Item = &'a Ring> + 'a {
+        self.rings.iter().filter(move |r| r.atoms.iter().any(|a| self.residues.get(*a) == Some(residue)))
+    }
+
+    /// The rings made of the given atoms only, such as the atoms of a ligand
+    pub fn of_atoms<'a>(&'a self, atoms: &'a [usize]) -> impl Iterator<Item = &'a Ring> + 'a {
+        self.rings.iter().filter(move |r| r.atoms.iter().all(|a| atoms.contains(a)))
+    }
+
+    /// The rings containing the atom
+    pub fn containing(&self, atom: usize) -> impl Iterator<Item = &Ring> + '_ {
+        self.rings.iter().filter(move |r| r.contains(atom))
+    }
+
+    pub fn in_ring(&self, atom: usize) -> bool {
+        self.containing(atom).next().is_some()
+    }
+
+    /// Whether the atom is part of an aromatic ring
+    pub fn is_aromatic(&self, atom: usize) -> bool {
+        self.containing(atom).any(|r| r.aromatic)
+    }
+}
+
+fn is_aromatic(ring: &Ring, graph: &MolecularGraph, atoms: &[&Atom]) -> bool {
+    let n = ring.len();
+    let ring_bonds: Vec<_> = (0..n).filter_map(|k| graph.bond(ring.atoms[k], ring.atoms[(k + 1) % n])).collect();
+    if ring_bonds.iter().all(|b| b.order == BondOrder::Aromatic) {
+        return true;
+    }
+    let element = |i: usize| atoms[i].inferred_element();
+    let ordered = ring_bonds.iter().all(|b| b.source == BondSource::Template)
+        || ring.atoms.iter().any(|a| graph.atom_bonds(*a).any(|b| b.order != BondOrder::Single));
+    if !ordered {
+        let conjugable = ring.atoms.iter().all(|a| {
+            matches!(element(*a), Some(Element::C) | Some(Element::N) | Some(Element::O) | Some(Element::S))
+        });
+        return (5..=6).contains(&n) && conjugable && ring.deviation <= MAX_RING_DEVIATION;
+    }
+
+    // Hückel's rule on the pi electrons each atom contributes
+    let mut electrons = 0;
+    for atom in ring.atoms.iter().copied() {
+        let in_ring = |b: &Bond| ring_bonds.iter().any(|r| r.a == b.a && r.b == b.b);
+        let double_in_ring = graph
+            .atom_bonds(atom)
+            .any(|b| in_ring(b) && matches!(b.order, BondOrder::Double | BondOrder::Aromatic));
+        let exocyclic = graph.atom_bonds(atom).find(|b| !in_ring(b) && b.order == BondOrder::Double);
+        electrons += match (double_in_ring, exocyclic, element(atom)) {
+            (true, _, _) => 1,
+            // a double bond to a fused ring counts, one to a carbonyl oxygen does not
+            (false, Some(bond), _) => match element(bond.partner(atom).unwrap()) {
+                Some(Element::C) => 1,
+                _ => 0,
+            },
+            (false, None, Some(Element::N)) | (false, None, Some(Element::O)) | (false, None, Some(Element::S))
+            | (false, None, Some(Element::Se)) => 2,
+            (false, None, Some(Element::C)) if *atoms[atom].charge() < 0 => 2,
+            (false, None, Some(Element::C)) if *atoms[atom].charge() > 0 => 0,
+            _ => return false,
+        };
+    }
+    electrons % 4 == 2
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bonds::{perceive_bonds, BondParameters};
+
+    #[test]
+    fn naphthalene_and_cyclohexane() {
+        let mut pdb = PDB::new();
+        let mut add = |name: &str, res_name: &str, res_seq, x: f64, y: f64, z: f64| {
+            let serial = pdb.atom_count() + 1;
+            pdb.add_atom(Atom::new(true, serial, name, res_name, "A", res_seq, x, y, z, 1.0, 0.0, "C", 0).unwrap());
+        };
+        // two fused hexagons sharing the bond along the y axis
+        let (a, h) = (1.4, 1.4 * 3f64.sqrt() / 2.0);
+        for (k, (x, y)) in [(0.0, a / 2.0), (h, a), (2.0 * h, a / 2.0), (2.0 * h, -a / 2.0), (h, -a), (0.0, -a / 2.0)]
+            .iter()
+            .enumerate()
+        {
+            add(&format!("C{}", k + 1), "NAP", 1, *x, *y, 0.0);
+        }
+        for (k, (x, y)) in [(-h, a), (-2.0 * h, a / 2.0), (-2.0 * h, -a / 2.0), (-h, -a)].iter().enumerate() {
+            add(&format!("C{}", k + 7), "NAP", 1, *x, *y, 0.0);
+        }
+        // chair cyclohexane
+        for k in 0..6 {
+            let phi = (60.0 * k as f64).to_radians();
+            add(&format!("C{}", k + 1), "CHX", 2, 1.45 * phi.cos() + 20.0, 1.45 * phi.sin(), if k % 2 == 0 { 0.25 } else { -0.25 });
+        }
+
+        let graph = perceive_bonds(&pdb, &BondParameters::default());
+        let rings = RingSet::new(&pdb, &graph, 8);
+        assert_eq!(rings.len(), 3);
+        assert!(rings.rings().all(|r| r.len() == 6));
+        let naphthalene = ResidueId::new("A", 1, None);
+        assert_eq!(rings.of_residue(&naphthalene).count(), 2);
+        assert!(rings.of_residue(&naphthalene).all(|r| r.aromatic && r.normal.z.abs() > 0.999));
+        assert!((0..10).all(|i| rings.is_aromatic(i)));
+        assert_eq!(rings.containing(0).count(), 2);
+
+        let cyclohexane = rings.of_atoms(&[10, 11, 12, 13, 14, 15]).next().unwrap();
+        assert!(!cyclohexane.aromatic);
+        assert!((cyclohexane.center.x - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn kekule_structures() {
+        let pdb = crate::testing::peptide(&[("PRO", -60.0, -45.0)]);
+        let graph = perceive_bonds(&pdb, &BondParameters::default());
+        // the peptide builder has no CG and CD, so proline has no ring
+        assert!(graph.sssr(8).is_empty());
+
+        let mut pdb = PDB::new();
+        // histidine side chain from CG, a regular pentagon
+        for (k, name) in ["CG", "ND1", "CE1", "NE2", "CD2"].iter().enumerate() {
+            let phi = (72.0 * k as f64).to_radians();
+            let element = &name[..1];
+            pdb.add_atom(Atom::new(false, k + 1, name, "HIS", "A", 1, 1.2 * phi.cos(), 1.2 * phi.sin(), 0.0, 1.0, 0.0, element, 0).unwrap());
+        }
+        let graph = perceive_bonds(&pdb, &BondParameters::default());
+        let rings = RingSet::new(&pdb, &graph, 8);
+        assert_eq!(rings.len(), 1);
+        assert!(rings.rings().next().unwrap().aromatic);
+    }
+}
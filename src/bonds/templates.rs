@@ -0,0 +1,125 @@
+use super::BondOrder::{self, Single, Double};
+
+/// A bond between two atoms of a residue template, named as in the PDB
+type TemplateBond = (&'static str, &'static str, BondOrder);
+
+const AMINO_ACID_BACKBONE: &[TemplateBond] = &[
+    ("N", "CA", Single), ("CA", "C", Single), ("C", "O", Double), ("C", "OXT", Single),
+];
+
+const NUCLEOTIDE_BACKBONE: &[TemplateBond] = &[
+    ("OP3", "P", Single), ("P", "OP1", Double), ("P", "OP2", Single), ("P", "O5'", Single),
+    ("O5'", "C5'", Single), ("C5'", "C4'", Single), ("C4'", "O4'", Single), ("C4'", "C3'", Single),
+    ("C3'", "O3'", Single), ("C3'", "C2'", Single), ("C2'", "C1'", Single), ("C1'", "O4'", Single),
+];
+
+const ADENINE: &[TemplateBond] = &[
+    ("C1'", "N9", Single), ("N9", "C8", Single), ("C8", "N7", Double), ("N7", "C5", Single),
+    ("C5", "C6", Single), ("C6", "N6", Single), ("C6", "N1", Double), ("N1", "C2", Single),
+    ("C2", "N3", Double), ("N3", "C4", Single), ("C4", "C5", Double), ("C4", "N9", Single),
+];
+
+const GUANINE: &[TemplateBond] = &[
+    ("C1'", "N9", Single), ("N9", "C8", Single), ("C8", "N7", Double), ("N7", "C5", Single),
+    ("C5", "C6", Single), ("C6", "O6", Double), ("C6", "N1", Single), ("N1", "C2", Single),
+    ("C2", "N2", Single), ("C2", "N3", Double), ("N3", "C4", Single), ("C4", "C5", Double),
+    ("C4", "N9", Single),
+];
+
+const CYTOSINE: &[TemplateBond] = &[
+    ("C1'", "N1", Single), ("N1", "C2", Single), ("C2", "O2", Double), ("C2", "N3", Single),
+    ("N3", "C4", Double), ("C4", "N4", Single), ("C4", "C5", Single), ("C5", "C6", Double),
+    ("C6", "N1", Single),
+];
+
+const URACIL: &[TemplateBond] = &[
+    ("C1'", "N1", Single), ("N1", "C2", Single), ("C2", "O2", Double), ("C2", "N3", Single),
+    ("N3", "C4", Single), ("C4", "O4", Double), ("C4", "C5", Single), ("C5", "C6", Double),
+    ("C6", "N1", Single),
+];
+
+/// The bonds between the heavy atoms of a standard amino acid or nucleotide,
+/// aromatic rings in the Kekulé form of the Chemical Component Dictionary.
+/// `None` if there is no template for the residue.
+pub(super) fn residue_template(res_name: &str) -> Option<Vec<TemplateBond>> {
+    let side_chain: &[TemplateBond] = match res_name {
+        "GLY" => &[],
+        "ALA" => &[("CA", "CB", Single)],
+        "ARG" => &[
+            ("CA", "CB", Single), ("CB", "CG", Single), ("CG", "CD", Single), ("CD", "NE", Single),
+            ("NE", "CZ", Single), ("CZ", "NH1", Double), ("CZ", "NH2", Single),
+        ],
+        "ASN" => &[("CA", "CB", Single), ("CB", "CG", Single), ("CG", "OD1", Double), ("CG", "ND2", Single)],
+        "ASP" => &[("CA", "CB", Single), ("CB", "CG", Single), ("CG", "OD1", Double), ("CG", "OD2", Single)],
+        "CYS" => &[("CA", "CB", Single), ("CB", "SG", Single)],
+        "GLN" => &[
+            ("CA", "CB", Single), ("CB", "CG", Single), ("CG", "CD", Single), ("CD", "OE1", Double), ("CD", "NE2", Single),
+        ],
+        "GLU" => &[
+            ("CA", "CB", Single), ("CB", "CG", Single), ("CG", "CD", Single), ("CD", "OE1", Double), ("CD", "OE2", Single),
+        ],
+        "HIS" => &[
+            ("CA", "CB", Single), ("CB", "CG", Single), ("CG", "ND1", Single), ("CG", "CD2", Double),
+            ("ND1", "CE1", Double), ("CE1", "NE2", Single), ("NE2", "CD2", Single),
+        ],
+        "ILE" => &[("CA", "CB", Single), ("CB", "CG1", Single), ("CB", "CG2", Single), ("CG1", "CD1", Single)],
+        "LEU" => &[("CA", "CB", Single), ("CB", "CG", Single), ("CG", "CD1", Single), ("CG", "CD2", Single)],
+        "LYS" => &[("CA", "CB", Single), ("CB", "CG", Single), ("CG", "CD", Single), ("CD", "CE", Single), ("CE", "NZ", Single)],
+        "MET" => &[("CA", "CB", Single), ("CB", "CG", Single), ("CG", "SD", Single), ("SD", "CE", Single)],
+        "MSE" => &[("CA", "CB", Single), ("CB", "CG", Single), ("CG", "SE", Single), ("SE", "CE", Single)],
+        "PHE" => &[
+            ("CA", "CB", Single), ("CB", "CG", Single), ("CG", "CD1", Double), ("CG", "CD2", Single),
+            ("CD1", "CE1", Single), ("CD2", "CE2", Double), ("CE1", "CZ", Double), ("CE2", "CZ", Single),
+        ],
+        "PRO" => &[("CA", "CB", Single), ("CB", "CG", Single), ("CG", "CD", Single), ("CD", "N", Single)],
+        "SER" => &[("CA", "CB", Single), ("CB", "OG", Single)],
+        "THR" => &[("CA", "CB", Single), ("CB", "OG1", Single), ("CB", "CG2", Single)],
+        "TRP" => &[
+            ("CA", "CB", Single), ("CB", "CG", Single), ("CG", "CD1", Double), ("CG", "CD2", Single),
+            ("CD1", "NE1", Single), ("NE1", "CE2", Single), ("CD2", "CE2", Double), ("CD2", "CE3", Single),
+            ("CE2", "CZ2", Single), ("CE3", "CZ3", Double), ("CZ2", "CH2", Double), ("CZ3", "CH2", Single),
+        ],
+        "TYR" => &[
+            ("CA", "CB", Single), ("CB", "CG", Single), ("CG", "CD1", Double), ("CG", "CD2", Single),
+            ("CD1", "CE1", Single), ("CD2", "CE2", Double), ("CE1", "CZ", Double), ("CE2", "CZ", Single),
+            ("CZ", "OH", Single),
+        ],
+        "VAL" => &[("CA", "CB", Single), ("CB", "CG1", Single), ("CB", "CG2", Single)],
+        _ => return nucleotide_template(res_name),
+    };
+    Some(AMINO_ACID_BACKBONE.iter().chain(side_chain.iter()).copied().collect())
+}
+
+fn nucleotide_template(res_name: &str) -> Option<Vec<TemplateBond>> {
+    let (base, ribose): (&[TemplateBond], bool) = match res_name {
+        "A" => (ADENINE, true),
+        "G" => (GUANINE, true),
+        "C" => (CYTOSINE, true),
+        "U" => (URACIL, true),
+        "DA" => (ADENINE, false),
+        "DG" => (GUANINE, false),
+        "DC" => (CYTOSINE, false),
+        "DT" => (URACIL, false),
+        _ => return None,
+    };
+    let mut bonds: Vec<TemplateBond> = NUCLEOTIDE_BACKBONE.iter().chain(base.iter()).copied().collect();
+    if ribose {
+        bonds.push(("C2'", "O2'", Single));
+    }
+    if res_name == "DT" {
+        bonds.push(("C5", "C7", Single));
+    }
+    Some(bonds)
+}
+
+/// Whether the residue is linked to the next one by a peptide bond (C-N)
+/// or a phosphodiester bond (O3'-P), `None` if it has no template
+pub(super) fn link_atoms(res_name: &str) -> Option<(&'static str, &'static str)> {
+    if nucleotide_template(res_name).is_some() {
+        Some(("O3'", "P"))
+    } else if residue_template(res_name).is_some() {
+        Some(("C", "N"))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,451 @@
+use anyhow::{anyhow, ensure, Result, Context as _};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufRead};
+
+use crate::structs::{PDB, Element, ResidueId};
+use crate::bonds::BondOrder;
+
+/// An atom of a chemical component
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentAtom {
+    pub name: String,
+    pub element: Option<Element>,
+    /// The formal charge
+    pub charge: isize,
+    pub aromatic: bool,
+    /// Whether the atom leaves when the component is linked, like OXT of the amino acids
+    pub leaving: bool,
+}
+
+/// A bond between two atoms of a chemical component, given by their names
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentBond {
+    pub a: String,
+    pub b: String,
+    /// The bond order, aromatic bonds in their Kekulé form
+    pub order: BondOrder,
+    pub aromatic: bool,
+}
+
+/// The reference description of a residue or ligand in the Chemical Component Dictionary
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChemicalComponent {
+    /// The three letter code used as residue name
+    pub id: String,
+    pub name: String,
+    /// The type, like "L-PEPTIDE LINKING", "RNA LINKING" or "NON-POLYMER"
+    pub kind: String,
+    /// The standard residues a modified residue derives from
+    pub parents: Vec<String>,
+    pub atoms: Vec<ComponentAtom>,
+    pub bonds: Vec<ComponentBond>,
+}
+
+impl ChemicalComponent {
+    pub fn atom(&self, name: &str) -> Option<&ComponentAtom> {
+        self.atoms.iter().find(|a| a.name == name)
+    }
+
+    /// The bond between two atoms, in either order
+    pub fn bond(&self, a: &str, b: &str) -> Option<&ComponentBond> {
+        self.bonds.iter().find(|bond| (bond.a == a && bond.b == b) || (bond.a == b && bond.b == a))
+    }
+
+    /// Whether the component is linked into a chain by peptide bonds
+    pub fn is_peptide_linking(&self) -> bool {
+        self.kind.contains("PEPTIDE LINKING")
+    }
+
+    /// Whether the component is linked into a chain by phosphodiester bonds
+    pub fn is_nucleotide_linking(&self) -> bool {
+        self.kind.contains("DNA LINKING") || self.kind.contains("RNA LINKING")
+    }
+
+    /// The heavy atoms expected in a linked residue that are not in `present`,
+    /// leaving atoms are never missing
+    pub fn missing_atoms<'a>(&self, present: impl IntoIterator<Item = &'a str>) -> Vec<&str> {
+        let present: Vec<&str> = present.into_iter().collect();
+        self.atoms
+            .iter()
+            .filter(|a| !a.leaving && !a.element.is_some_and(|e| e.is_hydrogen()))
+            .map(|a| a.name.as_str())
+            .filter(|name| !present.contains(name))
+            .collect()
+    }
+}
+
+/// A set of chemical components by their id, read from the wwPDB Chemical Component
+/// Dictionary (`components.cif`) or a subset of it
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ComponentDictionary {
+    components: HashMap<String, ChemicalComponent>,
+}
+
+impl ComponentDictionary {
+    pub fn new() -> ComponentDictionary {
+        ComponentDictionary::default()
+    }
+
+    /// Adds a component, replacing any component with the same id
+    pub fn add(&mut self, component: ChemicalComponent) {
+        self.components.insert(component.id.clone(), component);
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ChemicalComponent> {
+        self.components.get(id)
+    }
+
+    pub fn components(&self) -> impl Iterator<Item = &ChemicalComponent> + '_ {
+        self.components.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    /// The standard residue a modified residue derives from, the residue itself if it
+    /// has no parent and `None` if it is not in the dictionary
+    pub fn parent<'a>(&'a self, id: &'a str) -> Option<&'a str> {
+        self.get(id).map(|c| c.parents.first().map(|p| p.as_str()).unwrap_or(id))
+    }
+
+    /// The heavy atoms missing from every residue of the structure that is in the dictionary,
+    /// residues without missing atoms are left out
+    pub fn missing_atoms(&self, pdb: &PDB) -> Vec<(ResidueId, Vec<String>)> {
+        let mut result = Vec::new();
+        for (residue, indices) in pdb.residue_indices() {
+            let atoms: Vec<_> = indices.iter().filter_map(|i| pdb.atom(*i)).collect();
+            if let Some(component) = self.get(atoms[0].res_name()) {
+                let missing = component.missing_atoms(atoms.iter().map(|a| a.atom_name().as_str()));
+                if !missing.is_empty() {
+                    result.push((residue, missing.into_iter().map(String::from).collect()));
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Reads the chemical components of an mmCIF file such as `components.cif`
+pub fn read_ccd(filename: &str) -> Result<ComponentDictionary> {
+    let f = File::open(filename).with_context(|| format!("could not open {}", filename))?;
+    read_ccd_raw(BufReader::new(f))
+}
+
+pub fn read_ccd_raw<T>(input: BufReader<T>) -> Result<ComponentDictionary>
+    where T: std::io::Read
+{
+    let mut dictionary = ComponentDictionary::new();
+    let mut block = Block::default();
+    let mut text: Option<String> = None;
+    for (mut line_number, read_line) in input.lines().enumerate() {
+        line_number += 1;
+        let line = read_line.with_context(|| format!("could not read line {}", line_number))?;
+
+        // multi-line text fields are delimited by lines starting with a semicolon
+        if let Some(field) = text.as_mut() {
+            if line.starts_with(';') {
+                let value = text.take().unwrap();
+                block.value(value.trim_end().to_owned(), line_number)?;
+            } else {
+                field.push_str(&line);
+                field.push('\n');
+            }
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix(';') {
+            text = Some(format!("{}\n", rest));
+            continue;
+        }
+
+        for (token, quoted) in tokenize(&line, line_number)? {
+            if quoted {
+                block.value(token, line_number)?;
+            } else if let Some(id) = token.strip_prefix("data_") {
+                if let Some(component) = std::mem::take(&mut block).component(line_number)? {
+                    dictionary.add(component);
+                }
+                block.id = Some(id.to_owned());
+            } else if token == "loop_" {
+                block.end_loop(line_number)?;
+                block.in_loop = true;
+            } else if token.starts_with('_') {
+                block.key(token, line_number)?;
+            } else {
+                block.value(token, line_number)?;
+            }
+        }
+    }
+    ensure!(text.is_none(), "unterminated text field at the end of the file");
+    if let Some(component) = block.component(0)? {
+        dictionary.add(component);
+    }
+    Ok(dictionary)
+}
+
+/// The rows of the categories of one data block, each row mapping field names to values
+#[derive(Debug, Default)]
+struct Block {
+    id: Option<String>,
+    categories: HashMap<String, Vec<HashMap<String, String>>>,
+    in_loop: bool,
+    headers: Vec<String>,
+    values: Vec<String>,
+    pending_key: Option<String>,
+}
+
+impl Block {
+    fn key(&mut self, key: String, line_number: usize) -> Result<()> {
+        if self.in_loop && self.values.is_empty() {
+            self.headers.push(key);
+            return Ok(());
+        }
+        self.end_loop(line_number)?;
+        ensure!(self.pending_key.is_none(), "item without value at line {}", line_number);
+        self.pending_key = Some(key);
+        Ok(())
+    }
+
+    fn value(&mut self, value: String, line_number: usize) -> Result<()> {
+        if self.in_loop {
+            ensure!(!self.headers.is_empty(), "loop without fields at line {}", line_number);
+            self.values.push(value);
+            return Ok(());
+        }
+        let key = self.pending_key.take().ok_or_else(|| anyhow!("value without item at line {}", line_number))?;
+        let (category, field) = split_key(&key, line_number)?;
+        let rows = self.categories.entry(category).or_default();
+        if rows.is_empty() {
+            rows.push(HashMap::new());
+        }
+        rows[0].insert(field, value);
+        Ok(())
+    }
+
+    fn end_loop(&mut self, line_number: usize) -> Result<()> {
+        if !self.in_loop {
+            return Ok(());
+        }
+        self.in_loop = false;
+        let headers = std::mem::take(&mut self.headers);
+        let values = std::mem::take(&mut self.values);
+        if headers.is_empty() {
+            return Ok(());
+        }
+        ensure!(values.len() % headers.len() == 0, "loop ending at line {} has an incomplete row", line_number);
+        let fields = headers
+            .iter()
+            .map(|h| split_key(h, line_number))
+            .collect::<Result<Vec<(String, String)>>>()?;
+        for row in values.chunks(headers.len()) {
+            let mut map = HashMap::new();
+            for ((category, field), value) in fields.iter().zip(row) {
+                ensure!(*category == fields[0].0, "loop of mixed categories at line {}", line_number);
+                map.insert(field.clone(), value.clone());
+            }
+            self.categories.entry(fields[0].0.clone()).or_default().push(map);
+        }
+        Ok(())
+    }
+
+    /// The component described by the block, `None` for a block without `_chem_comp`
+    fn component(mut self, line_number: usize) -> Result<Option<ChemicalComponent>> {
+        self.end_loop(line_number)?;
+        let info = match self.categories.get("chem_comp").and_then(|rows| rows.first()) {
+            Some(info) => info,
+            None => return Ok(None),
+        };
+        let id = get(info, "id").or(self.id.as_deref()).unwrap_or("").to_ascii_uppercase();
+
+        let mut atoms = Vec::new();
+        for row in self.categories.get("chem_comp_atom").into_iter().flatten() {
+            let name = get(row, "atom_id").ok_or_else(|| anyhow!("atom without name in component {}", id))?;
+            let charge = match get(row, "charge") {
+                Some(charge) => charge.parse::<isize>().with_context(|| format!("invalid charge of atom {} in component {}", name, id))?,
+                None => 0,
+            };
+            atoms.push(ComponentAtom {
+                name: name.to_owned(),
+                element: get(row, "type_symbol").and_then(Element::from_symbol),
+                charge,
+                aromatic: flag(row, "pdbx_aromatic_flag"),
+                leaving: flag(row, "pdbx_leaving_atom_flag"),
+            });
+        }
+
+        let mut bonds = Vec::new();
+        for row in self.categories.get("chem_comp_bond").into_iter().flatten() {
+            let (a, b) = match (get(row, "atom_id_1"), get(row, "atom_id_2")) {
+                (Some(a), Some(b)) => (a, b),
+                _ => return Err(anyhow!("bond without atoms in component {}", id)),
+            };
+            let order = match get(row, "value_order").map(|o| o.to_ascii_uppercase()).as_deref() {
+                Some("SING") | None => BondOrder::Single,
+                Some("DOUB") => BondOrder::Double,
+                Some("TRIP") => BondOrder::Triple,
+                Some("AROM") => BondOrder::Aromatic,
+                Some(other) => return Err(anyhow!("unknown bond order {} in component {}", other, id)),
+            };
+            bonds.push(ComponentBond { a: a.to_owned(), b: b.to_owned(), order, aromatic: flag(row, "pdbx_aromatic_flag") });
+        }
+
+        Ok(Some(ChemicalComponent {
+            name: get(info, "name").unwrap_or("").to_owned(),
+            kind: get(info, "type").unwrap_or("").to_ascii_uppercase(),
+            parents: get(info, "mon_nstd_parent_comp_id")
+                .map(|p| p.split(',').map(|p| p.trim().to_ascii_uppercase()).filter(|p| !p.is_empty()).collect())
+                .unwrap_or_default(),
+            id,
+            atoms,
+            bonds,
+        }))
+    }
+}
+
+/// The value of a field, `None` if it is missing or unknown ('?' or '.')
+fn get<'a>(row: &'a HashMap<String, String>, field: &str) -> Option<&'a str> {
+    row.get(field).map(|v| v.as_str()).filter(|v| *v != "?" && *v != ".")
+}
+
+fn flag(row: &HashMap<String, String>, field: &str) -> bool {
+    get(row, field) == Some("Y")
+}
+
+/// Splits `_category.field` into its parts
+fn split_key(key: &str, line_number: usize) -> Result<(String, String)> {
+    let (category, field) = key[1..]
+        .split_once('.')
+        .ok_or_else(|| anyhow!("item name without category at line {}: {}", line_number, key))?;
+    Ok((category.to_ascii_lowercase(), field.to_ascii_lowercase()))
+}
+
+/// The whitespace separated tokens of a line and whether they were quoted.
+/// A quoted token only ends at a matching quote followed by whitespace.
+fn tokenize(line: &str, line_number: usize) -> Result<Vec<(String, bool)>> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i].is_whitespace() {
+            i += 1;
+        } else if chars[i] == '#' {
+            break;
+        } else if chars[i] == '\'' || chars[i] == '"' {
+            let quote = chars[i];
+            let end = (i + 1..chars.len())
+                .find(|j| chars[*j] == quote && chars.get(j + 1).map(|c| c.is_whitespace()).unwrap_or(true))
+                .ok_or_else(|| anyhow!("unterminated quote at line {}", line_number))?;
+            tokens.push((chars[i + 1..end].iter().collect(), true));
+            i = end + 1;
+        } else {
+            let end = (i..chars.len()).find(|j| chars[*j].is_whitespace()).unwrap_or(chars.len());
+            tokens.push((chars[i..end].iter().collect(), false));
+            i = end;
+        }
+    }
+    Ok(tokens)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::Atom;
+
+    const COMPONENTS: &str = "\
+data_MSE
+#
+_chem_comp.id                                    MSE
+_chem_comp.name                                  SELENOMETHIONINE
+_chem_comp.type                                  'L-PEPTIDE LINKING'
+_chem_comp.mon_nstd_parent_comp_id               MET
+#
+loop_
+_chem_comp_atom.comp_id
+_chem_comp_atom.atom_id
+_chem_comp_atom.type_symbol
+_chem_comp_atom.charge
+_chem_comp_atom.pdbx_aromatic_flag
+_chem_comp_atom.pdbx_leaving_atom_flag
+MSE N   N  0 N N
+MSE CA  C  0 N N
+MSE C   C  0 N N
+MSE O   O  0 N N
+MSE OXT O  0 N Y
+MSE CB  C  0 N N
+MSE CG  C  0 N N
+MSE SE  SE 0 N N
+MSE CE  C  0 N N
+#
+loop_
+_chem_comp_bond.comp_id
+_chem_comp_bond.atom_id_1
+_chem_comp_bond.atom_id_2
+_chem_comp_bond.value_order
+_chem_comp_bond.pdbx_aromatic_flag
+MSE N  CA  SING N
+MSE CA C   SING N
+MSE C  O   DOUB N
+MSE C  OXT SING N
+MSE CA CB  SING N
+MSE CB CG  SING N
+MSE CG SE  SING N
+MSE SE CE  SING N
+#
+data_NA
+_chem_comp.id NA
+_chem_comp.name
+;SODIUM ION
+;
+_chem_comp.type NON-POLYMER
+_chem_comp.mon_nstd_parent_comp_id ?
+_chem_comp_atom.comp_id NA
+_chem_comp_atom.atom_id NA
+_chem_comp_atom.type_symbol NA
+_chem_comp_atom.charge 1
+_chem_comp_atom.pdbx_aromatic_flag N
+_chem_comp_atom.pdbx_leaving_atom_flag N
+";
+
+    #[test]
+    fn can_read_components() {
+        let dictionary = read_ccd_raw(BufReader::new(COMPONENTS.as_bytes())).unwrap();
+        assert_eq!(dictionary.len(), 2);
+
+        let mse = dictionary.get("MSE").unwrap();
+        assert_eq!(mse.kind, "L-PEPTIDE LINKING");
+        assert!(mse.is_peptide_linking());
+        assert_eq!(dictionary.parent("MSE"), Some("MET"));
+        assert_eq!(mse.atom("SE").unwrap().element, Some(Element::Se));
+        assert_eq!(mse.bond("O", "C").unwrap().order, BondOrder::Double);
+        assert_eq!(mse.missing_atoms(vec!["N", "CA", "C", "O", "CB", "CG"]), vec!["SE", "CE"]);
+
+        let sodium = dictionary.get("NA").unwrap();
+        assert_eq!(sodium.name, "SODIUM ION");
+        assert!(sodium.parents.is_empty());
+        assert_eq!(dictionary.parent("NA"), Some("NA"));
+        assert_eq!(sodium.atoms[0].charge, 1);
+
+        let mut pdb = PDB::new();
+        pdb.add_atom(Atom::new(true, 1, "N", "MSE", "A", 1, 0.0, 0.0, 0.0, 1.0, 0.0, "N", 0).unwrap());
+        let missing = dictionary.missing_atoms(&pdb);
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].1, vec!["CA", "C", "O", "CB", "CG", "SE", "CE"]);
+    }
+
+    #[test]
+    fn can_tokenize_quotes() {
+        let tokens = tokenize(r#"ATP "C5'" 'it''s' O3' # comment"#, 1).unwrap();
+        assert_eq!(tokens, vec![
+            ("ATP".to_owned(), false),
+            ("C5'".to_owned(), true),
+            ("it''s".to_owned(), true),
+            ("O3'".to_owned(), false),
+        ]);
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use crate::structs::{PDB, Atom, Element};
+use crate::spatial::SpatialIndex;
+use crate::bonds::{perceive, BondParameters};
+use crate::hbond::{is_donor, is_acceptor};
+
+/// Parameters of the clash check
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClashParameters {
+    /// The van der Waals radius (Å) by element
+    pub radii: HashMap<Element, f64>,
+    /// The radius (Å) of atoms whose element is unknown or not in `radii`
+    pub default_radius: f64,
+    /// Minimum overlap (Å) of two van der Waals spheres to count as a clash,
+    /// MolProbity reports serious clashes from 0.4 Å
+    pub min_overlap: f64,
+    /// Whether hydrogens are checked, MolProbity expects structures with hydrogens
+    pub include_hydrogens: bool,
+    /// Whether hydrogen bonded pairs (donor-acceptor and polar hydrogen-acceptor)
+    /// are exempt from the check
+    pub allow_hydrogen_bonds: bool,
+}
+
+impl Default for ClashParameters {
+    fn default() -> Self {
+        ClashParameters {
+            radii: Element::all().map(|e| (e, e.vdw_radius())).collect(),
+            default_radius: 1.7,
+            min_overlap: 0.4,
+            include_hydrogens: true,
+            allow_hydrogen_bonds: true,
+        }
+    }
+}
+
+impl ClashParameters {
+    fn radius(&self, atom: &Atom) -> f64 {
+        atom.inferred_element().and_then(|e| self.radii.get(&e)).copied().unwrap_or(self.default_radius)
+    }
+}
+
+/// Two atoms overlapping, given by their index in the `PDB` with `a < b`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clash {
+    pub a: usize,
+    pub b: usize,
+    /// The distance (Å) between the atoms
+    pub distance: f64,
+    /// The overlap (Å) of the van der Waals spheres
+    pub overlap: f64,
+}
+
+/// The result of a clash check
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClashReport {
+    /// The clashes, largest overlap first
+    pub clashes: Vec<Clash>,
+    /// The number of atoms checked
+    pub atoms: usize,
+    /// The number of clashes per 1000 atoms
+    pub clashscore: f64,
+}
+
+/// Finds the pairs of atoms whose van der Waals spheres overlap by more than the threshold.
+/// Bonded (1-2) and angle (1-3) pairs are skipped, bonds are perceived with the default
+/// `BondParameters`.
+/// Metal coordination is never a clash, and neither are atoms of different alternate locations.
+pub fn clashes(pdb: &PDB, parameters: &ClashParameters) -> ClashReport {
+    let atoms: Vec<&Atom> = pdb.atoms().collect();
+    let included: Vec<bool> = atoms.iter().map(|a| parameters.include_hydrogens || !a.is_hydrogen()).collect();
+    let radii: Vec<f64> = atoms.iter().map(|a| parameters.radius(a)).collect();
+    let max_radius = radii.iter().cloned().fold(0.0, f64::max);
+    let index = SpatialIndex::new(pdb);
+    let graph = perceive(pdb, &index, &BondParameters::default(), None);
+    let bonded = |i: usize| graph.neighbors(i);
+
+    let close = |i: usize, j: usize| bonded(i).iter().any(|k| *k == j || bonded(*k).contains(&j));
+    let coordinating = |a: &Atom| matches!(a.inferred_element(), Some(Element::N) | Some(Element::O) | Some(Element::S));
+    let is_metal = |a: &Atom| a.inferred_element().is_some_and(|e| e.is_metal());
+    let metal_bond = |a: &Atom, b: &Atom| (is_metal(a) && coordinating(b)) || (coordinating(a) && is_metal(b));
+    let polar_hydrogen = |i: usize| atoms[i].is_hydrogen() && bonded(i).iter().any(|d| is_donor(atoms[*d]));
+    let hydrogen_bond = |i: usize, j: usize| {
+        let donor = |k: usize| is_donor(atoms[k]) || polar_hydrogen(k);
+        (donor(i) && is_acceptor(atoms[j])) || (is_acceptor(atoms[i]) && donor(j))
+    };
+    let alternates = |a: &Atom, b: &Atom| matches!((a.alt_location(), b.alt_location()), (Some(x), Some(y)) if x != y);
+
+    let mut clashes: Vec<Clash> = index
+        .pairs_within(2.0 * max_radius - parameters.min_overlap)
+        .into_iter()
+        .filter(|(i, j, _)| included[*i] && included[*j])
+        .filter_map(|(i, j, distance)| {
+            let overlap = radii[i] + radii[j] - distance;
+            let exempt = close(i, j)
+                || metal_bond(atoms[i], atoms[j])
+                || alternates(atoms[i], atoms[j])
+                || (parameters.allow_hydrogen_bonds && hydrogen_bond(i, j));
+            (overlap >= parameters.min_overlap && !exempt).then_some(Clash { a: i, b: j, distance, overlap })
+        })
+        .collect();
+    clashes.sort_by(|x, y| y.overlap.total_cmp(&x.overlap).then(x.a.cmp(&y.a)).then(x.b.cmp(&y.b)));
+
+    let checked = included.iter().filter(|i| **i).count();
+    ClashReport {
+        clashscore: if checked == 0 { 0.0 } else { 1000.0 * clashes.len() as f64 / checked as f64 },
+        atoms: checked,
+        clashes,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::atom;
+
+    #[test]
+    fn overlaps_without_bonded_pairs() {
+        let mut pdb = PDB::new();
+        // a bond angle of 110 degrees puts the 1-3 pair 2.46 Å apart
+        pdb.add_atom(atom(1, "C1", "UNK", 1, (0.0, 0.0, 0.0), "C"));
+        pdb.add_atom(atom(2, "C2", "UNK", 1, (1.5, 0.0, 0.0), "C"));
+        pdb.add_atom(atom(3, "C3", "UNK", 1, (2.013, 1.41, 0.0), "C"));
+        pdb.add_atom(atom(4, "C1", "UNK", 2, (0.0, -2.5, 0.0), "C"));
+        pdb.add_atom(atom(5, "N", "ALA", 3, (20.0, 0.0, 0.0), "N"));
+        pdb.add_atom(atom(6, "O", "GLY", 4, (22.6, 0.0, 0.0), "O"));
+
+        let report = clashes(&pdb, &ClashParameters::default());
+        let pairs: Vec<(usize, usize)> = report.clashes.iter().map(|c| (c.a, c.b)).collect();
+        assert_eq!(pairs, vec![(0, 3), (1, 3)]);
+        assert!((report.clashes[0].overlap - 0.9).abs() < 1e-9);
+        assert!((report.clashscore - 2000.0 / 6.0).abs() < 1e-9);
+
+        let strict = ClashParameters { allow_hydrogen_bonds: false, ..ClashParameters::default() };
+        assert_eq!(clashes(&pdb, &strict).clashes.len(), 3);
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::structs::{PDB, Atom, ResidueId};
+use crate::geometry::Vector3;
+use crate::transform::Transform;
+use crate::spatial::SpatialIndex;
+use crate::superpose::{kabsch, pair_atoms, rmsd, AtomPairing};
+use crate::error::PDBError;
+
+/// Inclusion radius (Å) of the lDDT reference distances
+const LDDT_RADIUS: f64 = 15.0;
+/// Distance difference thresholds (Å) of lDDT
+const LDDT_THRESHOLDS: [f64; 4] = [0.5, 1.0, 2.0, 4.0];
+const GDT_TS_CUTOFFS: [f64; 4] = [1.0, 2.0, 4.0, 8.0];
+const GDT_HA_CUTOFFS: [f64; 4] = [0.5, 1.0, 2.0, 4.0];
+
+/// The TM-score of a model against a reference, normalized by the reference length
+#[derive(Debug, Clone, PartialEq)]
+pub struct TmScore {
+    pub score: f64,
+    /// The superposition of the model onto the reference giving `score`
+    pub transform: Transform,
+    /// The distance scale d0 (Å) for the reference length
+    pub d0: f64,
+    /// The number of CA atoms closer than 5 Å after superposition
+    pub aligned: usize,
+    /// The RMSD (Å) of the aligned CA atoms
+    pub rmsd: f64,
+}
+
+/// The global distance test scores of a model against a reference (0 - 100)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gdt {
+    pub gdt_ts: f64,
+    pub gdt_ha: f64,
+}
+
+/// The local distance difference test of a model against a reference (0 - 1)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lddt {
+    pub score: f64,
+    pub per_residue: Vec<(ResidueId, f64)>,
+}
+
+/// Computes the TM-score of the CA atoms of `model` against `reference`.
+/// Residues are matched by chain id, residue sequence number and insertion code.
+pub fn tm_score(model: &PDB, reference: &PDB) -> Result<TmScore, PDBError> {
+    let (mobile, target, length) = ca_coordinates(model, reference)?;
+    let d0 = if length > 21 {
+        (1.24 * (length as f64 - 15.0).cbrt() - 1.8).max(0.5)
+    } else {
+        0.5
+    };
+    let d0_search = d0.clamp(4.5, 8.0);
+    let (score, transform) = max_superposition(&mobile, &target, d0_search, |distances| {
+        distances.iter().map(|d| 1.0 / (1.0 + (d / d0).powi(2))).sum::<f64>() / length as f64
+    });
+
+    let (aligned_mobile, aligned_target): (Vec<Vector3>, Vec<Vector3>) = mobile
+        .iter()
+        .map(|p| transform.apply(p))
+        .zip(target.iter().copied())
+        .filter(|(p, q)| p.distance(q) < 5.0)
+        .unzip();
+    Ok(TmScore {
+        score,
+        transform,
+        d0,
+        aligned: aligned_mobile.len(),
+        rmsd: rmsd(&aligned_mobile, &aligned_target).unwrap_or(0.0),
+    })
+}
+
+/// Computes GDT-TS and GDT-HA of the CA atoms of `model` against `reference`.
+/// Residues are matched by chain id, residue sequence number and insertion code.
+pub fn gdt(model: &PDB, reference: &PDB) -> Result<Gdt, PDBError> {
+    let (mobile, target, length) = ca_coordinates(model, reference)?;
+    let mut fractions = HashMap::new();
+    for cutoff in GDT_TS_CUTOFFS.iter().chain(GDT_HA_CUTOFFS.iter()) {
+        fractions.entry(cutoff.to_bits()).or_insert_with(|| {
+            max_superposition(&mobile, &target, *cutoff, |distances| {
+                distances.iter().filter(|d| **d <= *cutoff).count() as f64 / length as f64
+            }).0
+        });
+    }
+    let average = |cutoffs: &[f64]| {
+        100.0 * cutoffs.iter().map(|c| fractions[&c.to_bits()]).sum::<f64>() / cutoffs.len() as f64
+    };
+    Ok(Gdt {
+        gdt_ts: average(&GDT_TS_CUTOFFS),
+        gdt_ha: average(&GDT_HA_CUTOFFS),
+    })
+}
+
+/// Computes the all-atom lDDT of `model` against `reference`, ignoring hydrogens.
+/// Atoms are matched by chain id, residue sequence number, insertion code and atom name.
+/// If `write_temp_factor` is `true` the per-residue score is stored as temp factor of the model atoms.
+pub fn lddt(model: &mut PDB, reference: &PDB, write_temp_factor: bool) -> Result<Lddt, PDBError> {
+    let heavy = |a: &Atom| !a.is_hydrogen();
+    let pairs: HashMap<usize, usize> = pair_atoms(model, reference, heavy, &AtomPairing::ResidueAtom)
+        .into_iter()
+        .map(|(m, r)| (r, m))
+        .collect();
+    let mut counts: Vec<(ResidueId, usize, usize)> = Vec::new();
+    let mut residue_of: HashMap<ResidueId, usize> = HashMap::new();
+    let reference_atoms: Vec<(usize, &Atom, usize)> = reference
+        .atoms()
+        .enumerate()
+        .filter(|(_, a)| heavy(a))
+        .map(|(i, a)| {
+            let id = a.residue_id();
+            let slot = *residue_of.entry(id.clone()).or_insert_with(|| {
+                counts.push((id, 0, 0));
+                counts.len() - 1
+            });
+            (i, a, slot)
+        })
+        .collect();
+
+    let index = SpatialIndex::from_points(reference_atoms.iter().map(|(_, a, _)| a.coords()).collect());
+    for (i, atom_i, slot) in reference_atoms.iter() {
+        let slot = *slot;
+        for neighbor in index.within_atom(atom_i, LDDT_RADIUS) {
+            let (j, atom_j, other) = &reference_atoms[neighbor];
+            if i == j || *other == slot {
+                continue;
+            }
+            let d_ref = atom_i.distance(atom_j);
+            if d_ref >= LDDT_RADIUS {
+                continue;
+            }
+            counts[slot].2 += LDDT_THRESHOLDS.len();
+            if let (Some(m_i), Some(m_j)) = (pairs.get(i), pairs.get(j)) {
+                let d_model = model.atom(*m_i).unwrap().distance(model.atom(*m_j).unwrap());
+                counts[slot].1 += LDDT_THRESHOLDS.iter().filter(|t| (d_model - d_ref).abs() < **t).count();
+            }
+        }
+    }
+    if counts.iter().all(|c| c.2 == 0) {
+        return Err(PDBError::InvalidValue(
+            "the reference has no atom pairs within the lDDT inclusion radius".to_owned()
+        ))
+    }
+
+    let preserved: usize = counts.iter().map(|c| c.1).sum();
+    let total: usize = counts.iter().map(|c| c.2).sum();
+    let per_residue: Vec<(ResidueId, f64)> = counts
+        .into_iter()
+        .filter(|c| c.2 > 0)
+        .map(|(id, preserved, total)| (id, preserved as f64 / total as f64))
+        .collect();
+
+    if write_temp_factor {
+        let scores: HashMap<&ResidueId, f64> = per_residue.iter().map(|(id, score)| (id, *score)).collect();
+        for atom in model.atoms_mut() {
+            if let Some(score) = scores.get(&atom.residue_id()) {
+                atom.set_temp_factor(*score)?;
+            }
+        }
+    }
+    Ok(Lddt {
+        score: preserved as f64 / total as f64,
+        per_residue,
+    })
+}
+
+/// The paired CA coordinates of model and reference, and the number of reference residues.
+fn ca_coordinates(model: &PDB, reference: &PDB) -> Result<(Vec<Vector3>, Vec<Vector3>, usize), PDBError> {
+    let is_ca = |a: &Atom| a.atom_name() == "CA" && !*a.hetero();
+    let mut seen = HashSet::new();
+    let pairs: Vec<(usize, usize)> = pair_atoms(model, reference, is_ca, &AtomPairing::ResidueAtom)
+        .into_iter()
+        .filter(|(_, r)| seen.insert(reference.atom(*r).unwrap().residue_id()))
+        .collect();
+    let length = reference
+        .atoms()
+        .filter(|a| is_ca(a))
+        .map(|a| a.residue_id())
+        .collect::<HashSet<_>>()
+        .len();
+    if pairs.len() < 3 {
+        return Err(PDBError::InvalidValue(
+            format!("at least 3 paired CA atoms are needed for a comparison, found {}", pairs.len())
+        ))
+    }
+    Ok((
+        pairs.iter().map(|(m, _)| model.atom(*m).unwrap().coords()).collect(),
+        pairs.iter().map(|(_, r)| reference.atom(*r).unwrap().coords()).collect(),
+        length,
+    ))
+}
+
+/// Searches the superposition maximizing `score`, a function of the distances of all pairs.
+/// Superpositions are seeded on fragments of decreasing length and refined iteratively
+/// on the pairs closer than `cutoff`.
+fn max_superposition<S>(mobile: &[Vector3], target: &[Vector3], cutoff: f64, score: S) -> (f64, Transform)
+    where S: Fn(&[f64]) -> f64
+{
+    let n = mobile.len();
+    let mut best = (f64::MIN, Transform::identity());
+    let mut fragment = n;
+    loop {
+        let step = (fragment / 2).max(1);
+        for start in (0..=(n - fragment)).step_by(step) {
+            let mut selected: Vec<usize> = (start..start + fragment).collect();
+            for _ in 0..20 {
+                let m: Vec<Vector3> = selected.iter().map(|i| mobile[*i]).collect();
+                let t: Vec<Vector3> = selected.iter().map(|i| target[*i]).collect();
+                let transform = match kabsch(&m, &t) {
+                    Some(transform) => transform,
+                    None => break,
+                };
+                let distances: Vec<f64> = mobile
+                    .iter()
+                    .zip(target)
+                    .map(|(p, q)| transform.apply(p).distance(q))
+                    .collect();
+                let value = score(&distances);
+                if value > best.0 {
+                    best = (value, transform);
+                }
+                let next: Vec<usize> = (0..n).filter(|i| distances[*i] < cutoff).collect();
+                if next.len() < 3 || next == selected {
+                    break;
+                }
+                selected = next;
+            }
+        }
+        if fragment <= 4 {
+            break;
+        }
+        fragment = (fragment / 2).max(4).min(n);
+    }
+    best
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn helix(n: usize) -> PDB {
+        let mut pdb = PDB::new();
+        for i in 0..n {
+            let t = i as f64 * 100.0_f64.to_radians();
+            pdb.add_atom(Atom::new(false, i + 1, "CA", "ALA", "A", i + 1, 2.3 * t.cos(), 2.3 * t.sin(), 1.5 * i as f64, 1.0, 0.0, "C", 0).unwrap());
+        }
+        pdb
+    }
+
+    #[test]
+    fn identical_models_score_perfectly() {
+        let reference = helix(30);
+        let mut model = reference.clone();
+        model.rotate_axis_angle(Vector3::new(0.3, 1.0, 0.2), 40.0, None).unwrap();
+        model.translate(Vector3::new(3.0, 1.0, -5.0)).unwrap();
+
+        let tm = tm_score(&model, &reference).unwrap();
+        assert!((tm.score - 1.0).abs() < 1e-9);
+        assert_eq!(tm.aligned, 30);
+        let gdt = gdt(&model, &reference).unwrap();
+        assert!((gdt.gdt_ts - 100.0).abs() < 1e-9);
+        assert!((gdt.gdt_ha - 100.0).abs() < 1e-9);
+        let lddt = lddt(&mut model, &reference, true).unwrap();
+        assert!((lddt.score - 1.0).abs() < 1e-9);
+        assert!(model.atoms().all(|a| (a.temp_factor() - 1.0).abs() < 1e-9));
+    }
+
+    #[test]
+    fn perturbed_model_scores_lower() {
+        let reference = helix(30);
+        let mut model = reference.clone();
+        for atom in model.atoms_mut().skip(20) {
+            let x = *atom.x();
+            atom.set_x(x + 6.0).unwrap();
+        }
+        let tm = tm_score(&model, &reference).unwrap();
+        assert!(tm.score < 0.9 && tm.score > 0.5);
+        let gdt = gdt(&model, &reference).unwrap();
+        assert!(gdt.gdt_ts < 100.0 && gdt.gdt_ts > 60.0);
+        assert!(lddt(&mut model, &reference, false).unwrap().score < 1.0);
+    }
+}
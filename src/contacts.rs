@@ -0,0 +1,225 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::structs::{PDB, Atom, ResidueId};
+use crate::spatial::SpatialIndex;
+
+/// The atoms that represent a residue in a contact map
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContactMode {
+    /// The CA atom
+    CA,
+    /// The CB atom, or CA for glycine
+    CB,
+    /// Any heavy (non hydrogen) atom
+    HeavyAtom,
+}
+
+/// A residue-residue contact, `distance` is the closest distance of the representative atoms
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contact {
+    pub first: ResidueId,
+    pub second: ResidueId,
+    pub distance: f64,
+}
+
+/// Residue-residue contacts of the selected residues
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContactMap {
+    /// The residues in the order of the rows and columns of `matrix`
+    pub residues: Vec<ResidueId>,
+    /// The contacts as a sparse list, each pair listed once
+    pub contacts: Vec<Contact>,
+}
+
+impl ContactMap {
+    /// The contact map as a dense boolean matrix indexed like `residues`.
+    pub fn matrix(&self) -> Vec<Vec<bool>> {
+        let position: HashMap<&ResidueId, usize> = self.residues.iter().enumerate().map(|(i, r)| (r, i)).collect();
+        let mut matrix = vec![vec![false; self.residues.len()]; self.residues.len()];
+        for contact in self.contacts.iter() {
+            if let (Some(i), Some(j)) = (position.get(&contact.first), position.get(&contact.second)) {
+                matrix[*i][*j] = true;
+                matrix[*j][*i] = true;
+            }
+        }
+        matrix
+    }
+}
+
+/// A residue at a chain-chain interface with its closest contact in the partner chain
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterfaceResidue {
+    pub residue: ResidueId,
+    pub res_name: String,
+    /// The closest residue of the partner chain
+    pub partner: ResidueId,
+    /// The atom serial numbers of the closest atom pair (this residue, partner)
+    pub closest_atoms: (usize, usize),
+    pub distance: f64,
+}
+
+/// The interface between two chains
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interface {
+    pub chain_a: String,
+    pub chain_b: String,
+    /// The interface residues of `chain_a`
+    pub residues_a: Vec<InterfaceResidue>,
+    /// The interface residues of `chain_b`
+    pub residues_b: Vec<InterfaceResidue>,
+}
+
+/// Computes the residue-residue contacts closer than `cutoff` (Å) between residues of
+/// the chains `chains_a` and `chains_b`. An empty list selects every chain.
+/// With the same chains on both sides this is the contact map of those chains,
+/// where neighboring residues in sequence (`|i - j| < min_separation`) are skipped.
+pub fn contact_map(pdb: &PDB, chains_a: &[&str], chains_b: &[&str], mode: ContactMode, cutoff: f64, min_separation: usize) -> ContactMap {
+    let selected = |chains: &[&str], atom: &Atom| chains.is_empty() || chains.iter().any(|c| c.eq_ignore_ascii_case(atom.chain_id()));
+    let atoms = representative_atoms(pdb, mode);
+    let residues: Vec<ResidueId> = {
+        let mut seen = HashSet::new();
+        atoms
+            .iter()
+            .map(|i| pdb.atom(*i).unwrap())
+            .filter(|a| selected(chains_a, a) || selected(chains_b, a))
+            .map(Atom::residue_id)
+            .filter(|r| seen.insert(r.clone()))
+            .collect()
+    };
+    let order: HashMap<&ResidueId, usize> = residues.iter().enumerate().map(|(i, r)| (r, i)).collect();
+
+    let index = SpatialIndex::from_points(atoms.iter().map(|i| pdb.atom(*i).unwrap().coords()).collect());
+    let mut closest: BTreeMap<(usize, usize), f64> = BTreeMap::new();
+    for (i, j, distance) in index.pairs_within(cutoff) {
+        let (a, b) = (pdb.atom(atoms[i]).unwrap(), pdb.atom(atoms[j]).unwrap());
+        let ab = selected(chains_a, a) && selected(chains_b, b);
+        let ba = selected(chains_b, a) && selected(chains_a, b);
+        if !(ab || ba) {
+            continue;
+        }
+        let (ra, rb) = (a.residue_id(), b.residue_id());
+        if ra == rb || (ra.chain_id == rb.chain_id && ra.res_seq.abs_diff(rb.res_seq) < min_separation) {
+            continue;
+        }
+        let (oa, ob) = (order[&ra], order[&rb]);
+        let key = (oa.min(ob), oa.max(ob));
+        let entry = closest.entry(key).or_insert(distance);
+        *entry = entry.min(distance);
+    }
+
+    let contacts = closest
+        .into_iter()
+        .map(|((i, j), distance)| Contact {
+            first: residues[i].clone(),
+            second: residues[j].clone(),
+            distance,
+        })
+        .collect();
+    ContactMap { residues, contacts }
+}
+
+/// Finds the residues at the interface of every pair of chains.
+/// A residue is at the interface if any of its heavy atoms is within `cutoff` (Å)
+/// of a heavy atom of the partner chain.
+pub fn interfaces(pdb: &PDB, cutoff: f64) -> Vec<Interface> {
+    let atoms = representative_atoms(pdb, ContactMode::HeavyAtom);
+    let index = SpatialIndex::from_points(atoms.iter().map(|i| pdb.atom(*i).unwrap().coords()).collect());
+
+    // (chain a, chain b) -> residue of chain a -> closest contact
+    let mut found: BTreeMap<(String, String), BTreeMap<ResidueId, InterfaceResidue>> = BTreeMap::new();
+    let mut record = |a: &Atom, b: &Atom, distance: f64| {
+        let residues = found.entry((a.chain_id().to_owned(), b.chain_id().to_owned())).or_default();
+        let candidate = InterfaceResidue {
+            residue: a.residue_id(),
+            res_name: a.res_name().to_owned(),
+            partner: b.residue_id(),
+            closest_atoms: (*a.serial_number(), *b.serial_number()),
+            distance,
+        };
+        match residues.get(&candidate.residue) {
+            Some(existing) if existing.distance <= distance => (),
+            _ => {
+                residues.insert(candidate.residue.clone(), candidate);
+            },
+        }
+    };
+    for (i, j, distance) in index.pairs_within(cutoff) {
+        let (a, b) = (pdb.atom(atoms[i]).unwrap(), pdb.atom(atoms[j]).unwrap());
+        if a.chain_id() == b.chain_id() {
+            continue;
+        }
+        record(a, b, distance);
+        record(b, a, distance);
+    }
+
+    let mut interfaces = Vec::new();
+    for ((chain_a, chain_b), residues_a) in found.iter() {
+        if chain_a >= chain_b {
+            continue;
+        }
+        let residues_b = found.get(&(chain_b.clone(), chain_a.clone())).cloned().unwrap_or_default();
+        interfaces.push(Interface {
+            chain_a: chain_a.clone(),
+            chain_b: chain_b.clone(),
+            residues_a: residues_a.values().cloned().collect(),
+            residues_b: residues_b.into_values().collect(),
+        });
+    }
+    interfaces
+}
+
+/// The indices of the atoms representing residues in `mode`.
+fn representative_atoms(pdb: &PDB, mode: ContactMode) -> Vec<usize> {
+    match mode {
+        ContactMode::HeavyAtom => pdb
+            .atoms()
+            .enumerate()
+            .filter(|(_, a)| !a.is_hydrogen())
+            .map(|(i, _)| i)
+            .collect(),
+        ContactMode::CA | ContactMode::CB => pdb
+            .residue_indices()
+            .into_iter()
+            .filter_map(|(_, indices)| {
+                let find = |name: &str| indices.iter().copied().find(|i| pdb.atom(*i).unwrap().atom_name() == name);
+                match mode {
+                    ContactMode::CB => find("CB").or_else(|| find("CA")),
+                    _ => find("CA"),
+                }
+            })
+            .collect(),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add(pdb: &mut PDB, serial: usize, name: &str, chain: &str, res_seq: usize, x: f64) {
+        pdb.add_atom(Atom::new(false, serial, name, "ALA", chain, res_seq, x, 0.0, 0.0, 1.0, 0.0, "C", 0).unwrap());
+    }
+
+    #[test]
+    fn contacts_and_interface() {
+        let mut pdb = PDB::new();
+        add(&mut pdb, 1, "CA", "A", 1, 0.0);
+        add(&mut pdb, 2, "CB", "A", 1, 1.0);
+        add(&mut pdb, 3, "CA", "A", 2, 3.8);
+        add(&mut pdb, 4, "CA", "A", 5, 7.6);
+        add(&mut pdb, 5, "CA", "B", 1, 11.0);
+        add(&mut pdb, 6, "CA", "B", 2, 30.0);
+
+        let map = contact_map(&pdb, &["A"], &["A"], ContactMode::CA, 8.0, 4);
+        assert_eq!(map.residues.len(), 3);
+        assert_eq!(map.contacts.len(), 1);
+        assert_eq!(map.contacts[0].second, ResidueId::new("A", 5, None));
+        assert!(map.matrix()[0][2]);
+
+        let interfaces = interfaces(&pdb, 4.0);
+        assert_eq!(interfaces.len(), 1);
+        assert_eq!(interfaces[0].residues_a.len(), 1);
+        assert_eq!(interfaces[0].residues_a[0].closest_atoms, (4, 5));
+        assert_eq!(interfaces[0].residues_b[0].partner, ResidueId::new("A", 5, None));
+    }
+}
@@ -0,0 +1,478 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use crate::structs::{PDB, ResidueId, SecondaryStructure, Helix, HelixClass, Sheet, Strand, StrandSense};
+use crate::structs::atom::MAX_PEPTIDE_BOND;
+use crate::geometry::{self, Vector3};
+use crate::spatial::SpatialIndex;
+
+/// Electrostatic factor of the DSSP hydrogen bond energy (kcal/mol Å)
+const COUPLING: f64 = 0.084 * 332.0;
+/// Hydrogen bond energy cutoff (kcal/mol)
+const MAX_HBOND_ENERGY: f64 = -0.5;
+/// CA-CA distance above which no hydrogen bond is considered (Å)
+const MAX_CA_DISTANCE: f64 = 9.0;
+
+/// The DSSP assignment of a structure
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dssp {
+    /// The assignment of every residue with a complete backbone, in order
+    pub residues: Vec<(ResidueId, SecondaryStructure)>,
+    /// Continuous H, G and I segments
+    pub helices: Vec<Helix>,
+    /// Strands grouped into sheets by their ladders
+    pub sheets: Vec<Sheet>,
+}
+
+impl Dssp {
+    /// The assignment as a string of DSSP codes, one character per residue.
+    pub fn summary(&self) -> String {
+        self.residues.iter().map(|(_, ss)| ss.code()).collect()
+    }
+}
+
+struct Backbone {
+    id: ResidueId,
+    res_name: String,
+    n: Vector3,
+    ca: Vector3,
+    c: Vector3,
+    o: Vector3,
+    h: Option<Vector3>,
+    /// No peptide bond to the previous residue
+    break_before: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BridgeType {
+    Parallel,
+    Antiparallel,
+}
+
+/// A ladder of consecutive bridges, `i` and `j` are the residue ranges of both sides
+#[derive(Debug, Clone)]
+struct Ladder {
+    kind: BridgeType,
+    i: (usize, usize),
+    j: (usize, usize),
+    bridges: usize,
+}
+
+/// Assigns the secondary structure of all protein residues from backbone geometry (Kabsch & Sander).
+/// Residues need N, CA, C and O atoms, hydrogen positions are always computed from the backbone.
+pub fn dssp(pdb: &PDB) -> Dssp {
+    let residues = backbone(pdb);
+    let n = residues.len();
+    let hbonds = hydrogen_bonds(&residues);
+    // hbond(i, j): CO of residue i to NH of residue j
+    let hbond = |i: isize, j: isize| -> bool {
+        i >= 0 && j >= 0 && (i as usize) < n && (j as usize) < n && hbonds.contains(&(i as usize, j as usize))
+    };
+    let no_break = |from: usize, to: usize| -> bool {
+        to < n && ((from + 1)..=to).all(|k| !residues[k].break_before)
+    };
+
+    let mut assignment = vec![SecondaryStructure::Coil; n];
+
+    // n-turns
+    let mut turns = [vec![false; n], vec![false; n], vec![false; n]];
+    for (t, turn) in turns.iter_mut().enumerate() {
+        let span = t + 3;
+        for (i, starts) in turn.iter_mut().enumerate() {
+            *starts = no_break(i, i + span) && hbond(i as isize, (i + span) as isize);
+        }
+    }
+
+    // minimal alpha helices first
+    for i in 1..n {
+        if turns[1][i - 1] && turns[1][i] {
+            for ss in assignment[i..(i + 4).min(n)].iter_mut() {
+                *ss = SecondaryStructure::AlphaHelix;
+            }
+        }
+    }
+
+    // bridges and ladders
+    let ladders = ladders(n, &hbond, &no_break);
+    let linked = bulge_links(&ladders);
+    let mut bridge_partners: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); n];
+    for (l, ladder) in ladders.iter().enumerate() {
+        let strand = ladder.bridges > 1 || linked.iter().any(|(a, b)| *a == l || *b == l);
+        let ss = if strand { SecondaryStructure::Strand } else { SecondaryStructure::Bridge };
+        for k in (ladder.i.0..=ladder.i.1).chain(ladder.j.0..=ladder.j.1) {
+            bridge_partners[k].insert(l);
+            if assignment[k] != SecondaryStructure::AlphaHelix {
+                assignment[k] = ss;
+            }
+        }
+    }
+    for (a, b) in linked.iter() {
+        let (first, second) = (&ladders[*a], &ladders[*b]);
+        let gap_j = match first.kind {
+            BridgeType::Parallel => (first.j.1, second.j.0),
+            BridgeType::Antiparallel => (second.j.1, first.j.0),
+        };
+        for (from, to) in [(first.i.1, second.i.0), gap_j].iter() {
+            for ss in assignment[*from..=*to].iter_mut() {
+                if *ss != SecondaryStructure::AlphaHelix {
+                    *ss = SecondaryStructure::Strand;
+                }
+            }
+        }
+    }
+
+    // 3-10 and pi helices only where the whole minimal helix is free
+    for (t, ss) in [(0, SecondaryStructure::ThreeTenHelix), (2, SecondaryStructure::PiHelix)].iter() {
+        let span = t + 3;
+        for i in 1..n {
+            if turns[*t][i - 1] && turns[*t][i] && i + span <= n
+                && assignment[i..i + span].iter().all(|a| matches!(a, SecondaryStructure::Coil | SecondaryStructure::Turn | SecondaryStructure::Bend) || a == ss)
+            {
+                for a in assignment[i..i + span].iter_mut() {
+                    *a = *ss;
+                }
+            }
+        }
+    }
+
+    // turns and bends on the remaining residues
+    for (t, turn) in turns.iter().enumerate() {
+        for i in 0..n {
+            if turn[i] {
+                for a in assignment[(i + 1)..(i + t + 3).min(n)].iter_mut() {
+                    if *a == SecondaryStructure::Coil {
+                        *a = SecondaryStructure::Turn;
+                    }
+                }
+            }
+        }
+    }
+    for i in 2..n.saturating_sub(2) {
+        if assignment[i] == SecondaryStructure::Coil && no_break(i - 2, i + 2) {
+            let kappa = 180.0 - geometry::angle(&residues[i - 2].ca, &residues[i].ca, &residues[i + 2].ca);
+            if kappa > 70.0 {
+                assignment[i] = SecondaryStructure::Bend;
+            }
+        }
+    }
+
+    Dssp {
+        helices: helix_segments(&residues, &assignment),
+        sheets: sheets(&residues, &assignment, &ladders, &bridge_partners),
+        residues: residues.into_iter().map(|r| r.id).zip(assignment).collect(),
+    }
+}
+
+/// Runs `dssp` and stores the result in `pdb`, replacing the previous
+/// per-residue assignment, helices and sheets.
+pub fn assign_secondary_structure(pdb: &mut PDB) -> Dssp {
+    let result = dssp(pdb);
+    pdb.clear_secondary_structure();
+    for (id, ss) in result.residues.iter() {
+        pdb.set_secondary_structure(id.clone(), *ss);
+    }
+    for helix in result.helices.iter() {
+        pdb.add_helix(helix.clone());
+    }
+    for sheet in result.sheets.iter() {
+        pdb.add_sheet(sheet.clone());
+    }
+    result
+}
+
+fn backbone(pdb: &PDB) -> Vec<Backbone> {
+    let mut residues: Vec<Backbone> = Vec::new();
+    for (id, indices) in pdb.residue_indices() {
+        let find = |name: &str| {
+            indices.iter().map(|i| pdb.atom(*i).unwrap()).find(|a| a.atom_name() == name).map(|a| a.coords())
+        };
+        let (n, ca, c, o) = match (find("N"), find("CA"), find("C"), find("O")) {
+            (Some(n), Some(ca), Some(c), Some(o)) => (n, ca, c, o),
+            _ => continue,
+        };
+        let res_name = pdb.atom(indices[0]).unwrap().res_name().to_owned();
+        let break_before = match residues.last() {
+            Some(previous) => previous.id.chain_id != id.chain_id || previous.c.distance(&n) > MAX_PEPTIDE_BOND,
+            None => true,
+        };
+        let h = match residues.last() {
+            Some(previous) if !break_before && res_name != "PRO" => {
+                (previous.c - previous.o).normalize().map(|d| n + d)
+            },
+            _ => None,
+        };
+        residues.push(Backbone { id, res_name, n, ca, c, o, h, break_before });
+    }
+    residues
+}
+
+/// The (acceptor, donor) pairs with a hydrogen bond energy below the cutoff.
+fn hydrogen_bonds(residues: &[Backbone]) -> HashSet<(usize, usize)> {
+    let index = SpatialIndex::from_points(residues.iter().map(|r| r.ca).collect());
+    let mut hbonds = HashSet::new();
+    for (i, j, _) in index.pairs_within(MAX_CA_DISTANCE) {
+        for (acceptor, donor) in [(i, j), (j, i)].iter() {
+            if *donor == acceptor + 1 {
+                continue;
+            }
+            if hbond_energy(&residues[*acceptor], &residues[*donor]) < MAX_HBOND_ENERGY {
+                hbonds.insert((*acceptor, *donor));
+            }
+        }
+    }
+    hbonds
+}
+
+/// The electrostatic energy (kcal/mol) of the hydrogen bond C=O of `acceptor` to N-H of `donor`.
+fn hbond_energy(acceptor: &Backbone, donor: &Backbone) -> f64 {
+    let h = match donor.h {
+        Some(h) => h,
+        None => return 0.0,
+    };
+    let r_on = acceptor.o.distance(&donor.n);
+    let r_ch = acceptor.c.distance(&h);
+    let r_oh = acceptor.o.distance(&h);
+    let r_cn = acceptor.c.distance(&donor.n);
+    if r_on < 0.5 || r_ch < 0.5 || r_oh < 0.5 || r_cn < 0.5 {
+        return -9.9;
+    }
+    (COUPLING * (1.0 / r_on + 1.0 / r_ch - 1.0 / r_oh - 1.0 / r_cn)).max(-9.9)
+}
+
+fn ladders<H, B>(n: usize, hbond: &H, no_break: &B) -> Vec<Ladder>
+    where H: Fn(isize, isize) -> bool, B: Fn(usize, usize) -> bool
+{
+    let mut ladders: Vec<Ladder> = Vec::new();
+    for i in 1..n.saturating_sub(4) {
+        for j in (i + 3)..n.saturating_sub(1) {
+            if !no_break(i - 1, i + 1) || !no_break(j - 1, j + 1) {
+                continue;
+            }
+            let (a, b) = (i as isize, j as isize);
+            let kind = if (hbond(a - 1, b) && hbond(b, a + 1)) || (hbond(b - 1, a) && hbond(a, b + 1)) {
+                BridgeType::Parallel
+            } else if (hbond(a, b) && hbond(b, a)) || (hbond(a - 1, b + 1) && hbond(b - 1, a + 1)) {
+                BridgeType::Antiparallel
+            } else {
+                continue;
+            };
+            let extends = |ladder: &Ladder| {
+                ladder.kind == kind && ladder.i.1 + 1 == i && match kind {
+                    BridgeType::Parallel => ladder.j.1 + 1 == j,
+                    BridgeType::Antiparallel => ladder.j.0 == j + 1,
+                }
+            };
+            match ladders.iter_mut().find(|l| extends(l)) {
+                Some(ladder) => {
+                    ladder.i.1 = i;
+                    match kind {
+                        BridgeType::Parallel => ladder.j.1 = j,
+                        BridgeType::Antiparallel => ladder.j.0 = j,
+                    }
+                    ladder.bridges += 1;
+                },
+                None => ladders.push(Ladder { kind, i: (i, i), j: (j, j), bridges: 1 }),
+            }
+        }
+    }
+    ladders
+}
+
+/// Pairs of ladders of the same type connected by a beta bulge: a gap of at most
+/// one residue on one strand and at most four residues on the other.
+fn bulge_links(ladders: &[Ladder]) -> Vec<(usize, usize)> {
+    let mut links = Vec::new();
+    for (a, first) in ladders.iter().enumerate() {
+        for (b, second) in ladders.iter().enumerate().skip(a + 1) {
+            if first.kind != second.kind || second.i.0 <= first.i.1 {
+                continue;
+            }
+            let gap_i = second.i.0 - first.i.1 - 1;
+            let gap_j = match first.kind {
+                BridgeType::Parallel if second.j.0 > first.j.1 => second.j.0 - first.j.1 - 1,
+                BridgeType::Antiparallel if first.j.0 > second.j.1 => first.j.0 - second.j.1 - 1,
+                _ => continue,
+            };
+            if (gap_i <= 1 && gap_j <= 4) || (gap_i <= 4 && gap_j <= 1) {
+                links.push((a, b));
+            }
+        }
+    }
+    links
+}
+
+fn helix_segments(residues: &[Backbone], assignment: &[SecondaryStructure]) -> Vec<Helix> {
+    let mut helices = Vec::new();
+    let mut start = 0;
+    for i in 1..=assignment.len() {
+        let ends = i == assignment.len() || assignment[i] != assignment[start] || residues[i].break_before;
+        if !ends {
+            continue;
+        }
+        let class = match assignment[start] {
+            SecondaryStructure::AlphaHelix => Some(HelixClass::RightHandedAlpha),
+            SecondaryStructure::ThreeTenHelix => Some(HelixClass::RightHanded310),
+            SecondaryStructure::PiHelix => Some(HelixClass::RightHandedPi),
+            _ => None,
+        };
+        if let Some(class) = class {
+            helices.push(Helix {
+                serial_number: helices.len() + 1,
+                id: (helices.len() + 1).to_string(),
+                start: residues[start].id.clone(),
+                start_res_name: residues[start].res_name.clone(),
+                end: residues[i - 1].id.clone(),
+                end_res_name: residues[i - 1].res_name.clone(),
+                class,
+                comment: String::new(),
+                length: i - start,
+            });
+        }
+        start = i;
+    }
+    helices
+}
+
+fn sheets(residues: &[Backbone], assignment: &[SecondaryStructure], ladders: &[Ladder], bridge_partners: &[BTreeSet<usize>]) -> Vec<Sheet> {
+    // strands are continuous E segments
+    let mut strands: Vec<(usize, usize)> = Vec::new();
+    for (i, ss) in assignment.iter().enumerate() {
+        if *ss != SecondaryStructure::Strand {
+            continue;
+        }
+        match strands.last_mut() {
+            Some(strand) if strand.1 + 1 == i && !residues[i].break_before => strand.1 = i,
+            _ => strands.push((i, i)),
+        }
+    }
+    let strand_of = |k: usize| strands.iter().position(|(from, to)| *from <= k && k <= *to);
+
+    // strand adjacency through ladders
+    let mut partners: BTreeMap<usize, BTreeMap<usize, BridgeType>> = BTreeMap::new();
+    for ladder in ladders.iter() {
+        if let (Some(a), Some(b)) = (strand_of(ladder.i.0), strand_of(ladder.j.0)) {
+            if a != b {
+                partners.entry(a).or_default().insert(b, ladder.kind);
+                partners.entry(b).or_default().insert(a, ladder.kind);
+            }
+        }
+    }
+
+    let mut sheets = Vec::new();
+    let mut visited = vec![false; strands.len()];
+    for first in 0..strands.len() {
+        if visited[first] || !(strands[first].0..=strands[first].1).any(|k| !bridge_partners[k].is_empty()) {
+            continue;
+        }
+        // start from an edge strand of the component if there is one
+        let mut component = vec![first];
+        let mut seen: HashSet<usize> = [first].iter().copied().collect();
+        let mut k = 0;
+        while k < component.len() {
+            for other in partners.get(&component[k]).map(|p| p.keys().copied().collect::<Vec<usize>>()).unwrap_or_default() {
+                if seen.insert(other) {
+                    component.push(other);
+                }
+            }
+            k += 1;
+        }
+        let start = component
+            .iter()
+            .copied()
+            .find(|s| partners.get(s).map(|p| p.len()).unwrap_or(0) <= 1)
+            .unwrap_or(first);
+
+        let mut order: Vec<(usize, StrandSense)> = vec![(start, StrandSense::First)];
+        visited[start] = true;
+        let mut k = 0;
+        while k < order.len() {
+            let current = order[k].0;
+            if let Some(next) = partners.get(&current) {
+                for (other, kind) in next.iter() {
+                    if !visited[*other] {
+                        visited[*other] = true;
+                        let sense = match kind {
+                            BridgeType::Parallel => StrandSense::Parallel,
+                            BridgeType::Antiparallel => StrandSense::Antiparallel,
+                        };
+                        order.push((*other, sense));
+                    }
+                }
+            }
+            k += 1;
+        }
+
+        sheets.push(Sheet {
+            id: sheet_id(sheets.len()),
+            strands: order
+                .into_iter()
+                .map(|(s, sense)| {
+                    let (from, to) = strands[s];
+                    Strand {
+                        start: residues[from].id.clone(),
+                        start_res_name: residues[from].res_name.clone(),
+                        end: residues[to].id.clone(),
+                        end_res_name: residues[to].res_name.clone(),
+                        sense,
+                        registration: None,
+                    }
+                })
+                .collect(),
+        });
+    }
+    sheets
+}
+
+/// Sheet identifiers A, B, ..., Z, AA, AB, ..., ZZ, AAA, ..., ZZZ, which fill the three
+/// columns of the SHEET record for up to 18278 sheets
+fn sheet_id(mut index: usize) -> String {
+    let mut id = Vec::new();
+    loop {
+        id.push((b'A' + (index % 26) as u8) as char);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    id.iter().rev().collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::peptide;
+
+    #[test]
+    fn assigns_alpha_helix() {
+        let mut pdb = peptide(&[("ALA", -57.0, -47.0); 14]);
+        let result = assign_secondary_structure(&mut pdb);
+        assert_eq!(result.residues.len(), 14);
+        let summary = result.summary();
+        assert!(summary.contains("HHHHHHHH"), "{}", summary);
+        assert_eq!(result.helices.len(), 1);
+        assert!(result.sheets.is_empty());
+        assert_eq!(pdb.secondary_structure(&ResidueId::new("A", 7, None)), Some(SecondaryStructure::AlphaHelix));
+    }
+
+    #[test]
+    fn assigns_hairpin() {
+        let mut residues = vec![("VAL", -120.0, 130.0); 6];
+        residues.extend_from_slice(&[("GLY", 60.0, 30.0), ("ASN", 90.0, 0.0)]);
+        residues.extend_from_slice(&[("VAL", -120.0, 130.0); 6]);
+        let result = dssp(&peptide(&residues));
+        assert!(result.summary().contains("EETTEE"), "{}", result.summary());
+        assert_eq!(result.sheets.len(), 1);
+        let senses: Vec<StrandSense> = result.sheets[0].strands.iter().map(|s| s.sense).collect();
+        assert_eq!(senses, vec![StrandSense::First, StrandSense::Antiparallel]);
+    }
+
+    #[test]
+    fn sheet_ids() {
+        assert_eq!(sheet_id(0), "A");
+        assert_eq!(sheet_id(25), "Z");
+        assert_eq!(sheet_id(26), "AA");
+        assert_eq!(sheet_id(701), "ZZ");
+        assert_eq!(sheet_id(702), "AAA");
+        assert_eq!(sheet_id(18277), "ZZZ");
+    }
+}
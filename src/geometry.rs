@@ -0,0 +1,264 @@
+use std::fmt;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+/// A point or a direction in 3D space (Å)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Vector3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Vector3 {
+    pub fn new(x: f64, y: f64, z: f64) -> Vector3 {
+        Vector3 { x, y, z }
+    }
+
+    pub fn zero() -> Vector3 {
+        Vector3::default()
+    }
+
+    pub fn dot(&self, other: &Vector3) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(&self, other: &Vector3) -> Vector3 {
+        Vector3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn norm_squared(&self) -> f64 {
+        self.dot(self)
+    }
+
+    pub fn norm(&self) -> f64 {
+        self.norm_squared().sqrt()
+    }
+
+    /// Returns the unit vector in the same direction, or `None` for a zero vector.
+    pub fn normalize(&self) -> Option<Vector3> {
+        let norm = self.norm();
+        if norm > 0.0 {
+            Some(*self / norm)
+        } else {
+            None
+        }
+    }
+
+    pub fn distance(&self, other: &Vector3) -> f64 {
+        (*self - *other).norm()
+    }
+
+    pub fn distance_squared(&self, other: &Vector3) -> f64 {
+        (*self - *other).norm_squared()
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.x.is_finite() && self.y.is_finite() && self.z.is_finite()
+    }
+
+    pub fn to_tuple(self) -> (f64, f64, f64) {
+        (self.x, self.y, self.z)
+    }
+
+    pub fn to_array(self) -> [f64; 3] {
+        [self.x, self.y, self.z]
+    }
+}
+
+/// The angle a-b-c in degrees, with `b` at the vertex.
+pub fn angle(a: &Vector3, b: &Vector3, c: &Vector3) -> f64 {
+    let ba = *a - *b;
+    let bc = *c - *b;
+    let cos = ba.dot(&bc) / (ba.norm() * bc.norm());
+    cos.clamp(-1.0, 1.0).acos().to_degrees()
+}
+
+/// The dihedral angle a-b-c-d in degrees, in the range (-180, 180].
+pub fn dihedral(a: &Vector3, b: &Vector3, c: &Vector3, d: &Vector3) -> f64 {
+    let b0 = *a - *b;
+    let b1 = *c - *b;
+    let b2 = *d - *c;
+    let b1 = b1 / b1.norm();
+    let v = b0 - b1 * b0.dot(&b1);
+    let w = b2 - b1 * b2.dot(&b1);
+    let x = v.dot(&w);
+    let y = b1.cross(&v).dot(&w);
+    y.atan2(x).to_degrees()
+}
+
+impl fmt::Display for Vector3 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {}, {})", self.x, self.y, self.z)
+    }
+}
+
+impl From<(f64, f64, f64)> for Vector3 {
+    fn from(t: (f64, f64, f64)) -> Self {
+        Vector3::new(t.0, t.1, t.2)
+    }
+}
+
+impl From<[f64; 3]> for Vector3 {
+    fn from(a: [f64; 3]) -> Self {
+        Vector3::new(a[0], a[1], a[2])
+    }
+}
+
+impl Add for Vector3 {
+    type Output = Vector3;
+    fn add(self, other: Vector3) -> Vector3 {
+        Vector3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl AddAssign for Vector3 {
+    fn add_assign(&mut self, other: Vector3) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Vector3 {
+    type Output = Vector3;
+    fn sub(self, other: Vector3) -> Vector3 {
+        Vector3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl SubAssign for Vector3 {
+    fn sub_assign(&mut self, other: Vector3) {
+        *self = *self - other;
+    }
+}
+
+impl Mul<f64> for Vector3 {
+    type Output = Vector3;
+    fn mul(self, scalar: f64) -> Vector3 {
+        Vector3::new(self.x * scalar, self.y * scalar, self.z * scalar)
+    }
+}
+
+impl Div<f64> for Vector3 {
+    type Output = Vector3;
+    fn div(self, scalar: f64) -> Vector3 {
+        Vector3::new(self.x / scalar, self.y / scalar, self.z / scalar)
+    }
+}
+
+impl Neg for Vector3 {
+    type Output = Vector3;
+    fn neg(self) -> Vector3 {
+        Vector3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+/// Places an atom `d` from three reference atoms by internal coordinates (NeRF):
+/// the bond length c-d (Å), the angle b-c-d and the dihedral a-b-c-d (degrees).
+#[cfg(test)]
+pub(crate) fn place_atom(a: &Vector3, b: &Vector3, c: &Vector3, bond: f64, angle: f64, torsion: f64) -> Vector3 {
+    let bc = (*c - *b).normalize().unwrap_or_default();
+    let n = (*b - *a).cross(&bc).normalize().unwrap_or_default();
+    let m = n.cross(&bc);
+    let (angle, torsion) = (angle.to_radians(), torsion.to_radians());
+    *c + bc * (-bond * angle.cos())
+        + m * (bond * angle.sin() * torsion.cos())
+        + n * (bond * angle.sin() * torsion.sin())
+}
+
+/// Eigen decomposition of a symmetric matrix by the cyclic Jacobi method.
+/// Returns the eigenvalues and the eigenvectors (as columns), sorted by descending eigenvalue.
+pub(crate) fn symmetric_eigen<const N: usize>(matrix: &[[f64; N]; N]) -> ([f64; N], [[f64; N]; N]) {
+    let mut a = *matrix;
+    let mut v = [[0.0; N]; N];
+    for (i, row) in v.iter_mut().enumerate() {
+        row[i] = 1.0;
+    }
+    for _ in 0..100 {
+        let off: f64 = (0..N).flat_map(|i| (0..N).filter(move |j| *j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[i][j] * a[i][j])
+            .sum();
+        if off < 1e-22 {
+            break;
+        }
+        for p in 0..N {
+            for q in (p + 1)..N {
+                if a[p][q].abs() < 1e-300 {
+                    continue;
+                }
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let t = if theta == 0.0 { 1.0 } else { t };
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for row in a.iter_mut() {
+                    let (akp, akq) = (row[p], row[q]);
+                    row[p] = c * akp - s * akq;
+                    row[q] = s * akp + c * akq;
+                }
+                let (row_p, row_q) = (a[p], a[q]);
+                for (k, (apk, aqk)) in row_p.iter().zip(row_q.iter()).enumerate() {
+                    a[p][k] = c * apk - s * aqk;
+                    a[q][k] = s * apk + c * aqk;
+                }
+                for row in v.iter_mut() {
+                    let (vkp, vkq) = (row[p], row[q]);
+                    row[p] = c * vkp - s * vkq;
+                    row[q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+    let mut order: Vec<usize> = (0..N).collect();
+    order.sort_by(|i, j| a[*j][*j].partial_cmp(&a[*i][*i]).unwrap_or(std::cmp::Ordering::Equal));
+    let mut values = [0.0; N];
+    let mut vectors = [[0.0; N]; N];
+    for (new, old) in order.into_iter().enumerate() {
+        values[new] = a[old][old];
+        for k in 0..N {
+            vectors[k][new] = v[k][old];
+        }
+    }
+    (values, vectors)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn angle_and_dihedral() {
+        let a = Vector3::new(1.0, 0.0, 0.0);
+        let b = Vector3::zero();
+        let c = Vector3::new(0.0, 1.0, 0.0);
+        let d = Vector3::new(0.0, 1.0, 1.0);
+        assert!((angle(&a, &b, &c) - 90.0).abs() < 1e-9);
+        assert!((dihedral(&a, &b, &c, &d) + 90.0).abs() < 1e-9);
+        let d = Vector3::new(1.0, 1.0, 0.0);
+        assert!(dihedral(&a, &b, &c, &d).abs() < 1e-9);
+    }
+
+    #[test]
+    fn place_atom_by_internal_coordinates() {
+        let a = Vector3::new(1.0, 1.0, 0.0);
+        let b = Vector3::new(0.0, 0.0, 0.0);
+        let c = Vector3::new(1.5, 0.0, 0.0);
+        let d = place_atom(&a, &b, &c, 1.3, 115.0, -60.0);
+        assert!((d.distance(&c) - 1.3).abs() < 1e-9);
+        assert!((angle(&b, &c, &d) - 115.0).abs() < 1e-9);
+        assert!((dihedral(&a, &b, &c, &d) + 60.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn eigen_of_symmetric_matrix() {
+        let (values, vectors) = symmetric_eigen(&[[2.0, 1.0, 0.0], [1.0, 2.0, 0.0], [0.0, 0.0, 1.5]]);
+        assert!((values[0] - 3.0).abs() < 1e-12);
+        assert!((values[1] - 1.5).abs() < 1e-12);
+        assert!((values[2] - 1.0).abs() < 1e-12);
+        assert!((vectors[0][0].abs() - 0.5_f64.sqrt()).abs() < 1e-12);
+        assert!((vectors[0][0] - vectors[1][0]).abs() < 1e-12);
+    }
+}
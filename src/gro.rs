@@ -0,0 +1,200 @@
+use anyhow::{anyhow, ensure, Context as _, Result};
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufReader, BufWriter};
+
+use crate::structs::{PDB, Atom, Element, UnitCell};
+use crate::structs::atom::is_protein_residue;
+use crate::geometry::Vector3;
+
+/// Ångström per nanometer, GROMACS files use nm and nm/ps
+const NM: f64 = 10.0;
+
+/// Reads a GROMACS .gro file.
+/// Coordinates and velocities are converted from nm to Å, the box becomes the unit cell
+/// and all atoms are put in chain A. The atom numbers of the file are not used as they
+/// wrap around at 100000, the atoms are numbered in order instead.
+pub fn read_gro(filename: &str) -> Result<PDB> {
+    let f = File::open(filename).with_context(|| format!("could not open {}", filename))?;
+    read_gro_raw(BufReader::new(f))
+}
+
+pub fn read_gro_raw<T>(input: BufReader<T>) -> Result<PDB>
+    where T: std::io::Read
+{
+    let mut lines = input.lines();
+    let mut next_line = |line_number: usize| -> Result<String> {
+        lines
+            .next()
+            .ok_or_else(|| anyhow!("unexpected end of file at line {}", line_number))?
+            .with_context(|| format!("could not read line {}", line_number))
+    };
+
+    let _title = next_line(1)?;
+    let count = next_line(2)?;
+    let count: usize = count.trim().parse().with_context(|| format!("can not parse the atom count at line 2: {}", count.trim()))?;
+
+    let mut pdb = PDB::new();
+    for index in 0..count {
+        let line_number = index + 3;
+        pdb.add_atom(parse_atom(&next_line(line_number)?, index + 1, line_number)?);
+    }
+
+    let line_number = count + 3;
+    let line = next_line(line_number)?;
+    let values = line
+        .split_whitespace()
+        .map(|v| v.parse::<f64>().with_context(|| format!("can not parse '{}' at line {}", v, line_number)))
+        .collect::<Result<Vec<f64>>>()?;
+    let value = |i: usize| values.get(i).copied().unwrap_or(0.0) * NM;
+    ensure!(values.len() == 3 || values.len() == 9, "expected 3 or 9 box values at line {}, found {}", line_number, values.len());
+    if values.iter().any(|v| *v != 0.0) {
+        pdb.set_unit_cell(Some(UnitCell::from_vectors(&[
+            Vector3::new(value(0), value(3), value(4)),
+            Vector3::new(value(5), value(1), value(6)),
+            Vector3::new(value(7), value(8), value(2)),
+        ])));
+    }
+    Ok(pdb)
+}
+
+/// Parses an atom line: residue number, residue name, atom name and atom number in
+/// columns of five, followed by the coordinates and optionally the velocities.
+/// The width of the numbers depends on the precision of the file and is taken from
+/// the distance between the first two decimal points.
+fn parse_atom(line: &str, serial_number: usize, line_number: usize) -> Result<Atom> {
+    let chars: Vec<char> = line.chars().collect();
+    ensure!(chars.len() > 20, "atom line is too short at line {}", line_number);
+    let text = |from: usize, to: usize| chars[from.min(chars.len())..to.min(chars.len())].iter().collect::<String>();
+
+    let first = chars[20..].iter().position(|c| *c == '.').map(|p| p + 20);
+    let second = first.and_then(|p| chars[p + 1..].iter().position(|c| *c == '.').map(|q| q + p + 1));
+    let width = match (first, second) {
+        (Some(first), Some(second)) => second - first,
+        _ => return Err(anyhow!("can not find the coordinates at line {}", line_number)),
+    };
+    let number = |k: usize| -> Result<f64> {
+        let field = text(20 + k * width, 20 + (k + 1) * width);
+        field.trim().parse::<f64>().with_context(|| format!("can not parse '{}' at line {}", field.trim(), line_number))
+    };
+    ensure!(chars.len() >= 20 + 3 * width, "atom line is too short at line {}", line_number);
+
+    let res_seq = text(0, 5);
+    let res_seq: usize = res_seq.trim().parse().with_context(|| format!("can not parse the residue number at line {}: {}", line_number, res_seq.trim()))?;
+    let res_name = text(5, 10);
+    let atom_name = text(10, 15);
+    let mut atom = Atom::new(
+        !is_protein_residue(res_name.trim()),
+        serial_number,
+        &atom_name,
+        &res_name,
+        "A",
+        res_seq,
+        number(0)? * NM,
+        number(1)? * NM,
+        number(2)? * NM,
+        1.0,
+        0.0,
+        Element::infer(atom_name.trim(), res_name.trim()).map(|e| e.symbol()).unwrap_or(""),
+        0,
+    ).ok_or_else(|| anyhow!("invalid atom at line {}", line_number))?;
+    if chars.len() >= 20 + 6 * width {
+        atom.set_velocity(Some(Vector3::new(number(3)?, number(4)?, number(5)?) * NM))?;
+    }
+    Ok(atom)
+}
+
+/// Saves the `PDB` as a GROMACS .gro file with coordinates in nm.
+/// Velocities are only written if every atom has one. Without a unit cell the box is written as zeros.
+pub fn save_gro(pdb: &PDB, filename: &str) -> Result<()> {
+    let f = File::create(filename).with_context(|| format!("could not create {}", filename))?;
+    save_gro_raw(pdb, BufWriter::new(f))
+}
+
+pub(crate) fn save_gro_raw<W: Write>(pdb: &PDB, mut stream: BufWriter<W>) -> Result<()> {
+    writeln!(stream, "{}", pdb.identifier().map(|i| i.as_str()).unwrap_or("libpdb"))?;
+    writeln!(stream, "{:5}", pdb.atom_count())?;
+    let velocities = pdb.atom_count() > 0 && pdb.atoms().all(|a| a.velocity().is_some());
+    for (index, atom) in pdb.atoms().enumerate() {
+        let mut line = format!(
+            "{:5}{:<5.5}{:>5.5}{:5}{:8.3}{:8.3}{:8.3}",
+            atom.res_seq() % 100000,
+            atom.res_name(),
+            atom.atom_name(),
+            (index + 1) % 100000,
+            atom.x() / NM,
+            atom.y() / NM,
+            atom.z() / NM,
+        );
+        if let Some(velocity) = atom.velocity().filter(|_| velocities) {
+            line.push_str(&format!("{:8.4}{:8.4}{:8.4}", velocity.x / NM, velocity.y / NM, velocity.z / NM));
+        }
+        writeln!(stream, "{}", line)?;
+    }
+    match pdb.unit_cell() {
+        Some(cell) => {
+            let [a, b, c] = cell.vectors();
+            let mut values = vec![a.x, b.y, c.z];
+            if !cell.is_rectangular() {
+                values.extend([a.y, a.z, b.x, b.z, c.x, c.y]);
+            }
+            let values: String = values.iter().map(|v| format!("{:10.5}", v / NM)).collect();
+            writeln!(stream, "{}", values)?;
+        },
+        None => writeln!(stream, "{:10.5}{:10.5}{:10.5}", 0.0, 0.0, 0.0)?,
+    }
+    stream.flush()?;
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WATER: &str = "\
+Water
+    3
+    1SOL     OW    1   0.126   1.624   1.679  0.1227 -0.0580  0.0434
+    1SOL    HW1    2   0.190   1.661   1.747  0.8085  0.3191 -0.7791
+    1SOL    HW2    3   0.177   1.568   1.613 -0.9045 -2.6469  1.3180
+   1.86206   1.86206   1.86206
+";
+
+    #[test]
+    fn can_read_gro() {
+        let pdb = read_gro_raw(BufReader::new(WATER.as_bytes())).unwrap();
+        assert_eq!(pdb.atom_count(), 3);
+        let oxygen = pdb.atom(0).unwrap();
+        assert_eq!(oxygen.atom_name(), "OW");
+        assert_eq!(oxygen.res_name(), "SOL");
+        assert!(oxygen.is_water());
+        assert_eq!(oxygen.element(), Some(Element::O));
+        assert!((oxygen.x() - 1.26).abs() < 1e-9);
+        assert!((oxygen.velocity().unwrap().y + 0.58).abs() < 1e-9);
+        let cell = pdb.unit_cell().unwrap();
+        assert!((cell.a - 18.6206).abs() < 1e-9);
+        assert!(cell.is_rectangular());
+
+        // higher precision files have wider columns
+        let precise = "t\n1\n    1SOL     OW    1   0.12600   1.62400   1.67900\n0 0 0\n";
+        let pdb = read_gro_raw(BufReader::new(precise.as_bytes())).unwrap();
+        assert!((pdb.atom(0).unwrap().z() - 16.79).abs() < 1e-9);
+        assert!(pdb.atom(0).unwrap().velocity().is_none());
+        assert!(pdb.unit_cell().is_none());
+
+        // force field residue names are protein residues
+        let protein = "t\n2\n    1NALA     N    1   0.100   0.100   0.100\n    2HIE     CA    2   0.200   0.100   0.100\n0 0 0\n";
+        let pdb = read_gro_raw(BufReader::new(protein.as_bytes())).unwrap();
+        assert!(pdb.atoms().all(|a| !*a.hetero()));
+    }
+
+    #[test]
+    fn gro_round_trip() {
+        let pdb = read_gro_raw(BufReader::new(WATER.as_bytes())).unwrap();
+        let mut output = Vec::new();
+        save_gro_raw(&pdb, BufWriter::new(&mut output)).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        assert_eq!(text.lines().skip(1).collect::<Vec<_>>(), WATER.lines().skip(1).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,213 @@
+use crate::structs::{PDB, Atom, Element};
+use crate::spatial::SpatialIndex;
+
+/// Maximum distance (Å) of a hydrogen or antecedent atom to the heavy atom it is bonded to
+const MAX_BOND_LENGTH: f64 = 1.9;
+const MAX_HYDROGEN_BOND_LENGTH: f64 = 1.2;
+
+/// Cutoffs of the hydrogen bond detection
+#[derive(Debug, Clone, PartialEq)]
+pub struct HBondParameters {
+    /// Maximum donor-acceptor distance (Å)
+    pub max_distance: f64,
+    /// Minimum donor-acceptor distance (Å), closer pairs are considered bonded
+    pub min_distance: f64,
+    /// Maximum hydrogen-acceptor distance (Å) if hydrogens are present
+    pub max_hydrogen_distance: f64,
+    /// Minimum donor-hydrogen-acceptor angle (degrees) if hydrogens are present
+    pub min_angle: f64,
+    /// Minimum antecedent-donor-acceptor angle (degrees) without hydrogens
+    pub min_antecedent_angle: f64,
+    /// Whether explicit hydrogens are used when a donor has them
+    pub use_hydrogens: bool,
+}
+
+impl Default for HBondParameters {
+    fn default() -> Self {
+        HBondParameters {
+            max_distance: 3.5,
+            min_distance: 2.5,
+            max_hydrogen_distance: 2.5,
+            min_angle: 120.0,
+            min_antecedent_angle: 90.0,
+            use_hydrogens: true,
+        }
+    }
+}
+
+/// Which parts of the residues take part in a hydrogen bond
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HBondKind {
+    BackboneBackbone,
+    BackboneSidechain,
+    SidechainSidechain,
+    /// A hetero group (ligand or water) is the donor or the acceptor
+    Ligand,
+}
+
+/// A hydrogen bond, atoms are given by their index in the `PDB`
+#[derive(Debug, Clone, PartialEq)]
+pub struct HydrogenBond {
+    pub donor: usize,
+    /// The hydrogen used, `None` if the bond was found from heavy atoms only
+    pub hydrogen: Option<usize>,
+    pub acceptor: usize,
+    /// The donor-acceptor distance (Å)
+    pub distance: f64,
+    /// The donor-hydrogen-acceptor angle, or the antecedent-donor-acceptor angle
+    /// without hydrogen (degrees), `None` if no antecedent was found
+    pub angle: Option<f64>,
+    pub kind: HBondKind,
+}
+
+/// Whether the atom can donate a hydrogen bond, judged by residue and atom name.
+/// Nitrogen and oxygen atoms of hetero groups are treated as donors and acceptors.
+pub fn is_donor(atom: &Atom) -> bool {
+    if *atom.hetero() || atom.is_water() {
+        return is_polar(atom);
+    }
+    match (atom.res_name().as_str(), atom.atom_name().as_str()) {
+        ("PRO", "N") => false,
+        (_, "N") => true,
+        ("ARG", "NE") | ("ARG", "NH1") | ("ARG", "NH2") => true,
+        ("ASN", "ND2") | ("GLN", "NE2") | ("LYS", "NZ") | ("TRP", "NE1") => true,
+        ("HIS", "ND1") | ("HIS", "NE2") => true,
+        ("SER", "OG") | ("THR", "OG1") | ("TYR", "OH") | ("CYS", "SG") => true,
+        _ => false,
+    }
+}
+
+/// Whether the atom can accept a hydrogen bond, judged by residue and atom name.
+/// Nitrogen and oxygen atoms of hetero groups are treated as donors and acceptors.
+pub fn is_acceptor(atom: &Atom) -> bool {
+    if *atom.hetero() || atom.is_water() {
+        return is_polar(atom);
+    }
+    matches!(
+        (atom.res_name().as_str(), atom.atom_name().as_str()),
+        (_, "O") | (_, "OXT")
+            | ("ASP", "OD1") | ("ASP", "OD2") | ("GLU", "OE1") | ("GLU", "OE2")
+            | ("ASN", "OD1") | ("GLN", "OE1")
+            | ("HIS", "ND1") | ("HIS", "NE2")
+            | ("SER", "OG") | ("THR", "OG1") | ("TYR", "OH") | ("MET", "SD")
+    )
+}
+
+fn is_polar(atom: &Atom) -> bool {
+    matches!(atom.inferred_element(), Some(Element::N) | Some(Element::O))
+}
+
+/// Finds the hydrogen bonds of a structure.
+/// A donor with explicit hydrogens is judged by the hydrogen-acceptor distance and the
+/// donor-hydrogen-acceptor angle, otherwise by the donor-acceptor distance and the angle
+/// at the donor to its bonded heavy atom.
+pub fn hydrogen_bonds(pdb: &PDB, parameters: &HBondParameters) -> Vec<HydrogenBond> {
+    let index = SpatialIndex::new(pdb);
+    let atom = |i: usize| pdb.atom(i).unwrap();
+    let mut bonds = Vec::new();
+
+    for (d, donor) in pdb.atoms().enumerate().filter(|(_, a)| is_donor(a)) {
+        let neighbors = index.within_atom(donor, MAX_BOND_LENGTH);
+        let hydrogens: Vec<usize> = neighbors
+            .iter()
+            .copied()
+            .filter(|i| atom(*i).is_hydrogen() && atom(*i).distance(donor) < MAX_HYDROGEN_BOND_LENGTH)
+            .collect();
+        let antecedent = neighbors
+            .iter()
+            .copied()
+            .find(|i| *i != d && !atom(*i).is_hydrogen() && atom(*i).residue_id() == donor.residue_id());
+
+        for a in index.within_atom(donor, parameters.max_distance) {
+            let acceptor = atom(a);
+            if a == d || !is_acceptor(acceptor) || acceptor.residue_id() == donor.residue_id() {
+                continue;
+            }
+            let distance = donor.distance(acceptor);
+            if distance < parameters.min_distance {
+                continue;
+            }
+            let (hydrogen, angle) = if parameters.use_hydrogens && !hydrogens.is_empty() {
+                let best = hydrogens
+                    .iter()
+                    .map(|h| (*h, atom(*h).distance(acceptor), Atom::angle(donor, atom(*h), acceptor)))
+                    .filter(|(_, h_distance, angle)| *h_distance <= parameters.max_hydrogen_distance && *angle >= parameters.min_angle)
+                    .max_by(|a, b| a.2.total_cmp(&b.2));
+                match best {
+                    Some((h, _, angle)) => (Some(h), Some(angle)),
+                    None => continue,
+                }
+            } else {
+                let angle = antecedent.map(|x| Atom::angle(atom(x), donor, acceptor));
+                if angle.map(|angle| angle < parameters.min_antecedent_angle).unwrap_or(false) {
+                    continue;
+                }
+                (None, angle)
+            };
+            bonds.push(HydrogenBond {
+                donor: d,
+                hydrogen,
+                acceptor: a,
+                distance,
+                angle,
+                kind: kind(donor, acceptor),
+            });
+        }
+    }
+    bonds
+}
+
+fn kind(donor: &Atom, acceptor: &Atom) -> HBondKind {
+    let ligand = |a: &Atom| *a.hetero() || a.is_water();
+    if ligand(donor) || ligand(acceptor) {
+        HBondKind::Ligand
+    } else {
+        match (donor.is_backbone(), acceptor.is_backbone()) {
+            (true, true) => HBondKind::BackboneBackbone,
+            (false, false) => HBondKind::SidechainSidechain,
+            _ => HBondKind::BackboneSidechain,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::peptide;
+
+    #[test]
+    fn helix_backbone_hydrogen_bonds() {
+        let pdb = peptide(&[("ALA", -57.0, -47.0); 10]);
+        let bonds = hydrogen_bonds(&pdb, &HBondParameters::default());
+        // N-H of residue i + 4 to O of residue i
+        let pairs: Vec<(usize, usize)> = bonds
+            .iter()
+            .filter(|b| b.kind == HBondKind::BackboneBackbone)
+            .map(|b| (*pdb.atom(b.donor).unwrap().res_seq(), *pdb.atom(b.acceptor).unwrap().res_seq()))
+            .collect();
+        assert!(pairs.contains(&(5, 1)), "{:?}", pairs);
+        assert!(pairs.contains(&(10, 6)), "{:?}", pairs);
+        assert!(bonds.iter().all(|b| b.hydrogen.is_none()));
+    }
+
+    #[test]
+    fn explicit_hydrogen_decides() {
+        let mut pdb = PDB::new();
+        let mut add = |serial, name: &str, res: &str, seq, x, y, element: &str| {
+            pdb.add_atom(Atom::new(false, serial, name, res, "A", seq, x, y, 0.0, 1.0, 0.0, element, 0).unwrap());
+        };
+        add(1, "CA", "GLY", 1, -1.45, 0.0, "C");
+        add(2, "N", "GLY", 1, 0.0, 0.0, "N");
+        add(3, "H", "GLY", 1, 1.0, 0.0, "H");
+        add(4, "O", "GLY", 5, 2.9, 0.0, "O");
+        add(5, "O", "GLY", 9, 0.3, 2.9, "O");
+
+        let bonds = hydrogen_bonds(&pdb, &HBondParameters::default());
+        assert_eq!(bonds.len(), 1);
+        assert_eq!((bonds[0].donor, bonds[0].hydrogen, bonds[0].acceptor), (1, Some(2), 3));
+
+        let heavy_only = HBondParameters { use_hydrogens: false, ..HBondParameters::default() };
+        assert_eq!(hydrogen_bonds(&pdb, &heavy_only).len(), 2);
+    }
+}
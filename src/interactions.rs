@@ -0,0 +1,403 @@
+use crate::structs::{PDB, Atom, Element, ResidueId};
+use crate::geometry::Vector3;
+use crate::spatial::SpatialIndex;
+use crate::bonds::{perceive, BondParameters, MolecularGraph, Ring, RingSet};
+use crate::hbond::{hydrogen_bonds, is_acceptor, HBondParameters};
+
+/// Minimum length (Å) of a single bond from a nitrogen, shorter bonds indicate an sp2 nitrogen
+const MIN_AMINE_BOND_LENGTH: f64 = 1.43;
+/// Largest ring (atoms) searched for aromatic rings
+const MAX_RING_SIZE: usize = 8;
+
+/// Cutoffs of the ligand interaction profiler, distances in Å and angles in degrees
+#[derive(Debug, Clone, PartialEq)]
+pub struct InteractionParameters {
+    pub hydrogen_bond: HBondParameters,
+    /// Maximum distance between the centers of two opposite charges
+    pub salt_bridge_distance: f64,
+    /// Maximum distance between two hydrophobic carbons
+    pub hydrophobic_distance: f64,
+    /// Maximum distance between the centers of two aromatic rings
+    pub pi_stacking_distance: f64,
+    /// Maximum angle between the planes of face to face stacked rings
+    pub pi_parallel_angle: f64,
+    /// Minimum angle between the planes of edge to face (T-shaped) stacked rings
+    pub pi_t_angle: f64,
+    /// Maximum distance of a ring center or cation from the normal through the other ring center
+    pub pi_offset: f64,
+    /// Maximum distance between a cation and the center of an aromatic ring
+    pub cation_pi_distance: f64,
+    /// Maximum halogen-acceptor distance
+    pub halogen_distance: f64,
+    /// Minimum carbon-halogen-acceptor angle
+    pub halogen_angle: f64,
+    /// Maximum metal-ligand distance
+    pub metal_distance: f64,
+}
+
+impl Default for InteractionParameters {
+    fn default() -> Self {
+        InteractionParameters {
+            hydrogen_bond: HBondParameters::default(),
+            salt_bridge_distance: 5.5,
+            hydrophobic_distance: 4.0,
+            pi_stacking_distance: 5.5,
+            pi_parallel_angle: 30.0,
+            pi_t_angle: 60.0,
+            pi_offset: 2.0,
+            cation_pi_distance: 6.0,
+            halogen_distance: 4.0,
+            halogen_angle: 140.0,
+            metal_distance: 3.0,
+        }
+    }
+}
+
+/// The type of a non-covalent interaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InteractionKind {
+    /// A hydrogen bond, the ligand provides the donor if `ligand_donor`
+    HydrogenBond { ligand_donor: bool },
+    SaltBridge,
+    /// Face to face stacking if `parallel`, edge to face (T-shaped) otherwise
+    PiStacking { parallel: bool },
+    /// The cation belongs to the ligand if `ligand_cation`, the aromatic ring otherwise
+    CationPi { ligand_cation: bool },
+    Hydrophobic,
+    HalogenBond,
+    MetalCoordination,
+}
+
+/// An interaction between a ligand and a surrounding residue, atoms are given by their index in the `PDB`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interaction {
+    pub kind: InteractionKind,
+    /// The ligand atoms taking part, several for rings and charged groups
+    pub ligand_atoms: Vec<usize>,
+    pub residue: ResidueId,
+    pub res_name: String,
+    /// The residue atoms taking part, several for rings and charged groups
+    pub residue_atoms: Vec<usize>,
+    /// The distance (Å) between the atoms or between the centers of the groups
+    pub distance: f64,
+    /// The angle (degrees) characterizing the interaction if it has one: the hydrogen bond angle,
+    /// the angle between the ring planes for pi stacking and the C-X...A angle for halogen bonds
+    pub angle: Option<f64>,
+}
+
+/// The interactions of one ligand
+#[derive(Debug, Clone, PartialEq)]
+pub struct LigandInteractions {
+    pub ligand: ResidueId,
+    pub res_name: String,
+    /// The atoms of the ligand by their index in the `PDB`
+    pub atoms: Vec<usize>,
+    pub interactions: Vec<Interaction>,
+}
+
+impl LigandInteractions {
+    /// The interactions of the given kind
+    pub fn of_kind(&self, kind: InteractionKind) -> impl Iterator<Item = &Interaction> + '_ {
+        self.interactions.iter().filter(move |i| i.kind == kind)
+    }
+}
+
+/// A group of atoms carrying a formal charge
+struct ChargedGroup {
+    atoms: Vec<usize>,
+    center: Vector3,
+    positive: bool,
+}
+
+impl ChargedGroup {
+    fn new(atoms: Vec<usize>, positive: bool, coords: &[Vector3]) -> ChargedGroup {
+        let center = atoms.iter().fold(Vector3::zero(), |sum, i| sum + coords[*i]) / atoms.len() as f64;
+        ChargedGroup { atoms, center, positive }
+    }
+}
+
+/// Profiles the non-covalent interactions of every ligand with the surrounding residues.
+/// A ligand is a residue made of hetero atoms that is neither water nor a lone metal ion,
+/// its partners are all atoms outside of the ligand except water.
+/// The covalent structure of the ligands (rings, charged groups and hydrophobic carbons)
+/// is taken from the bonds perceived with the default `BondParameters`.
+pub fn ligand_interactions(pdb: &PDB, parameters: &InteractionParameters) -> Vec<LigandInteractions> {
+    let atoms: Vec<&Atom> = pdb.atoms().collect();
+    let coords: Vec<Vector3> = atoms.iter().map(|a| a.coords()).collect();
+    let elements: Vec<Option<Element>> = atoms.iter().map(|a| a.inferred_element()).collect();
+    let is_metal = |i: usize| elements[i].is_some_and(|e| e.is_metal());
+    let index = SpatialIndex::new(pdb);
+    let graph = perceive(pdb, &index, &BondParameters::default(), None);
+    let heavy_neighbors = |i: usize| graph.neighbors(i).iter().copied().filter(|j| !atoms[*j].is_hydrogen());
+
+    let residues = pdb.residue_indices();
+    let ring_set = RingSet::new(pdb, &graph, MAX_RING_SIZE);
+    let aromatic_rings: Vec<&Ring> = ring_set.rings().filter(|r| r.aromatic).collect();
+    let mut protein_charges = Vec::new();
+    for (_, indices) in residues.iter() {
+        let find = |name: &str| indices.iter().copied().find(|i| atoms[*i].atom_name() == name);
+        let group = |names: &[&str]| names.iter().map(|n| find(n)).collect::<Option<Vec<usize>>>();
+        let res_name = atoms[indices[0]].res_name().as_str();
+        if let Some((names, positive)) = residue_charge(res_name) {
+            if let Some(group) = group(names) {
+                protein_charges.push(ChargedGroup::new(group, positive, &coords));
+            }
+        }
+    }
+    let hbonds = hydrogen_bonds(pdb, &parameters.hydrogen_bond);
+
+    let mut result = Vec::new();
+    for (ligand, indices) in residues.iter() {
+        let first = atoms[indices[0]];
+        if !indices.iter().all(|i| *atoms[*i].hetero()) || first.is_water() || indices.iter().all(|i| is_metal(*i)) {
+            continue;
+        }
+        let in_ligand = |i: usize| indices.contains(&i);
+        let is_partner = |i: usize| !in_ligand(i) && !atoms[i].is_water();
+        let mut interactions = Vec::new();
+        let mut add = |kind, ligand_atoms: Vec<usize>, residue_atoms: Vec<usize>, distance, angle| {
+            let partner = atoms[residue_atoms[0]];
+            interactions.push(Interaction {
+                kind,
+                ligand_atoms,
+                residue: partner.residue_id(),
+                res_name: partner.res_name().to_owned(),
+                residue_atoms,
+                distance,
+                angle,
+            });
+        };
+
+        for hbond in hbonds.iter() {
+            if in_ligand(hbond.donor) && is_partner(hbond.acceptor) {
+                add(InteractionKind::HydrogenBond { ligand_donor: true }, vec![hbond.donor], vec![hbond.acceptor], hbond.distance, hbond.angle);
+            } else if in_ligand(hbond.acceptor) && is_partner(hbond.donor) {
+                add(InteractionKind::HydrogenBond { ligand_donor: false }, vec![hbond.acceptor], vec![hbond.donor], hbond.distance, hbond.angle);
+            }
+        }
+
+        let rings: Vec<&Ring> = ring_set.of_atoms(indices).filter(|r| r.aromatic).collect();
+        let partner_rings = || aromatic_rings.iter().filter(|r| is_partner(r.atoms[0]));
+        let charges = ligand_charges(indices, &atoms, &elements, &coords, &graph, &ring_set);
+
+        for charge in charges.iter() {
+            for other in protein_charges.iter().filter(|g| g.positive != charge.positive && is_partner(g.atoms[0])) {
+                let distance = charge.center.distance(&other.center);
+                if distance <= parameters.salt_bridge_distance {
+                    add(InteractionKind::SaltBridge, charge.atoms.clone(), other.atoms.clone(), distance, None);
+                }
+            }
+        }
+
+        for ring in rings.iter() {
+            for other in partner_rings() {
+                let distance = ring.center.distance(&other.center);
+                if distance > parameters.pi_stacking_distance {
+                    continue;
+                }
+                let angle = ring.normal.dot(&other.normal).abs().min(1.0).acos().to_degrees();
+                let offset = ring.offset(&other.center).min(other.offset(&ring.center));
+                let parallel = angle <= parameters.pi_parallel_angle;
+                if offset <= parameters.pi_offset && (parallel || angle >= parameters.pi_t_angle) {
+                    add(InteractionKind::PiStacking { parallel }, ring.atoms.clone(), other.atoms.clone(), distance, Some(angle));
+                }
+            }
+        }
+
+        let cation_pi = |ring: &Ring, cation: &ChargedGroup| {
+            let distance = ring.center.distance(&cation.center);
+            (distance <= parameters.cation_pi_distance && ring.offset(&cation.center) <= parameters.pi_offset).then_some(distance)
+        };
+        for ring in rings.iter() {
+            for cation in protein_charges.iter().filter(|g| g.positive && is_partner(g.atoms[0])) {
+                if let Some(distance) = cation_pi(ring, cation) {
+                    add(InteractionKind::CationPi { ligand_cation: false }, ring.atoms.clone(), cation.atoms.clone(), distance, None);
+                }
+            }
+        }
+        for cation in charges.iter().filter(|g| g.positive) {
+            for ring in partner_rings() {
+                if let Some(distance) = cation_pi(ring, cation) {
+                    add(InteractionKind::CationPi { ligand_cation: true }, cation.atoms.clone(), ring.atoms.clone(), distance, None);
+                }
+            }
+        }
+
+        let hydrophobic = |i: usize| elements[i] == Some(Element::C) && heavy_neighbors(i).all(|j| elements[j] == Some(Element::C));
+        for l in indices.iter().copied().filter(|l| hydrophobic(*l)) {
+            // only the closest contact of each ligand carbon with each residue
+            let mut closest: Vec<(ResidueId, usize, f64)> = Vec::new();
+            for p in index.within(&coords[l], parameters.hydrophobic_distance) {
+                if !is_partner(p) || !hydrophobic(p) {
+                    continue;
+                }
+                let distance = coords[l].distance(&coords[p]);
+                let residue = atoms[p].residue_id();
+                match closest.iter_mut().find(|(r, _, _)| *r == residue) {
+                    Some(entry) if entry.2 > distance => *entry = (residue, p, distance),
+                    Some(_) => (),
+                    None => closest.push((residue, p, distance)),
+                }
+            }
+            for (_, p, distance) in closest {
+                add(InteractionKind::Hydrophobic, vec![l], vec![p], distance, None);
+            }
+        }
+
+        for x in indices.iter().copied().filter(|x| elements[*x].is_some_and(|e| e.is_halogen())) {
+            let carbon = match heavy_neighbors(x).find(|c| elements[*c] == Some(Element::C)) {
+                Some(carbon) => carbon,
+                None => continue,
+            };
+            for a in index.within(&coords[x], parameters.halogen_distance) {
+                if !is_partner(a) || !is_acceptor(atoms[a]) {
+                    continue;
+                }
+                let angle = Atom::angle(atoms[carbon], atoms[x], atoms[a]);
+                if angle >= parameters.halogen_angle {
+                    add(InteractionKind::HalogenBond, vec![x], vec![a], coords[x].distance(&coords[a]), Some(angle));
+                }
+            }
+        }
+
+        let coordinating = |i: usize| matches!(elements[i], Some(Element::N) | Some(Element::O) | Some(Element::S));
+        for l in indices.iter().copied() {
+            for p in index.within(&coords[l], parameters.metal_distance) {
+                let metal_pair = (is_metal(l) && coordinating(p)) || (coordinating(l) && is_metal(p));
+                if is_partner(p) && metal_pair {
+                    add(InteractionKind::MetalCoordination, vec![l], vec![p], coords[l].distance(&coords[p]), None);
+                }
+            }
+        }
+
+        result.push(LigandInteractions {
+            ligand: ligand.clone(),
+            res_name: first.res_name().to_owned(),
+            atoms: indices.clone(),
+            interactions,
+        });
+    }
+    result
+}
+
+/// The charged side chain group of a standard amino acid and whether it is positive
+fn residue_charge(res_name: &str) -> Option<(&'static [&'static str], bool)> {
+    match res_name {
+        "LYS" => Some((&["NZ"], true)),
+        "ARG" => Some((&["NE", "NH1", "NH2"], true)),
+        "HIS" => Some((&["ND1", "NE2"], true)),
+        "ASP" => Some((&["OD1", "OD2"], false)),
+        "GLU" => Some((&["OE1", "OE2"], false)),
+        _ => None,
+    }
+}
+
+/// The charged groups of a ligand, from formal charges and common ionizable groups:
+/// carboxylates, phosphates, sulfonates, amidines, guanidines and aliphatic amines.
+fn ligand_charges(
+    indices: &[usize],
+    atoms: &[&Atom],
+    elements: &[Option<Element>],
+    coords: &[Vector3],
+    graph: &MolecularGraph,
+    rings: &RingSet,
+) -> Vec<ChargedGroup> {
+    let heavy = |i: usize| -> Vec<usize> { graph.neighbors(i).iter().copied().filter(|j| !atoms[*j].is_hydrogen()).collect() };
+    let terminal_oxygens = |i: usize| -> Vec<usize> {
+        heavy(i).into_iter().filter(|o| elements[*o] == Some(Element::O) && heavy(*o).len() == 1).collect()
+    };
+    let mut groups = Vec::new();
+    for i in indices.iter().copied() {
+        let neighbors = heavy(i);
+        match elements[i] {
+            Some(Element::C) => {
+                let oxygens = terminal_oxygens(i);
+                let nitrogens: Vec<usize> = neighbors.iter().copied().filter(|n| elements[*n] == Some(Element::N)).collect();
+                if oxygens.len() == 2 && neighbors.len() == 3 {
+                    groups.push(ChargedGroup::new(oxygens, false, coords));
+                } else if nitrogens.len() >= 2 && oxygens.is_empty() && neighbors.len() == 3 && !rings.in_ring(i) {
+                    groups.push(ChargedGroup::new(nitrogens, true, coords));
+                }
+            },
+            Some(Element::P) | Some(Element::S) => {
+                let oxygens = terminal_oxygens(i);
+                if oxygens.len() >= 3 {
+                    groups.push(ChargedGroup::new(oxygens, false, coords));
+                }
+            },
+            Some(Element::N) => {
+                let amine = !neighbors.is_empty()
+                    && neighbors.iter().all(|c| elements[*c] == Some(Element::C) && coords[i].distance(&coords[*c]) >= MIN_AMINE_BOND_LENGTH);
+                if amine {
+                    groups.push(ChargedGroup::new(vec![i], true, coords));
+                }
+            },
+            _ => (),
+        }
+    }
+    for i in indices.iter().copied() {
+        let charge = *atoms[i].charge();
+        if charge != 0 && !groups.iter().any(|g| g.atoms.contains(&i)) {
+            groups.push(ChargedGroup::new(vec![i], charge > 0, coords));
+        }
+    }
+    groups
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::atom;
+
+    fn hexagon(z: f64) -> Vec<(f64, f64, f64)> {
+        (0..6)
+            .map(|k| {
+                let phi = (60.0 * k as f64).to_radians();
+                (1.39 * phi.cos(), 1.39 * phi.sin(), z)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stacked_benzene() {
+        let mut pdb = PDB::new();
+        let names = ["CG", "CD1", "CE1", "CZ", "CE2", "CD2"];
+        for (k, (name, position)) in names.iter().zip(hexagon(0.0)).enumerate() {
+            pdb.add_atom(atom(k + 1, name, "PHE", 1, position, "C"));
+        }
+        for (k, position) in hexagon(3.7).into_iter().enumerate() {
+            pdb.add_atom(atom(k + 7, &format!("C{}", k + 1), "BNZ", 100, position, "C"));
+        }
+        let profile = ligand_interactions(&pdb, &InteractionParameters::default());
+        assert_eq!(profile.len(), 1);
+        let ligand = &profile[0];
+        assert_eq!(ligand.res_name, "BNZ");
+        let stacking: Vec<&Interaction> = ligand.of_kind(InteractionKind::PiStacking { parallel: true }).collect();
+        assert_eq!(stacking.len(), 1);
+        assert!((stacking[0].distance - 3.7).abs() < 1e-9);
+        assert!(stacking[0].angle.unwrap() < 1e-6);
+        assert_eq!(ligand.of_kind(InteractionKind::Hydrophobic).count(), 6);
+    }
+
+    #[test]
+    fn acetate_with_lysine_and_zinc() {
+        let mut pdb = PDB::new();
+        pdb.add_atom(atom(1, "NZ", "LYS", 1, (3.6, 2.07, 0.0), "N"));
+        pdb.add_atom(atom(2, "C1", "ACT", 100, (0.0, 0.0, 0.0), "C"));
+        pdb.add_atom(atom(3, "O1", "ACT", 100, (1.08, 0.62, 0.0), "O"));
+        pdb.add_atom(atom(4, "O2", "ACT", 100, (-1.08, 0.62, 0.0), "O"));
+        pdb.add_atom(atom(5, "C2", "ACT", 100, (0.0, -1.5, 0.0), "C"));
+        pdb.add_atom(atom(6, "ZN", "ZN", 200, (-2.82, 1.62, 0.0), "ZN"));
+
+        let profile = ligand_interactions(&pdb, &InteractionParameters::default());
+        assert_eq!(profile.len(), 1);
+        let ligand = &profile[0];
+        let kinds: Vec<InteractionKind> = ligand.interactions.iter().map(|i| i.kind).collect();
+        assert!(kinds.contains(&InteractionKind::HydrogenBond { ligand_donor: false }));
+        let salt_bridge = ligand.of_kind(InteractionKind::SaltBridge).next().unwrap();
+        assert_eq!((salt_bridge.ligand_atoms.clone(), salt_bridge.residue_atoms.clone()), (vec![2, 3], vec![0]));
+        let metal = ligand.of_kind(InteractionKind::MetalCoordination).next().unwrap();
+        assert_eq!((metal.ligand_atoms[0], metal.residue_atoms[0], metal.res_name.as_str()), (3, 5, "ZN"));
+    }
+}
@@ -1,7 +1,15 @@
 
+use crate::structs::{Helix, Strand, UnitCell};
+
 pub(crate) enum ParsedItems {
-    Header(String, String, String),
+    #[allow(dead_code)]
+    Header(
+        String, // classification
+        String, // deposition date
+        String, // id code
+    ),
     Remark(usize, String),
+    Cryst(UnitCell),
     Atom(
         bool, // hetero
         usize, // serial number
@@ -20,6 +28,15 @@ pub(crate) enum ParsedItems {
         String, // element
         isize, // charge
     ),
+    Helix(Helix),
+    Sheet(
+        String, // sheet id
+        Strand,
+    ),
+    Conect(
+        usize, // serial number
+        Vec<usize>, // bonded serial numbers
+    ),
     Ter,
     End,
     Empty,
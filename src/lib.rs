@@ -4,7 +4,47 @@ mod validator;
 mod structs;
 mod error;
 mod item;
+mod geometry;
+mod transform;
+mod superpose;
+mod compare;
+mod spatial;
+mod contacts;
+mod sasa;
+mod dssp;
+mod torsion;
+mod hbond;
+mod interactions;
+mod clash;
+mod bonds;
+mod ccd;
+mod sdf;
+mod mol2;
+mod pqr;
+mod gro;
+#[cfg(test)]
+mod testing;
 
 pub use read::read_pdb;
-pub use structs::{PDB, Atom};
-pub use save::{save_pdb, save_pdb_atom};
\ No newline at end of file
+pub use structs::{PDB, Atom, Element, ResidueId, UnitCell, PocketCenter, SecondaryStructure, Helix, HelixClass, Sheet, Strand, StrandSense, Registration};
+pub use save::{save_pdb, save_pdb_atom};
+pub use error::PDBError;
+pub use geometry::{Vector3, angle, dihedral};
+pub use transform::{Quaternion, Transform};
+pub use superpose::{superpose, pair_atoms, kabsch, kabsch_weighted, rmsd, AtomPairing, Superposition};
+pub use compare::{tm_score, gdt, lddt, TmScore, Gdt, Lddt};
+pub use spatial::SpatialIndex;
+pub use contacts::{contact_map, interfaces, ContactMode, Contact, ContactMap, Interface, InterfaceResidue};
+pub use sasa::{sasa, max_asa, Sasa, SasaParameters, ResidueSasa};
+pub use dssp::{dssp, assign_secondary_structure, Dssp};
+pub use torsion::{torsions, ramachandran_region, chi_atoms, ResidueTorsions, RamachandranClass, RamachandranRegion};
+pub use hbond::{hydrogen_bonds, is_donor, is_acceptor, HBondParameters, HBondKind, HydrogenBond};
+pub use interactions::{ligand_interactions, Interaction, InteractionKind, InteractionParameters, LigandInteractions};
+pub use clash::{clashes, Clash, ClashParameters, ClashReport};
+pub use bonds::{perceive_bonds, perceive_bonds_with_components, Bond, BondOrder, BondSource, BondParameters, MolecularGraph, Ring, RingSet};
+pub use ccd::{read_ccd, ChemicalComponent, ComponentAtom, ComponentBond, ComponentDictionary};
+pub use sdf::{save_sdf, save_sdf_with_bonds, save_ligands_sdf, save_ligands_sdf_with_bonds};
+pub use mol2::{read_mol2, save_mol2, save_mol2_with_bonds};
+pub use pqr::{read_pqr, save_pqr};
+pub use gro::{read_gro, save_gro};
+pub use validator::{validate_geometry, validate_stereochemistry, GeometryKind, GeometryOutlier, ResidueGeometry, ResidueStereochemistry};
@@ -0,0 +1,399 @@
+use anyhow::{anyhow, ensure, Context as _, Result};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufReader, BufWriter};
+
+use crate::structs::{PDB, Atom, Element};
+use crate::structs::atom::is_protein_residue;
+use crate::bonds::{perceive_bonds, Bond, BondOrder, BondParameters, BondSource, MolecularGraph, RingSet};
+
+/// Largest ring searched when typing aromatic atoms and bonds
+const MAX_RING_SIZE: usize = 8;
+
+/// An atom line of a MOL2 file, kept until the substructures are known
+struct Mol2Atom {
+    serial_number: usize,
+    atom_name: String,
+    position: (f64, f64, f64),
+    atom_type: String,
+    subst_id: usize,
+    subst_name: String,
+    charge: Option<f64>,
+    line_number: usize,
+}
+
+/// The records of one molecule of a MOL2 file
+#[derive(Default)]
+struct Mol2Molecule {
+    molecule: Vec<String>,
+    atoms: Vec<Mol2Atom>,
+    bonds: Vec<(usize, usize, BondOrder, usize)>,
+    /// substructure id to its chain
+    chains: HashMap<usize, String>,
+}
+
+/// Reads all molecules of a Tripos MOL2 file, e.g. the poses written by a docking program.
+/// Substructures become residues and the Sybyl atom types and partial charges are kept on the atoms.
+/// The bonds of each molecule are returned with their order as a `MolecularGraph` and also
+/// stored as CONECT records. Amide bonds become single bonds and "nc" (not connected) bonds
+/// are left out.
+pub fn read_mol2(filename: &str) -> Result<Vec<(PDB, MolecularGraph)>> {
+    let f = File::open(filename).with_context(|| format!("could not open {}", filename))?;
+    read_mol2_raw(BufReader::new(f))
+}
+
+pub fn read_mol2_raw<T>(input: BufReader<T>) -> Result<Vec<(PDB, MolecularGraph)>>
+    where T: std::io::Read
+{
+    let mut section = String::new();
+    let mut molecules: Vec<Mol2Molecule> = Vec::new();
+
+    for (mut line_number, read_line) in input.lines().enumerate() {
+        line_number += 1;
+        let line = read_line.with_context(|| format!("could not read line {}", line_number))?;
+        let trimmed = line.trim();
+        if let Some(name) = trimmed.strip_prefix("@<TRIPOS>") {
+            if name == "MOLECULE" {
+                molecules.push(Mol2Molecule::default());
+            }
+            section = name.to_owned();
+            continue;
+        }
+        if trimmed.starts_with('#') || (trimmed.is_empty() && section != "MOLECULE") {
+            continue;
+        }
+        let current = match molecules.last_mut() {
+            Some(current) => current,
+            None => continue,
+        };
+        let fields: Vec<&str> = trimmed.split_whitespace().collect();
+        match section.as_str() {
+            "MOLECULE" => current.molecule.push(trimmed.to_owned()),
+            "ATOM" => {
+                ensure!(fields.len() >= 6, "atom record is too short at line {}", line_number);
+                let number = |field: &str| field.parse::<f64>().with_context(|| format!("can not parse '{}' at line {}", field, line_number));
+                current.atoms.push(Mol2Atom {
+                    serial_number: parse_usize(fields[0], line_number)?,
+                    atom_name: fields[1].to_owned(),
+                    position: (number(fields[2])?, number(fields[3])?, number(fields[4])?),
+                    atom_type: fields[5].to_owned(),
+                    subst_id: fields.get(6).map(|f| parse_usize(f, line_number)).transpose()?.unwrap_or(1),
+                    subst_name: fields.get(7).map(|f| f.to_string()).unwrap_or_else(|| "UNL".to_owned()),
+                    charge: fields.get(8).map(|f| number(f)).transpose()?,
+                    line_number,
+                });
+            },
+            "BOND" => {
+                ensure!(fields.len() >= 4, "bond record is too short at line {}", line_number);
+                let order = match fields[3].to_ascii_lowercase().as_str() {
+                    "nc" => continue,
+                    "2" => BondOrder::Double,
+                    "3" => BondOrder::Triple,
+                    "ar" => BondOrder::Aromatic,
+                    _ => BondOrder::Single,
+                };
+                current.bonds.push((parse_usize(fields[1], line_number)?, parse_usize(fields[2], line_number)?, order, line_number));
+            },
+            "SUBSTRUCTURE" => {
+                ensure!(fields.len() >= 3, "substructure record is too short at line {}", line_number);
+                if let Some(chain) = fields.get(5).filter(|c| !c.starts_with('*')) {
+                    current.chains.insert(parse_usize(fields[0], line_number)?, chain.to_string());
+                }
+            },
+            _ => (),
+        }
+    }
+    ensure!(!molecules.is_empty(), "no @<TRIPOS>MOLECULE record found");
+    molecules.into_iter().map(build_molecule).collect()
+}
+
+/// The `PDB` and bonds of the records of one molecule
+fn build_molecule(records: Mol2Molecule) -> Result<(PDB, MolecularGraph)> {
+    let mut pdb = PDB::new();
+    let name = records.molecule.first().map(|n| n.trim()).unwrap_or("");
+    if !name.is_empty() && !name.starts_with('*') {
+        pdb.set_identifier(name)?;
+    }
+    let charges = records.molecule.get(3).map(|c| !c.eq_ignore_ascii_case("NO_CHARGES")).unwrap_or(false);
+    let index: HashMap<usize, usize> = records.atoms.iter().enumerate().map(|(i, a)| (a.serial_number, i)).collect();
+    for atom in records.atoms {
+        let (res_name, res_seq) = split_substructure(&atom.subst_name);
+        let element = atom.atom_type.split('.').next().and_then(Element::from_symbol);
+        let mut new_atom = Atom::new(
+            !is_protein_residue(&res_name.to_ascii_uppercase()),
+            atom.serial_number,
+            &atom.atom_name,
+            &res_name,
+            records.chains.get(&atom.subst_id).map(|c| c.as_str()).unwrap_or("A"),
+            res_seq.unwrap_or(atom.subst_id),
+            atom.position.0,
+            atom.position.1,
+            atom.position.2,
+            1.0,
+            0.0,
+            element.map(|e| e.symbol()).unwrap_or(""),
+            0,
+        ).ok_or_else(|| anyhow!("invalid atom at line {}", atom.line_number))?;
+        new_atom.set_atom_type(&atom.atom_type)?;
+        if charges {
+            new_atom.set_partial_charge(atom.charge)?;
+        }
+        pdb.add_atom(new_atom);
+    }
+    let mut bonds = Vec::with_capacity(records.bonds.len());
+    for (a, b, order, line_number) in records.bonds.iter().copied() {
+        let atom = |serial: usize| index.get(&serial).copied().ok_or_else(|| anyhow!("bond to unknown atom {} at line {}", serial, line_number));
+        bonds.push(Bond { a: atom(a)?, b: atom(b)?, order, source: BondSource::Conect });
+    }
+    pdb.add_conects(records.bonds.iter().map(|(a, b, _, _)| (*a, *b)))?;
+    let graph = MolecularGraph::new(pdb.atom_count(), bonds);
+    Ok((pdb, graph))
+}
+
+/// Saves the `PDB` as a Tripos MOL2 file with one substructure per residue.
+/// Bonds are perceived with the default `BondParameters`, so CONECT records take precedence.
+/// Atoms without a Sybyl atom type are typed from their element and bonds.
+pub fn save_mol2(pdb: &PDB, filename: &str) -> Result<()> {
+    let graph = perceive_bonds(pdb, &BondParameters::default());
+    save_mol2_with_bonds(pdb, &graph, filename)
+}
+
+/// Saves the `PDB` as a Tripos MOL2 file with the bonds of `graph`, e.g. those read by `read_mol2`.
+pub fn save_mol2_with_bonds(pdb: &PDB, graph: &MolecularGraph, filename: &str) -> Result<()> {
+    let f = File::create(filename).with_context(|| format!("could not create {}", filename))?;
+    save_mol2_raw(pdb, graph, BufWriter::new(f))
+}
+
+pub(crate) fn save_mol2_raw<W: Write>(pdb: &PDB, graph: &MolecularGraph, mut stream: BufWriter<W>) -> Result<()> {
+    let rings = RingSet::new(pdb, graph, MAX_RING_SIZE);
+    let residues = pdb.residue_indices();
+    let protein = pdb.atoms().any(|a| is_protein_residue(a.res_name()));
+    let charges = pdb.atoms().any(|a| a.partial_charge().is_some());
+
+    writeln!(stream, "@<TRIPOS>MOLECULE")?;
+    writeln!(stream, "{}", pdb.identifier().map(|i| i.as_str()).unwrap_or("*****"))?;
+    writeln!(stream, "{:5} {:5} {:5} {:5} {:5}", pdb.atom_count(), graph.bond_count(), residues.len(), 0, 0)?;
+    writeln!(stream, "{}", if protein { "PROTEIN" } else { "SMALL" })?;
+    writeln!(stream, "{}", if charges { "USER_CHARGES" } else { "NO_CHARGES" })?;
+    writeln!(stream)?;
+
+    writeln!(stream, "@<TRIPOS>ATOM")?;
+    for (subst_id, (_, indices)) in residues.iter().enumerate() {
+        for index in indices {
+            let atom = pdb.atom(*index).unwrap();
+            writeln!(
+                stream,
+                "{:7} {:<8} {:10.4} {:10.4} {:10.4} {:<6} {:5} {:<8} {:9.4}",
+                index + 1,
+                atom.atom_name(),
+                atom.x(),
+                atom.y(),
+                atom.z(),
+                atom.atom_type().map(|t| t.to_owned()).unwrap_or_else(|| sybyl_type(pdb, graph, &rings, *index)),
+                subst_id + 1,
+                substructure_name(atom),
+                atom.partial_charge().unwrap_or(0.0),
+            )?;
+        }
+    }
+
+    writeln!(stream, "@<TRIPOS>BOND")?;
+    for (number, bond) in graph.bonds().enumerate() {
+        let aromatic = rings.containing(bond.a).any(|r| r.aromatic && r.contains(bond.b));
+        let order = match bond.order {
+            BondOrder::Aromatic => "ar",
+            _ if aromatic => "ar",
+            BondOrder::Single => "1",
+            BondOrder::Double => "2",
+            BondOrder::Triple => "3",
+        };
+        writeln!(stream, "{:6} {:5} {:5} {}", number + 1, bond.a + 1, bond.b + 1, order)?;
+    }
+
+    writeln!(stream, "@<TRIPOS>SUBSTRUCTURE")?;
+    for (subst_id, (id, indices)) in residues.iter().enumerate() {
+        let atom = pdb.atom(indices[0]).unwrap();
+        writeln!(
+            stream,
+            "{:6} {:<8} {:6} {:<8} {} {} {}",
+            subst_id + 1,
+            substructure_name(atom),
+            indices[0] + 1,
+            if is_protein_residue(atom.res_name()) { "RESIDUE" } else { "GROUP" },
+            1,
+            if id.chain_id.is_empty() { "****" } else { &id.chain_id },
+            atom.res_name(),
+        )?;
+    }
+    stream.flush()?;
+    Ok(())
+}
+
+/// The substructure name of the residue of an atom, e.g. "ALA12"
+fn substructure_name(atom: &Atom) -> String {
+    format!("{}{}{}", atom.res_name(), atom.res_seq(), atom.i_code().unwrap_or(""))
+}
+
+/// Splits a substructure name like "ALA12" into the residue name and number
+fn split_substructure(subst_name: &str) -> (String, Option<usize>) {
+    let digits = subst_name.len() - subst_name.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    let (name, number) = subst_name.split_at(subst_name.len() - digits);
+    match (name.is_empty(), number.parse().ok()) {
+        (true, _) => (subst_name.to_owned(), None),
+        (false, number) => (name.to_owned(), number),
+    }
+}
+
+/// A Sybyl atom type from the element, the bond orders and aromaticity around the atom
+fn sybyl_type(pdb: &PDB, graph: &MolecularGraph, rings: &RingSet, index: usize) -> String {
+    let element = match pdb.atom(index).and_then(|a| a.inferred_element()) {
+        Some(element) => element,
+        None => return "Du".to_owned(),
+    };
+    let has_order = |order: BondOrder| graph.atom_bonds(index).any(|b| b.order == order);
+    let suffix = match element {
+        Element::C | Element::N if rings.is_aromatic(index) || has_order(BondOrder::Aromatic) => "ar",
+        Element::C | Element::N if has_order(BondOrder::Triple) => "1",
+        Element::C | Element::N | Element::O | Element::S if has_order(BondOrder::Double) => "2",
+        Element::N if graph.degree(index) == 4 => "4",
+        Element::N if graph.neighbors(index).iter().any(|c| is_carbonyl(graph, pdb, *c)) => "am",
+        Element::C | Element::N | Element::O | Element::S | Element::P => "3",
+        _ => return element.symbol().to_owned(),
+    };
+    format!("{}.{}", element.symbol(), suffix)
+}
+
+/// Whether the atom is a carbon with a double bond to an oxygen
+fn is_carbonyl(graph: &MolecularGraph, pdb: &PDB, index: usize) -> bool {
+    let element = |i: usize| pdb.atom(i).and_then(|a| a.inferred_element());
+    element(index) == Some(Element::C)
+        && graph.atom_bonds(index).any(|b| {
+            b.order == BondOrder::Double && b.partner(index).and_then(element) == Some(Element::O)
+        })
+}
+
+fn parse_usize(field: &str, line_number: usize) -> Result<usize> {
+    field.parse::<usize>().with_context(|| format!("can not parse '{}' as an integer at line {}", field, line_number))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACETATE: &str = "\
+@<TRIPOS>MOLECULE
+ACT
+    4     3     1     0     0
+SMALL
+USER_CHARGES
+
+@<TRIPOS>ATOM
+      1 C1         0.0000     0.0000     0.0000 C.2        1 ACT301    0.7000
+      2 O1         1.0800     0.6200     0.0000 O.co2      1 ACT301   -0.8000
+      3 O2        -1.0800     0.6200     0.0000 O.co2      1 ACT301   -0.8000
+      4 C2         0.0000    -1.5000     0.0000 C.3        1 ACT301   -0.1000
+@<TRIPOS>BOND
+     1     1     2 ar
+     2     1     3 ar
+     3     1     4 1
+@<TRIPOS>SUBSTRUCTURE
+     1 ACT301        1 GROUP    1 B     ACT
+";
+
+    /// The fields of the lines of the BOND section
+    fn bond_section(text: &str) -> Vec<Vec<String>> {
+        text.lines()
+            .skip_while(|l| *l != "@<TRIPOS>BOND")
+            .skip(1)
+            .take_while(|l| !l.starts_with("@<TRIPOS>"))
+            .map(|l| l.split_whitespace().map(|f| f.to_owned()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn can_read_mol2() {
+        let (pdb, graph) = read_mol2_raw(BufReader::new(ACETATE.as_bytes())).unwrap().remove(0);
+        assert_eq!(graph.bond(0, 1).map(|b| b.order), Some(BondOrder::Aromatic));
+        assert_eq!(graph.bond(0, 3).map(|b| b.order), Some(BondOrder::Single));
+        assert_eq!(pdb.identifier().map(|i| i.as_str()), Some("ACT"));
+        assert_eq!(pdb.atom_count(), 4);
+        assert_eq!(pdb.conects().count(), 3);
+        let oxygen = pdb.atom(1).unwrap();
+        assert_eq!(oxygen.atom_type(), Some("O.co2"));
+        assert_eq!(oxygen.partial_charge(), Some(-0.8));
+        assert_eq!(oxygen.element(), Some(Element::O));
+        assert_eq!(oxygen.res_name(), "ACT");
+        assert_eq!(*oxygen.res_seq(), 301);
+        assert_eq!(oxygen.chain_id(), "B");
+        assert!(*oxygen.hetero());
+    }
+
+    #[test]
+    fn mol2_round_trip() {
+        let (pdb, graph) = read_mol2_raw(BufReader::new(ACETATE.as_bytes())).unwrap().remove(0);
+        let mut output = Vec::new();
+        save_mol2_raw(&pdb, &graph, BufWriter::new(&mut output)).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.contains("      4 C2           0.0000    -1.5000     0.0000 C.3        1 ACT301     -0.1000"));
+        assert!(text.contains("     1 ACT301        1 GROUP    1 B ACT"));
+
+        assert_eq!(bond_section(&text), bond_section(ACETATE));
+
+        let (again, again_graph) = read_mol2_raw(BufReader::new(text.as_bytes())).unwrap().remove(0);
+        assert_eq!(again.atom_count(), 4);
+        assert_eq!(again.conects().count(), 3);
+        assert_eq!(again_graph.bonds().collect::<Vec<_>>(), graph.bonds().collect::<Vec<_>>());
+        for (a, b) in pdb.atoms().zip(again.atoms()) {
+            assert_eq!(a, b);
+            assert_eq!(a.atom_type(), b.atom_type());
+            assert_eq!(a.partial_charge(), b.partial_charge());
+        }
+    }
+
+    #[test]
+    fn reads_all_molecules() {
+        let poses = format!("{}{}", ACETATE, ACETATE.replace("\nACT\n", "\nACT_2\n").replace("0.0000    -1.5000", "0.0000    -1.4000"));
+        let molecules = read_mol2_raw(BufReader::new(poses.as_bytes())).unwrap();
+        assert_eq!(molecules.len(), 2);
+        let (second, graph) = &molecules[1];
+        assert_eq!(second.identifier().map(|i| i.as_str()), Some("ACT_2"));
+        assert_eq!(second.atom_count(), 4);
+        assert_eq!(*second.atom(3).unwrap().y(), -1.4);
+        assert_eq!(graph.bond_count(), 3);
+    }
+
+    #[test]
+    fn keeps_bond_orders() {
+        // acetonitrile, the bond lengths alone would not give the triple bond
+        let input = "\
+@<TRIPOS>MOLECULE
+ACN
+    3     2     1     0     0
+SMALL
+NO_CHARGES
+
+@<TRIPOS>ATOM
+      1 C1         0.0000     0.0000     0.0000 C.3        1 ACN1      0.0000
+      2 C2         1.4600     0.0000     0.0000 C.1        1 ACN1      0.0000
+      3 N1         2.6200     0.0000     0.0000 N.1        1 ACN1      0.0000
+@<TRIPOS>BOND
+     1     1     2 1
+     2     2     3 3
+";
+        let (pdb, graph) = read_mol2_raw(BufReader::new(input.as_bytes())).unwrap().remove(0);
+        assert_eq!(graph.bond(1, 2).map(|b| b.order), Some(BondOrder::Triple));
+        let mut output = Vec::new();
+        save_mol2_raw(&pdb, &graph, BufWriter::new(&mut output)).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        assert_eq!(bond_section(&text), bond_section(input));
+    }
+
+    #[test]
+    fn can_split_substructure() {
+        assert_eq!(split_substructure("ALA12"), ("ALA".to_owned(), Some(12)));
+        assert_eq!(split_substructure("LIG"), ("LIG".to_owned(), None));
+        assert_eq!(split_substructure("42"), ("42".to_owned(), None));
+    }
+}
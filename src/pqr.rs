@@ -0,0 +1,182 @@
+use anyhow::{anyhow, ensure, Context as _, Result};
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufReader, BufWriter};
+
+use crate::structs::{PDB, Atom, Element};
+
+/// Reads a PQR file, as written by PDB2PQR.
+/// The fields of the atom records are whitespace-delimited, the chain identifier is optional and
+/// the insertion code may be a separate field or follow the residue number.
+/// The charge and radius columns, in place of occupancy and B-factor, are kept as the
+/// partial charge and radius of the atoms.
+pub fn read_pqr(filename: &str) -> Result<PDB> {
+    let f = File::open(filename).with_context(|| format!("could not open {}", filename))?;
+    read_pqr_raw(BufReader::new(f))
+}
+
+pub fn read_pqr_raw<T>(input: BufReader<T>) -> Result<PDB>
+    where T: std::io::Read
+{
+    let mut pdb = PDB::new();
+    for (mut line_number, read_line) in input.lines().enumerate() {
+        line_number += 1;
+        let line = read_line.with_context(|| format!("could not read line {}", line_number))?;
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let hetero = match fields.first() {
+            Some(&"ATOM") => false,
+            Some(&"HETATM") => true,
+            _ => continue,
+        };
+        pdb.add_atom(parse_atom(&fields, hetero, line_number)?);
+    }
+    Ok(pdb)
+}
+
+/// Parses the fields of an atom record:
+/// record, serial, name, residue name, [chain], residue number, [insertion code], x, y, z, charge and radius
+fn parse_atom(fields: &[&str], hetero: bool, line_number: usize) -> Result<Atom> {
+    // a separate insertion code is a letter where a residue number or coordinate would be
+    let separate_i_code = |i: usize| fields[i].starts_with(|c: char| c.is_ascii_alphabetic());
+    let (chain_id, res_seq, i_code, rest) = match fields.len() {
+        12 => (fields[4], fields[5], Some(fields[6]), &fields[7..]),
+        11 if separate_i_code(5) => ("", fields[4], Some(fields[5]), &fields[6..]),
+        11 => (fields[4], fields[5], None, &fields[6..]),
+        10 => ("", fields[4], None, &fields[5..]),
+        n => return Err(anyhow!("expected 10 to 12 fields in the atom record at line {}, found {}", line_number, n)),
+    };
+    let number = |field: &str| field.parse::<f64>().with_context(|| format!("can not parse '{}' at line {}", field, line_number));
+    let integer = |field: &str| field.parse::<usize>().with_context(|| format!("can not parse '{}' as an integer at line {}", field, line_number));
+    // the insertion code may also follow the residue number without a space
+    let number_part = res_seq.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let i_code = i_code.unwrap_or(&res_seq[number_part.len()..]);
+    let res_seq = integer(number_part)?;
+    let radius = number(rest[4])?;
+    ensure!(radius >= 0.0, "negative atom radius at line {}: {}", line_number, radius);
+
+    let mut atom = Atom::new(
+        hetero,
+        integer(fields[1])?,
+        fields[2],
+        fields[3],
+        chain_id,
+        res_seq,
+        number(rest[0])?,
+        number(rest[1])?,
+        number(rest[2])?,
+        1.0,
+        0.0,
+        Element::infer(fields[2], fields[3]).map(|e| e.symbol()).unwrap_or(""),
+        0,
+    ).ok_or_else(|| anyhow!("invalid atom at line {}", line_number))?;
+    atom.set_i_code(i_code)?;
+    atom.set_partial_charge(Some(number(rest[3])?))?;
+    atom.set_radius(Some(radius))?;
+    Ok(atom)
+}
+
+/// Saves the `PDB` as a PQR file.
+/// Atoms without a partial charge are written neutral and atoms without a radius get
+/// the van der Waals radius of their element.
+pub fn save_pqr(pdb: &PDB, filename: &str) -> Result<()> {
+    let f = File::create(filename).with_context(|| format!("could not create {}", filename))?;
+    save_pqr_raw(pdb, BufWriter::new(f))
+}
+
+pub(crate) fn save_pqr_raw<W: Write>(pdb: &PDB, mut stream: BufWriter<W>) -> Result<()> {
+    for atom in pdb.atoms() {
+        writeln!(
+            stream,
+            "{:<6}{:5} {:^4} {:>3} {:1} {:4}{:1}  {:8.3} {:8.3} {:8.3} {:7.4} {:6.4}",
+            if *atom.hetero() { "HETATM" } else { "ATOM" },
+            atom.serial_number(),
+            atom.atom_name(),
+            atom.res_name(),
+            atom.chain_id(),
+            atom.res_seq(),
+            atom.i_code().unwrap_or(""),
+            atom.x(),
+            atom.y(),
+            atom.z(),
+            atom.partial_charge().unwrap_or(0.0),
+            atom.radius()
+                .or_else(|| atom.inferred_element().map(|e| e.vdw_radius()))
+                .unwrap_or(0.0),
+        )?;
+    }
+    writeln!(stream, "TER")?;
+    writeln!(stream, "END")?;
+    stream.flush()?;
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_read_pqr() {
+        let input = "\
+REMARK   1 PQR file generated by PDB2PQR
+ATOM      1  N   MET A   1     -21.580  -3.697  12.455 -0.3000 1.8240
+ATOM      2  CA  MET A   1     -20.347  -3.156  11.852  0.2100 1.9080
+HETATM    3  O   HOH   101      10.000   1.000  -2.500 -0.8340 1.7683
+";
+        let pdb = read_pqr_raw(BufReader::new(input.as_bytes())).unwrap();
+        assert_eq!(pdb.atom_count(), 3);
+        let nitrogen = pdb.atom(0).unwrap();
+        assert_eq!(nitrogen.partial_charge(), Some(-0.3));
+        assert_eq!(nitrogen.radius(), Some(1.824));
+        assert_eq!(nitrogen.position(), (-21.58, -3.697, 12.455));
+        assert_eq!(nitrogen.element(), Some(Element::N));
+        let water = pdb.atom(2).unwrap();
+        assert!(*water.hetero());
+        assert_eq!(*water.res_seq(), 101);
+        assert_eq!(water.chain_id(), "");
+        assert!(read_pqr_raw(BufReader::new("ATOM      1  N   MET A   1     -21.580  -3.697".as_bytes())).is_err());
+    }
+
+    #[test]
+    fn can_read_insertion_codes() {
+        let input = "\
+ATOM      1  N   MET A   1 B   -21.580  -3.697  12.455 -0.3000 1.8240
+ATOM      2  N   MET     2 C   -21.580  -3.697  12.455 -0.3000 1.8240
+ATOM      3  N   MET A   3D    -21.580  -3.697  12.455 -0.3000 1.8240
+ATOM      4  N   MET     4     -21.580  -3.697  12.455 -0.3000 1.8240
+";
+        let pdb = read_pqr_raw(BufReader::new(input.as_bytes())).unwrap();
+        let ids: Vec<(&str, usize, Option<&str>)> = pdb.atoms().map(|a| (a.chain_id().as_str(), *a.res_seq(), a.i_code())).collect();
+        assert_eq!(ids, vec![("A", 1, Some("B")), ("", 2, Some("C")), ("A", 3, Some("D")), ("", 4, None)]);
+        assert_eq!(*pdb.atom(0).unwrap().x(), -21.58);
+
+        let mut output = Vec::new();
+        save_pqr_raw(&pdb, BufWriter::new(&mut output)).unwrap();
+        let again = read_pqr_raw(BufReader::new(output.as_slice())).unwrap();
+        assert_eq!(again.atoms().map(|a| (a.chain_id().as_str(), *a.res_seq(), a.i_code())).collect::<Vec<_>>(), ids);
+    }
+
+    #[test]
+    fn pqr_round_trip() {
+        let input = "ATOM      1  N   MET A    1    -21.580   -3.697   12.455 -0.3000 1.8240\n";
+        let pdb = read_pqr_raw(BufReader::new(input.as_bytes())).unwrap();
+        let mut output = Vec::new();
+        save_pqr_raw(&pdb, BufWriter::new(&mut output)).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        assert_eq!(text.lines().next(), Some(input.trim_end()));
+        let again = read_pqr_raw(BufReader::new(text.as_bytes())).unwrap();
+        assert_eq!(pdb, again);
+        assert_eq!(again.atom(0).unwrap().radius(), Some(1.824));
+    }
+
+    #[test]
+    fn large_residue_numbers_round_trip() {
+        let input = "ATOM      1  N   MET A 10000    -21.580   -3.697   12.455 -0.3000 1.8240\n";
+        let pdb = read_pqr_raw(BufReader::new(input.as_bytes())).unwrap();
+        let mut output = Vec::new();
+        save_pqr_raw(&pdb, BufWriter::new(&mut output)).unwrap();
+        let again = read_pqr_raw(BufReader::new(output.as_slice())).unwrap();
+        assert_eq!(*again.atom(0).unwrap().res_seq(), 10000);
+        assert_eq!(again.atom(0).unwrap().chain_id(), "A");
+    }
+}
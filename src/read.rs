@@ -3,7 +3,7 @@ use std::fs::File;
 use std::io::{BufReader, BufRead};
 use std::convert::TryFrom as _;
 
-use crate::structs::{PDB, Atom};
+use crate::structs::{PDB, Atom, Element, ResidueId, UnitCell, Helix, HelixClass, Sheet, Strand, StrandSense, Registration};
 use crate::item::ParsedItems;
 
 pub fn read_pdb(filename: &str) -> Result<PDB>
@@ -18,6 +18,8 @@ pub fn read_pdb_raw<T>(input: BufReader<T>) -> Result<PDB>
     where T: std::io::Read
 {
     let mut pdb = PDB::new();
+    let mut sheets: Vec<Sheet> = Vec::new();
+    let mut conects: Vec<(usize, usize)> = Vec::new();
     for (mut line_number, read_line) in input.lines().enumerate() {
         line_number += 1;
         let line = if let Ok(l) = read_line {
@@ -32,15 +34,16 @@ pub fn read_pdb_raw<T>(input: BufReader<T>) -> Result<PDB>
             match result {
                 ParsedItems::Header(_, _, idntifier) => pdb.set_identifier(&idntifier)?,
                 ParsedItems::Remark(remark_type, remark_text) => pdb.add_remarks(remark_type, &remark_text)?,
+                ParsedItems::Cryst(unit_cell) => pdb.set_unit_cell(Some(unit_cell)),
                 ParsedItems::Atom(
                     hetero,
                     serial_number,
                     atom_name,
-                    _alt_location,
+                    alt_location,
                     res_name,
                     chain_id,
                     res_seq,
-                    _i_code,
+                    i_code,
                     x,
                     y,
                     z,
@@ -49,8 +52,8 @@ pub fn read_pdb_raw<T>(input: BufReader<T>) -> Result<PDB>
                     _segment_id,
                     element,
                     charge,
-                ) => pdb.add_atom(
-                    Atom::new(
+                ) => {
+                    let mut atom = Atom::new(
                         hetero,
                         serial_number,
                         &atom_name,
@@ -64,13 +67,32 @@ pub fn read_pdb_raw<T>(input: BufReader<T>) -> Result<PDB>
                         temp_factor,
                         &element,
                         charge
-                    ).ok_or(anyhow!(""))?,
-                ),
+                    ).ok_or(anyhow!(""))?;
+                    atom.set_alt_location(alt_location.as_deref().unwrap_or(""))?;
+                    atom.set_i_code(i_code.as_deref().unwrap_or(""))?;
+                    pdb.add_atom(atom);
+                },
+                ParsedItems::Helix(helix) => pdb.add_helix(helix),
+                ParsedItems::Sheet(sheet_id, strand) => {
+                    match sheets.iter_mut().find(|s| s.id == sheet_id) {
+                        Some(sheet) => sheet.strands.push(strand),
+                        None => sheets.push(Sheet { id: sheet_id, strands: vec![strand] }),
+                    }
+                },
+                ParsedItems::Conect(serial_number, bonded) => {
+                    // a record connecting an atom to itself is skipped like any malformed record
+                    conects.extend(bonded.into_iter().filter(|other| *other != serial_number).map(|other| (serial_number, other)));
+                },
                 _ => (),
             }   
         };
 
     }
+    for sheet in sheets {
+        pdb.add_sheet(sheet);
+    }
+    // added at once, as sorting the records after every CONECT is quadratic
+    pdb.add_conects(conects)?;
     Ok(pdb)
 }
 
@@ -79,8 +101,12 @@ fn parse_line(line: &str, line_number: usize) -> Result<ParsedItems>{
         match &line[..6] {
             "HEADER" => parse_header(line, line_number),
             "REMARK" => parse_remarks(line, line_number),
+            "CRYST1" => parse_cryst(line, line_number),
             "HETATM" => parse_atom(line, line_number, true),
             "ATOM  " => parse_atom(line, line_number, false),
+            "HELIX " => parse_helix(line, line_number),
+            "SHEET " => parse_sheet(line, line_number),
+            "CONECT" => parse_conect(line, line_number),
             "TER   " => Ok(ParsedItems::Ter),
             "END   " => Ok(ParsedItems::End),
             _ => Ok(ParsedItems::Empty),
@@ -115,6 +141,26 @@ fn parse_remarks(line: &str, line_number: usize) -> Result<ParsedItems> {
     ))
 }
 
+fn parse_cryst(line: &str, line_number: usize) -> Result<ParsedItems> {
+    let chars: Vec<char> = line.chars().collect();
+    ensure!(chars.len() >= 54, format!("CRYST1 is too short: line {}", line_number));
+    let mut unit_cell = UnitCell::new(
+        parse_f64(&chars[6..15], line_number)?,
+        parse_f64(&chars[15..24], line_number)?,
+        parse_f64(&chars[24..33], line_number)?,
+        parse_f64(&chars[33..40], line_number)?,
+        parse_f64(&chars[40..47], line_number)?,
+        parse_f64(&chars[47..54], line_number)?,
+    );
+    let space_group: String = chars.get(55..chars.len().min(66)).unwrap_or(&[]).iter().collect();
+    if !space_group.trim().is_empty() {
+        unit_cell.space_group = Some(space_group.trim().to_owned());
+    }
+    let z: String = chars.get(66..chars.len().min(70)).unwrap_or(&[]).iter().collect();
+    unit_cell.z = z.trim().parse().ok();
+    Ok(ParsedItems::Cryst(unit_cell))
+}
+
 fn parse_atom(line: &str, line_number: usize, hetero: bool) -> Result<ParsedItems>
 {
     let chars: Vec<char> = line.chars().collect();
@@ -126,6 +172,8 @@ fn parse_atom(line: &str, line_number: usize, hetero: bool) -> Result<ParsedItem
     let res_name =  chars[17..20].iter().collect::<String>();
     let chain_id = String::from(chars[21]);
     let res_seq = parse_usize(&chars[22..26], line_number)?;
+    let alt_location = Some(String::from(chars[16])).filter(|c| !c.trim().is_empty());
+    let i_code = Some(String::from(chars[26])).filter(|c| !c.trim().is_empty());
 
     let x = parse_f64(&chars[30..38], line_number)?;
     let y = parse_f64(&chars[38..46], line_number)?;
@@ -141,10 +189,11 @@ fn parse_atom(line: &str, line_number: usize, hetero: bool) -> Result<ParsedItem
         temp_factor = parse_f64(&chars[60..66], line_number)?;
     }
 
-    let mut element = String::new();
-    if chars.len() >= 77 {
-        element = chars[76..78].iter().collect::<String>();
-    }
+    // an unknown symbol in columns 77-78 leaves the element unknown, see `Atom::inferred_element`
+    let element = chars.get(76..chars.len().min(78))
+        .map(|c| c.iter().collect::<String>())
+        .filter(|symbol| Element::from_symbol(symbol).is_some())
+        .unwrap_or_default();
 
     let mut charge: isize = 0;
     if chars.len() >= 80 && !(chars[78].is_whitespace() && chars[79].is_whitespace())
@@ -160,17 +209,20 @@ fn parse_atom(line: &str, line_number: usize, hetero: bool) -> Result<ParsedItem
                 anyhow!("can not parse character into digit at line {}: {:?}", line_number, chars[78])
             )?
         )?;
+        if chars[79] == '-' {
+            charge = -charge;
+        }
     }
 
     Ok(ParsedItems::Atom(
         hetero,
         serial_number,
         atom_name,
-        None,
+        alt_location,
         res_name,
         chain_id,
         res_seq,
-        None,
+        i_code,
         x,
         y,
         z,
@@ -184,6 +236,105 @@ fn parse_atom(line: &str, line_number: usize, hetero: bool) -> Result<ParsedItem
 
 }
 
+fn parse_helix(line: &str, line_number: usize) -> Result<ParsedItems> {
+    let chars: Vec<char> = line.chars().collect();
+    ensure!(chars.len() >= 38,
+            format!("HELIX line is too short: line {}", line_number));
+
+    let start = parse_residue_id(&chars, 19, 21, 25, line_number)?;
+    let end = parse_residue_id(&chars, 31, 33, 37, line_number)?;
+    let mut class = HelixClass::RightHandedAlpha;
+    if chars.len() >= 40 && !chars[38..40].iter().all(|c| c.is_whitespace()) {
+        let number = parse_usize(&chars[38..40], line_number)?;
+        class = HelixClass::from_number(number)
+            .ok_or_else(|| anyhow!("invalid helix class at line {}: {}", line_number, number))?;
+    }
+    let comment = chars.get(40..chars.len().min(70)).unwrap_or(&[]).iter().collect::<String>().trim().to_owned();
+    let length = if chars.len() >= 76 && !chars[71..76].iter().all(|c| c.is_whitespace()) {
+        parse_usize(&chars[71..76], line_number)?
+    } else {
+        (end.res_seq + 1).saturating_sub(start.res_seq)
+    };
+
+    Ok(ParsedItems::Helix(Helix {
+        serial_number: parse_usize(&chars[7..10], line_number)?,
+        id: chars[11..14].iter().collect::<String>().trim().to_owned(),
+        start,
+        start_res_name: chars[15..18].iter().collect::<String>().trim().to_owned(),
+        end,
+        end_res_name: chars[27..30].iter().collect::<String>().trim().to_owned(),
+        class,
+        comment,
+        length,
+    }))
+}
+
+fn parse_sheet(line: &str, line_number: usize) -> Result<ParsedItems> {
+    let chars: Vec<char> = line.chars().collect();
+    ensure!(chars.len() >= 38,
+            format!("SHEET line is too short: line {}", line_number));
+
+    let mut sense = StrandSense::First;
+    if chars.len() >= 40 && !chars[38..40].iter().all(|c| c.is_whitespace()) {
+        let number = parse_isize(&chars[38..40], line_number)?;
+        sense = StrandSense::from_number(number)
+            .ok_or_else(|| anyhow!("invalid strand sense at line {}: {}", line_number, number))?;
+    }
+    let mut registration = None;
+    if chars.len() >= 69 && !chars[41..45].iter().all(|c| c.is_whitespace()) {
+        registration = Some(Registration {
+            current_atom: chars[41..45].iter().collect::<String>().trim().to_owned(),
+            current_res_name: chars[45..48].iter().collect::<String>().trim().to_owned(),
+            current: parse_residue_id(&chars, 49, 50, 54, line_number)?,
+            previous_atom: chars[56..60].iter().collect::<String>().trim().to_owned(),
+            previous_res_name: chars[60..63].iter().collect::<String>().trim().to_owned(),
+            previous: parse_residue_id(&chars, 64, 65, 69, line_number)?,
+        });
+    }
+
+    Ok(ParsedItems::Sheet(
+        chars[11..14].iter().collect::<String>().trim().to_owned(),
+        Strand {
+            start: parse_residue_id(&chars, 21, 22, 26, line_number)?,
+            start_res_name: chars[17..20].iter().collect::<String>().trim().to_owned(),
+            end: parse_residue_id(&chars, 32, 33, 37, line_number)?,
+            end_res_name: chars[28..31].iter().collect::<String>().trim().to_owned(),
+            sense,
+            registration,
+        },
+    ))
+}
+
+/// Parses a residue given by the (0-based) columns of its chain id,
+/// the start of its sequence number and its insertion code.
+fn parse_residue_id(chars: &[char], chain: usize, seq: usize, i_code: usize, line_number: usize) -> Result<ResidueId> {
+    let i_code = chars.get(i_code).map(|c| c.to_string());
+    Ok(ResidueId::new(
+        &chars[chain].to_string(),
+        parse_usize(&chars[seq..seq + 4], line_number)?,
+        i_code.as_deref(),
+    ))
+}
+
+fn parse_conect(line: &str, line_number: usize) -> Result<ParsedItems> {
+    let chars: Vec<char> = line.chars().collect();
+    ensure!(chars.len() >= 16,
+            format!("CONECT line is too short: line {}", line_number));
+
+    let serial_number = parse_usize(&chars[6..11], line_number)?;
+    let mut bonded = Vec::new();
+    for start in (11..31).step_by(5) {
+        if chars.len() < start + 5 {
+            break;
+        }
+        if chars[start..start + 5].iter().all(|c| c.is_whitespace()) {
+            continue;
+        }
+        bonded.push(parse_usize(&chars[start..start + 5], line_number)?);
+    }
+    Ok(ParsedItems::Conect(serial_number, bonded))
+}
+
 fn parse_f64(input: &[char], line_number: usize) -> Result<f64> {
     let string = input
     .iter()
@@ -194,6 +345,16 @@ fn parse_f64(input: &[char], line_number: usize) -> Result<f64> {
     string.parse::<f64>().with_context(|| format!("can't parse the number as f64 at line {}: {:?}", line_number, input))
 }
 
+fn parse_isize(input: &[char], line_number: usize) -> Result<isize> {
+    let string = input
+    .iter()
+    .collect::<String>()
+    .split_whitespace()
+    .collect::<String>();
+
+    string.parse::<isize>().with_context(|| format!("can't parse the number as isize at line {}: {:?}", line_number, input))
+}
+
 fn parse_usize(input: &[char], line_number: usize) -> Result<usize> {
     let string = input
     .iter()
@@ -209,14 +370,138 @@ fn parse_usize(input: &[char], line_number: usize) -> Result<usize> {
 mod test {
     use super::*;
     #[test]
+    #[allow(clippy::unnecessary_cast)]
     fn can_parse_f64() {
         let chara: Vec<char> = "54.572".chars().collect();
         assert_eq!(54.572 as f64, parse_f64(&chara, 1).unwrap());    
     }
 
     #[test]
+    #[allow(clippy::unnecessary_cast)]
     fn can_parse_usize() {
         let chara: Vec<char> = "1234".chars().collect();
         assert_eq!(1234 as usize, parse_usize(&chara, 1).unwrap());
     }
+
+    #[test]
+    fn can_parse_helix() {
+        let line = "HELIX    1  H1 ILE A    7  PRO A   19  13/10 CONFORMATION RES 17,19       14";
+        match parse_helix(line, 1).unwrap() {
+            ParsedItems::Helix(helix) => {
+                assert_eq!(helix.id, "H1");
+                assert_eq!(helix.start, ResidueId::new("A", 7, None));
+                assert_eq!(helix.end_res_name, "PRO");
+                assert_eq!(helix.class, HelixClass::RightHandedAlpha);
+                assert_eq!(helix.comment, "3/10 CONFORMATION RES 17,19");
+                assert_eq!(helix.length, 14);
+            },
+            _ => panic!("not parsed as HELIX"),
+        }
+    }
+
+    #[test]
+    fn can_parse_sheet() {
+        let line = "SHEET    2  S1 3 ARG A  45  ILE A  49 -1  N  ILE A  49   O  GLY A  41A";
+        match parse_sheet(line, 1).unwrap() {
+            ParsedItems::Sheet(id, strand) => {
+                assert_eq!(id, "S1");
+                assert_eq!(strand.start, ResidueId::new("A", 45, None));
+                assert_eq!(strand.sense, StrandSense::Antiparallel);
+                let registration = strand.registration.unwrap();
+                assert_eq!(registration.current_atom, "N");
+                assert_eq!(registration.previous, ResidueId::new("A", 41, Some("A")));
+            },
+            _ => panic!("not parsed as SHEET"),
+        }
+    }
+
+    #[test]
+    fn can_parse_conect() {
+        let line = "CONECT 1179  746 1184 1195 1203";
+        match parse_conect(line, 1).unwrap() {
+            ParsedItems::Conect(serial, bonded) => {
+                assert_eq!(serial, 1179);
+                assert_eq!(bonded, vec![746, 1184, 1195, 1203]);
+            },
+            _ => panic!("not parsed as CONECT"),
+        }
+    }
+
+    #[test]
+    fn skips_conect_to_itself() {
+        let input = "\
+CONECT    1    1    2
+CONECT    2    3
+";
+        let pdb = read_pdb_raw(BufReader::new(input.as_bytes())).unwrap();
+        assert_eq!(pdb.conects().collect::<Vec<_>>(), vec![&(1, 2), &(2, 3)]);
+    }
+
+    #[test]
+    fn can_parse_cryst() {
+        let line = "CRYST1   52.000   58.600   61.900  90.00  90.00  90.00 P 21 21 21    8";
+        match parse_cryst(line, 1).unwrap() {
+            ParsedItems::Cryst(cell) => {
+                assert_eq!((cell.a, cell.b, cell.c), (52.0, 58.6, 61.9));
+                assert_eq!(cell.gamma, 90.0);
+                assert_eq!(cell.space_group.as_deref(), Some("P 21 21 21"));
+                assert_eq!(cell.z, Some(8));
+            },
+            _ => panic!("not parsed as CRYST1"),
+        }
+        match parse_cryst("CRYST1   52.000   58.600   61.900  90.00  90.00  90.00 P 1", 1).unwrap() {
+            ParsedItems::Cryst(cell) => assert_eq!(cell.z, None),
+            _ => panic!("not parsed as CRYST1"),
+        }
+    }
+
+    #[test]
+    fn can_parse_element() {
+        let element = |line: &str, hetero| match parse_atom(line, 1, hetero).unwrap() {
+            ParsedItems::Atom(.., element, _) => element.trim().to_owned(),
+            _ => panic!("not parsed as ATOM"),
+        };
+        assert_eq!(element("ATOM      2  CA  ALA A   1      11.104   6.134  -6.504  1.00  0.00", false), "");
+        assert_eq!(element("ATOM      2  CA  ALA A   1      11.104   6.134  -6.504  1.00  0.00           ", false), "");
+        assert_eq!(element("ATOM      2  CA  ALA A   1      11.104   6.134  -6.504  1.00  0.00           C", false), "C");
+        assert_eq!(element("ATOM      2  CA  ALA A   1      11.104   6.134  -6.504  1.00  0.00          XX", false), "");
+    }
+
+    #[test]
+    fn can_parse_alt_location_and_i_code() {
+        let input = "\
+ATOM      5  CB ASER A  52A     11.104   6.134  -6.504  0.50  0.00           C
+ATOM      6  CB BSER A  52A     11.504   6.034  -6.404  0.50  0.00           C
+";
+        let pdb = read_pdb_raw(BufReader::new(input.as_bytes())).unwrap();
+        let a = pdb.atom(0).unwrap().clone();
+        assert_eq!(a.alt_location(), Some("A"));
+        assert_eq!(a.i_code(), Some("A"));
+        assert_eq!(a.residue_id(), ResidueId::new("A", 52, Some("A")));
+        assert_eq!(pdb.atom(1).unwrap().alt_location(), Some("B"));
+    }
+
+    #[test]
+    fn keeps_atoms_with_unknown_elements() {
+        let input = "\
+ATOM      2  CA  ALA A   1      11.104   6.134  -6.504  1.00  0.00          XX
+HETATM  100 CA    CA A 101      11.104   6.134  -6.504  1.00  0.00
+";
+        let pdb = read_pdb_raw(BufReader::new(input.as_bytes())).unwrap();
+        assert_eq!(pdb.atom_count(), 2);
+        assert_eq!(pdb.atom(0).unwrap().element(), None);
+        assert_eq!(pdb.atom(0).unwrap().inferred_element(), Some(Element::C));
+        assert_eq!(pdb.atom(1).unwrap().element(), None);
+        assert_eq!(pdb.atom(1).unwrap().inferred_element(), Some(Element::Ca));
+    }
+
+    #[test]
+    fn can_parse_signed_charge() {
+        let charge = |line: &str| match parse_atom(line, 1, true).unwrap() {
+            ParsedItems::Atom(.., charge) => charge,
+            _ => panic!("not parsed as ATOM"),
+        };
+        assert_eq!(charge("HETATM 1001  O1  SO4 A 301      10.000  10.000  10.000  1.00 20.00           O1-"), -1);
+        assert_eq!(charge("HETATM 1002 ZN    ZN A 302      10.000  10.000  10.000  1.00 20.00          ZN2+"), 2);
+    }
 }
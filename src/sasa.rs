@@ -0,0 +1,207 @@
+use std::collections::{BTreeMap, HashMap};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::structs::{PDB, Atom, Element, ResidueId};
+use crate::geometry::Vector3;
+use crate::spatial::SpatialIndex;
+
+/// Parameters of the Shrake-Rupley surface calculation
+#[derive(Debug, Clone, PartialEq)]
+pub struct SasaParameters {
+    /// The radius of the solvent probe (Å)
+    pub probe_radius: f64,
+    /// The number of test points on each atom sphere
+    pub points: usize,
+    /// The van der Waals radius (Å) by element
+    pub radii: HashMap<Element, f64>,
+    /// The radius (Å) of atoms whose element is unknown or not in `radii`
+    pub default_radius: f64,
+    /// Whether hydrogens take part in the calculation, otherwise they get an area of 0
+    pub include_hydrogens: bool,
+}
+
+impl Default for SasaParameters {
+    fn default() -> Self {
+        SasaParameters {
+            probe_radius: 1.4,
+            points: 100,
+            radii: Element::all().map(|e| (e, e.vdw_radius())).collect(),
+            default_radius: 1.8,
+            include_hydrogens: false,
+        }
+    }
+}
+
+impl SasaParameters {
+    fn radius(&self, atom: &Atom) -> f64 {
+        atom.inferred_element().and_then(|e| self.radii.get(&e)).copied().unwrap_or(self.default_radius)
+    }
+}
+
+/// The solvent accessible surface area of a residue
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResidueSasa {
+    pub residue: ResidueId,
+    pub res_name: String,
+    /// The absolute area (Å²)
+    pub area: f64,
+    /// The area relative to the residue in an extended Gly-X-Gly tripeptide,
+    /// `None` for non standard residues
+    pub relative: Option<f64>,
+}
+
+/// The solvent accessible surface area of a structure (Å²)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sasa {
+    /// The area of each atom, indexed like the atoms of the `PDB`
+    pub atoms: Vec<f64>,
+    pub residues: Vec<ResidueSasa>,
+    /// The area of each chain by chain id
+    pub chains: BTreeMap<String, f64>,
+    pub total: f64,
+}
+
+/// Computes the solvent accessible surface area with the Shrake-Rupley algorithm.
+/// The atoms are processed in parallel with the `rayon` feature.
+pub fn sasa(pdb: &PDB, parameters: &SasaParameters) -> Sasa {
+    let atoms: Vec<&Atom> = pdb.atoms().collect();
+    let included: Vec<bool> = atoms.iter().map(|a| parameters.include_hydrogens || !a.is_hydrogen()).collect();
+    let radii: Vec<f64> = atoms.iter().map(|a| parameters.radius(a) + parameters.probe_radius).collect();
+    let max_radius = radii.iter().cloned().fold(0.0, f64::max);
+    let sphere = sphere_points(parameters.points);
+    let index = SpatialIndex::new(pdb);
+
+    let area_of = |i: usize| -> f64 {
+        if !included[i] || sphere.is_empty() {
+            return 0.0;
+        }
+        let center = atoms[i].coords();
+        let neighbors: Vec<usize> = index
+            .within(&center, radii[i] + max_radius)
+            .into_iter()
+            .filter(|j| *j != i && included[*j] && atoms[*j].coords().distance(&center) < radii[i] + radii[*j])
+            .collect();
+        let mut last = 0;
+        let accessible = sphere
+            .iter()
+            .filter(|p| {
+                let point = center + **p * radii[i];
+                let buried = |j: &usize| point.distance_squared(&atoms[*j].coords()) < radii[*j] * radii[*j];
+                // the last burying neighbor is likely to bury the next point as well
+                if neighbors.get(last).map(buried).unwrap_or(false) {
+                    return false;
+                }
+                match neighbors.iter().position(buried) {
+                    Some(position) => {
+                        last = position;
+                        false
+                    },
+                    None => true,
+                }
+            })
+            .count();
+        4.0 * std::f64::consts::PI * radii[i] * radii[i] * accessible as f64 / sphere.len() as f64
+    };
+
+    #[cfg(feature = "rayon")]
+    let areas: Vec<f64> = (0..atoms.len()).into_par_iter().map(area_of).collect();
+    #[cfg(not(feature = "rayon"))]
+    let areas: Vec<f64> = (0..atoms.len()).map(area_of).collect();
+
+    let residues = pdb
+        .residue_indices()
+        .into_iter()
+        .map(|(residue, indices)| {
+            let res_name = atoms[indices[0]].res_name().to_owned();
+            let area: f64 = indices.iter().map(|i| areas[*i]).sum();
+            ResidueSasa {
+                relative: max_asa(&res_name).map(|max| area / max),
+                residue,
+                res_name,
+                area,
+            }
+        })
+        .collect();
+    let mut chains = BTreeMap::new();
+    for (atom, area) in atoms.iter().zip(areas.iter()) {
+        *chains.entry(atom.chain_id().to_owned()).or_insert(0.0) += area;
+    }
+    Sasa {
+        total: areas.iter().sum(),
+        atoms: areas,
+        residues,
+        chains,
+    }
+}
+
+/// Evenly distributed points on the unit sphere (golden section spiral).
+fn sphere_points(n: usize) -> Vec<Vector3> {
+    let increment = std::f64::consts::PI * (3.0 - 5.0_f64.sqrt());
+    (0..n)
+        .map(|k| {
+            let y = 1.0 - (2.0 * k as f64 + 1.0) / n as f64;
+            let r = (1.0 - y * y).sqrt();
+            let phi = k as f64 * increment;
+            Vector3::new(phi.cos() * r, y, phi.sin() * r)
+        })
+        .collect()
+}
+
+/// The theoretical maximum accessible area (Å²) of a standard amino acid (Tien et al. 2013).
+pub fn max_asa(res_name: &str) -> Option<f64> {
+    let area = match res_name {
+        "ALA" => 129.0,
+        "ARG" => 274.0,
+        "ASN" => 195.0,
+        "ASP" => 193.0,
+        "CYS" => 167.0,
+        "GLN" => 225.0,
+        "GLU" => 223.0,
+        "GLY" => 104.0,
+        "HIS" => 224.0,
+        "ILE" => 197.0,
+        "LEU" => 201.0,
+        "LYS" => 236.0,
+        "MET" => 224.0,
+        "PHE" => 240.0,
+        "PRO" => 159.0,
+        "SER" => 155.0,
+        "THR" => 172.0,
+        "TRP" => 285.0,
+        "TYR" => 263.0,
+        "VAL" => 174.0,
+        _ => return None,
+    };
+    Some(area)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn atom(serial: usize, x: f64) -> Atom {
+        Atom::new(false, serial, "C", "UNK", "A", serial, x, 0.0, 0.0, 1.0, 0.0, "C", 0).unwrap()
+    }
+
+    #[test]
+    fn isolated_and_overlapping_spheres() {
+        let parameters = SasaParameters { points: 2000, ..SasaParameters::default() };
+        let mut pdb = PDB::new();
+        pdb.add_atom(atom(1, 0.0));
+        let single = sasa(&pdb, &parameters);
+        let expected = 4.0 * std::f64::consts::PI * 3.1 * 3.1;
+        assert!((single.total - expected).abs() < 1e-9);
+
+        pdb.add_atom(atom(2, 3.1));
+        let pair = sasa(&pdb, &parameters);
+        // each sphere loses a cap of height r - d / 2
+        let cap = 2.0 * std::f64::consts::PI * 3.1 * (3.1 - 1.55);
+        assert!((pair.atoms[0] - (expected - cap)).abs() / expected < 0.01);
+        assert_eq!(pair.residues.len(), 2);
+        assert!(pair.residues[0].relative.is_none());
+        assert!((pair.chains["A"] - pair.total).abs() < 1e-9);
+    }
+}
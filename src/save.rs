@@ -5,6 +5,7 @@ use std::fs::File;
 use std::io::prelude::*;
 use std::io::BufWriter;
 use std::iter;
+use std::collections::BTreeMap;
 
 
 pub fn save_pdb(pdb: PDB, filename: &str) -> Result<()>
@@ -50,6 +51,68 @@ fn save_pdb_raw<W: Write>(pdb: &PDB, mut stream: BufWriter<W>, atom_only: bool)
                 "REMARK {:3} {}", line.0, line.1
             ))?;
         }
+        // write unit cell
+        if let Some(cell) = pdb.unit_cell() {
+            write_line(format!(
+                "CRYST1{:9.3}{:9.3}{:9.3}{:7.2}{:7.2}{:7.2} {:<11}{:>4}",
+                cell.a, cell.b, cell.c, cell.alpha, cell.beta, cell.gamma,
+                cell.space_group.as_deref().unwrap_or("P 1"),
+                cell.z.map(|z| z.to_string()).unwrap_or_default()
+            ))?;
+        }
+        // write secondary structure
+        for helix in pdb.helices() {
+            write_line(format!(
+                "HELIX  {:3} {:>3} {:3} {:1} {:4}{:1} {:3} {:1} {:4}{:1}{:2}{:30.30} {:5}",
+                helix.serial_number,
+                helix.id,
+                helix.start_res_name,
+                helix.start.chain_id,
+                helix.start.res_seq,
+                helix.start.i_code.as_deref().unwrap_or(" "),
+                helix.end_res_name,
+                helix.end.chain_id,
+                helix.end.res_seq,
+                helix.end.i_code.as_deref().unwrap_or(" "),
+                helix.class.number(),
+                helix.comment,
+                helix.length
+            ))?;
+        }
+        for sheet in pdb.sheets() {
+            for (number, strand) in sheet.strands.iter().enumerate() {
+                write_line(format!(
+                    "SHEET  {:3} {:>3}{:2} {:3} {:1}{:4}{:1} {:3} {:1}{:4}{:1}{:2}",
+                    number + 1,
+                    sheet.id,
+                    sheet.strands.len(),
+                    strand.start_res_name,
+                    strand.start.chain_id,
+                    strand.start.res_seq,
+                    strand.start.i_code.as_deref().unwrap_or(" "),
+                    strand.end_res_name,
+                    strand.end.chain_id,
+                    strand.end.res_seq,
+                    strand.end.i_code.as_deref().unwrap_or(" "),
+                    strand.sense.number()
+                ) + &match &strand.registration {
+                    Some(r) => format!(
+                        " {:^4}{:3} {:1}{:4}{:1} {:^4}{:3} {:1}{:4}{:1}",
+                        r.current_atom,
+                        r.current_res_name,
+                        r.current.chain_id,
+                        r.current.res_seq,
+                        r.current.i_code.as_deref().unwrap_or(" "),
+                        r.previous_atom,
+                        r.previous_res_name,
+                        r.previous.chain_id,
+                        r.previous.res_seq,
+                        r.previous.i_code.as_deref().unwrap_or(" ")
+                    ),
+                    None => String::new(),
+                })?;
+            }
+        }
     }
 
     // write atoms
@@ -69,13 +132,34 @@ fn save_pdb_raw<W: Write>(pdb: &PDB, mut stream: BufWriter<W>, atom_only: bool)
             atom.z(),
             atom.occupancy(),
             atom.temp_factor(),
-            atom.element(),
-            atom.charge()
+            atom.element().map(|e| e.symbol().to_ascii_uppercase()).unwrap_or_default(),
+            match atom.charge() {
+                0 => String::new(),
+                charge => format!("{}{}", charge.abs(), if *charge < 0 { '-' } else { '+' }),
+            }
         ))?;
     }
     // TER
     write_line("TER".to_owned())?;
 
+    // write CONECT records, each bond is listed from both atoms
+    if !atom_only {
+        let mut bonded: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (a, b) in pdb.conects() {
+            bonded.entry(*a).or_default().push(*b);
+            bonded.entry(*b).or_default().push(*a);
+        }
+        for (serial_number, others) in bonded {
+            for chunk in others.chunks(4) {
+                let mut line = format!("CONECT{:5}", serial_number);
+                for other in chunk {
+                    line.push_str(&format!("{:5}", other));
+                }
+                write_line(line)?;
+            }
+        }
+    }
+
     stream.flush()?;
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,236 @@
+use anyhow::{ensure, Result};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+
+use crate::structs::{PDB, Atom, Element};
+use crate::bonds::{perceive_bonds, BondOrder, BondParameters, MolecularGraph};
+
+/// Saves groups of atoms, given by their index in the `PDB`, as the molecules of an SDF file.
+/// Bonds are perceived with the default `BondParameters`, so CONECT records take precedence.
+pub fn save_sdf(pdb: &PDB, molecules: &[Vec<usize>], filename: &str) -> Result<()>
+{
+    let graph = perceive_bonds(pdb, &BondParameters::default());
+    save_sdf_with_bonds(pdb, &graph, molecules, filename)
+}
+
+/// Saves groups of atoms as the molecules of an SDF file with the bonds of `graph`,
+/// e.g. from `perceive_bonds_with_components` for the bond orders of the dictionary.
+/// Aromatic bonds are written in a Kekulé form.
+pub fn save_sdf_with_bonds(pdb: &PDB, graph: &MolecularGraph, molecules: &[Vec<usize>], filename: &str) -> Result<()>
+{
+    let f = File::create(filename)?;
+    save_sdf_raw(pdb, graph, molecules, BufWriter::new(f))
+}
+
+/// Saves every ligand of the `PDB` as a molecule of an SDF file. A ligand is a residue
+/// made of hetero atoms that is not water.
+pub fn save_ligands_sdf(pdb: &PDB, filename: &str) -> Result<()>
+{
+    let graph = perceive_bonds(pdb, &BondParameters::default());
+    save_ligands_sdf_with_bonds(pdb, &graph, filename)
+}
+
+/// Saves every ligand of the `PDB` as a molecule of an SDF file with the bonds of `graph`.
+pub fn save_ligands_sdf_with_bonds(pdb: &PDB, graph: &MolecularGraph, filename: &str) -> Result<()>
+{
+    let ligands: Vec<Vec<usize>> = pdb
+        .residue_indices()
+        .into_iter()
+        .map(|(_, indices)| indices)
+        .filter(|indices| indices.iter().all(|i| pdb.atom(*i).map(|a| *a.hetero() && !a.is_water()).unwrap_or(false)))
+        .collect();
+    save_sdf_with_bonds(pdb, graph, &ligands, filename)
+}
+
+pub(crate) fn save_sdf_raw<W: Write>(pdb: &PDB, graph: &MolecularGraph, molecules: &[Vec<usize>], mut stream: BufWriter<W>) -> Result<()>
+{
+    for indices in molecules {
+        let atoms = indices
+            .iter()
+            .map(|i| pdb.atom(*i))
+            .collect::<Option<Vec<&Atom>>>()
+            .ok_or_else(|| anyhow::anyhow!("atom index out of range"))?;
+        ensure!(!atoms.is_empty(), "a molecule has no atoms");
+        let position: HashMap<usize, usize> = indices.iter().enumerate().map(|(k, i)| (*i, k + 1)).collect();
+        let kekule = kekulize(pdb, graph, &position);
+        let bonds: Vec<_> = graph
+            .bonds()
+            .filter_map(|b| {
+                let order = kekule.get(&(b.a, b.b)).copied().unwrap_or(b.order);
+                Some((*position.get(&b.a)?, *position.get(&b.b)?, order))
+            })
+            .collect();
+        ensure!(atoms.len() <= 999 && bonds.len() <= 999, "a molecule is too large for a V2000 molfile");
+
+        // header block: name, program and comment
+        writeln!(stream, "{}", atoms[0].res_name())?;
+        writeln!(stream, "  {:<8}{:10}3D", "libpdb", "")?;
+        writeln!(stream, "{}", atoms[0].residue_id())?;
+        writeln!(stream, "{:3}{:3}  0  0  0  0  0  0  0  0999 V2000", atoms.len(), bonds.len())?;
+        for atom in atoms.iter() {
+            writeln!(
+                stream,
+                "{:10.4}{:10.4}{:10.4} {:<3} 0  0  0  0  0  0  0  0  0  0  0  0",
+                atom.x(),
+                atom.y(),
+                atom.z(),
+                atom.inferred_element().map(|e| e.symbol()).unwrap_or("Du"),
+            )?;
+        }
+        for (a, b, order) in bonds.iter() {
+            // aromatic (4) is a query bond type in V2000, left over only if no Kekulé form was found
+            let order = match order {
+                BondOrder::Single => 1,
+                BondOrder::Double => 2,
+                BondOrder::Triple => 3,
+                BondOrder::Aromatic => 4,
+            };
+            writeln!(stream, "{:3}{:3}{:3}  0", a, b, order)?;
+        }
+        // formal charges, at most eight per line
+        let charges: Vec<(usize, isize)> = atoms
+            .iter()
+            .enumerate()
+            .filter(|(_, a)| *a.charge() != 0)
+            .map(|(k, a)| (k + 1, *a.charge()))
+            .collect();
+        for chunk in charges.chunks(8) {
+            let entries: String = chunk.iter().map(|(k, charge)| format!(" {:3} {:3}", k, charge)).collect();
+            writeln!(stream, "M  CHG{:3}{}", chunk.len(), entries)?;
+        }
+        writeln!(stream, "M  END")?;
+        writeln!(stream, "$$$$")?;
+    }
+    stream.flush()?;
+    Ok(())
+}
+
+/// Single and double orders for the aromatic bonds between the selected atoms.
+/// Every carbon, and every nitrogen with at most two bonds or a positive charge, gets one
+/// double bond if possible; the fewest atoms, preferably nitrogens, are left without one.
+fn kekulize(pdb: &PDB, graph: &MolecularGraph, selected: &HashMap<usize, usize>) -> HashMap<(usize, usize), BondOrder> {
+    let aromatic: Vec<(usize, usize)> = graph
+        .bonds()
+        .filter(|b| b.order == BondOrder::Aromatic && selected.contains_key(&b.a) && selected.contains_key(&b.b))
+        .map(|b| (b.a, b.b))
+        .collect();
+    let element = |i: usize| pdb.atom(i).and_then(|a| a.inferred_element());
+    let candidate = |i: usize| {
+        let multiple = graph.atom_bonds(i).any(|b| matches!(b.order, BondOrder::Double | BondOrder::Triple));
+        !multiple && match element(i) {
+            Some(Element::C) => true,
+            Some(Element::N) => graph.degree(i) <= 2 || pdb.atom(i).map(|a| *a.charge() > 0).unwrap_or(false),
+            _ => false,
+        }
+    };
+    let mut neighbors: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (a, b) in aromatic.iter().filter(|(a, b)| candidate(*a) && candidate(*b)) {
+        neighbors.entry(*a).or_default().push(*b);
+        neighbors.entry(*b).or_default().push(*a);
+    }
+    let mut order: Vec<usize> = neighbors.keys().copied().collect();
+    order.sort_unstable();
+
+    let mut partner = HashMap::new();
+    for skips in 0..=order.len() {
+        if match_atoms(0, &order, &neighbors, &mut partner, skips, &|i| element(i) != Some(Element::C)) {
+            break;
+        }
+    }
+    aromatic
+        .into_iter()
+        .map(|(a, b)| ((a, b), if partner.get(&a) == Some(&b) { BondOrder::Double } else { BondOrder::Single }))
+        .collect()
+}
+
+/// Pairs the atoms from `order[k]` on with an unpaired neighbor each, leaving at most `skips`
+/// atoms unpaired. Atoms for which `skip_first` holds are preferably left unpaired.
+fn match_atoms(
+    k: usize,
+    order: &[usize],
+    neighbors: &HashMap<usize, Vec<usize>>,
+    partner: &mut HashMap<usize, usize>,
+    skips: usize,
+    skip_first: &dyn Fn(usize) -> bool,
+) -> bool {
+    let i = match order.get(k) {
+        Some(i) => *i,
+        None => return true,
+    };
+    if partner.contains_key(&i) {
+        return match_atoms(k + 1, order, neighbors, partner, skips, skip_first);
+    }
+    if skips > 0 && skip_first(i) && match_atoms(k + 1, order, neighbors, partner, skips - 1, skip_first) {
+        return true;
+    }
+    for j in neighbors[&i].iter().copied() {
+        if partner.contains_key(&j) {
+            continue;
+        }
+        partner.insert(i, j);
+        partner.insert(j, i);
+        if match_atoms(k + 1, order, neighbors, partner, skips, skip_first) {
+            return true;
+        }
+        partner.remove(&i);
+        partner.remove(&j);
+    }
+    skips > 0 && !skip_first(i) && match_atoms(k + 1, order, neighbors, partner, skips - 1, skip_first)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn acetate_molfile() {
+        let mut pdb = PDB::new();
+        let mut add = |serial, name: &str, x, y, element: &str, charge| {
+            pdb.add_atom(Atom::new(true, serial, name, "ACT", "B", 101, x, y, 0.0, 1.0, 0.0, element, charge).unwrap());
+        };
+        add(1, "C", 0.0, 0.0, "C", 0);
+        add(2, "O", 1.08, 0.62, "O", 0);
+        add(3, "OXT", -1.08, 0.62, "O", -1);
+        add(4, "CH3", 0.0, -1.5, "C", 0);
+        pdb.add_atom(Atom::new(false, 5, "CA", "GLY", "A", 1, 20.0, 0.0, 0.0, 1.0, 0.0, "C", 0).unwrap());
+
+        let graph = perceive_bonds(&pdb, &BondParameters::default());
+        let mut output = Vec::new();
+        save_sdf_raw(&pdb, &graph, &[vec![0, 1, 2, 3], vec![4]], BufWriter::new(&mut output)).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "ACT");
+        assert_eq!(lines[2], "B:101");
+        assert_eq!(lines[3], "  4  3  0  0  0  0  0  0  0  0999 V2000");
+        assert_eq!(lines[5], "    1.0800    0.6200    0.0000 O   0  0  0  0  0  0  0  0  0  0  0  0");
+        assert_eq!(lines[8], "  1  2  1  0");
+        assert_eq!(lines[11], "M  CHG  1   3  -1");
+        assert_eq!(lines[12], "M  END");
+        assert_eq!(text.matches("$$$$").count(), 2);
+    }
+
+    #[test]
+    fn aromatic_bonds_are_kekulized() {
+        use crate::bonds::{Bond, BondSource};
+        // pyridine with aromatic bonds, as from a component dictionary
+        let mut pdb = PDB::new();
+        for k in 0..6 {
+            let angle = (k as f64 * 60.0).to_radians();
+            let element = if k == 0 { "N" } else { "C" };
+            let name = format!("{}{}", element, k + 1);
+            pdb.add_atom(Atom::new(true, k + 1, &name, "PYR", "B", 1, 1.39 * angle.cos(), 1.39 * angle.sin(), 0.0, 1.0, 0.0, element, 0).unwrap());
+        }
+        let bonds = (0..6).map(|k| Bond { a: k.min((k + 1) % 6), b: k.max((k + 1) % 6), order: BondOrder::Aromatic, source: BondSource::Template }).collect();
+        let graph = MolecularGraph::new(6, bonds);
+        let mut output = Vec::new();
+        save_sdf_raw(&pdb, &graph, &[(0..6).collect()], BufWriter::new(&mut output)).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        let orders: Vec<&str> = text.lines().skip(10).take(6).map(|l| &l[6..9]).collect();
+        assert_eq!(orders.iter().filter(|o| **o == "  2").count(), 3);
+        assert_eq!(orders.iter().filter(|o| **o == "  1").count(), 3);
+        assert!(!text.contains("  4  0\n"));
+    }
+}
@@ -0,0 +1,258 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::structs::{PDB, Atom};
+use crate::geometry::Vector3;
+
+/// A k-d tree over atom positions for radius and nearest neighbor queries.
+/// Query results refer to the points by their index, which for an index built
+/// with `SpatialIndex::new` is the index of the atom in the `PDB`.
+#[derive(Debug, Clone)]
+pub struct SpatialIndex {
+    points: Vec<Vector3>,
+    /// The point indices, laid out as an implicit balanced tree
+    order: Vec<usize>,
+    /// The split axis of the node at the same position in `order`
+    axes: Vec<u8>,
+}
+
+impl SpatialIndex {
+    /// Builds the index over all atoms of `pdb`.
+    pub fn new(pdb: &PDB) -> SpatialIndex {
+        SpatialIndex::from_points(pdb.atoms().map(Atom::coords).collect())
+    }
+
+    /// Builds the index over arbitrary points.
+    pub fn from_points(points: Vec<Vector3>) -> SpatialIndex {
+        let mut index = SpatialIndex {
+            order: (0..points.len()).collect(),
+            axes: vec![0; points.len()],
+            points,
+        };
+        index.build(0, index.order.len());
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// The position of the point with the given index.
+    pub fn point(&self, index: usize) -> Option<&Vector3> {
+        self.points.get(index)
+    }
+
+    /// The indices of all points within `radius` of `center`, in ascending order.
+    /// Empty for a negative radius.
+    pub fn within(&self, center: &Vector3, radius: f64) -> Vec<usize> {
+        let mut found = Vec::new();
+        if radius < 0.0 {
+            return found;
+        }
+        self.visit_within(0, self.order.len(), center, radius * radius, &mut |i| found.push(i));
+        found.sort_unstable();
+        found
+    }
+
+    /// The indices of all points within `radius` of `atom`, including the atom itself if it is indexed.
+    /// Empty for a negative radius.
+    pub fn within_atom(&self, atom: &Atom, radius: f64) -> Vec<usize> {
+        self.within(&atom.coords(), radius)
+    }
+
+    /// The `k` points nearest to `center` as (index, distance), closest first.
+    pub fn nearest(&self, center: &Vector3, k: usize) -> Vec<(usize, f64)> {
+        if k == 0 {
+            return Vec::new();
+        }
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        self.visit_nearest(0, self.order.len(), center, k, &mut heap);
+        let mut nearest: Vec<(usize, f64)> = heap
+            .into_iter()
+            .map(|n| (n.index, n.distance_squared.sqrt()))
+            .collect();
+        nearest.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        nearest
+    }
+
+    /// All pairs of points at most `cutoff` apart as (index, index, distance)
+    /// with the first index smaller, sorted by the indices. Empty for a negative cutoff.
+    /// The pairs are searched in parallel with the `rayon` feature.
+    pub fn pairs_within(&self, cutoff: f64) -> Vec<(usize, usize, f64)> {
+        let pairs_of = |i: usize| -> Vec<(usize, usize, f64)> {
+            let center = self.points[i];
+            self.within(&center, cutoff)
+                .into_iter()
+                .filter(|j| *j > i)
+                .map(|j| (i, j, center.distance(&self.points[j])))
+                .collect()
+        };
+        #[cfg(feature = "rayon")]
+        let pairs: Vec<(usize, usize, f64)> = (0..self.points.len()).into_par_iter().flat_map_iter(pairs_of).collect();
+        #[cfg(not(feature = "rayon"))]
+        let pairs: Vec<(usize, usize, f64)> = (0..self.points.len()).flat_map(pairs_of).collect();
+        pairs
+    }
+
+    fn build(&mut self, lo: usize, hi: usize) {
+        if hi <= lo {
+            return;
+        }
+        let points = &self.points;
+        let (min, max) = self.order[lo..hi].iter().fold(
+            ([f64::INFINITY; 3], [f64::NEG_INFINITY; 3]),
+            |(mut min, mut max), i| {
+                for (axis, value) in points[*i].to_array().iter().enumerate() {
+                    min[axis] = min[axis].min(*value);
+                    max[axis] = max[axis].max(*value);
+                }
+                (min, max)
+            },
+        );
+        let axis = (0..3)
+            .max_by(|a, b| (max[*a] - min[*a]).total_cmp(&(max[*b] - min[*b])))
+            .unwrap_or(0);
+        let mid = lo + (hi - lo) / 2;
+        self.order[lo..hi].select_nth_unstable_by(mid - lo, |a, b| {
+            coordinate(&points[*a], axis).total_cmp(&coordinate(&points[*b], axis))
+        });
+        self.axes[mid] = axis as u8;
+        self.build(lo, mid);
+        self.build(mid + 1, hi);
+    }
+
+    fn visit_within<F>(&self, lo: usize, hi: usize, center: &Vector3, radius_squared: f64, found: &mut F)
+        where F: FnMut(usize)
+    {
+        if hi <= lo {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let index = self.order[mid];
+        let point = &self.points[index];
+        if point.distance_squared(center) <= radius_squared {
+            found(index);
+        }
+        let axis = self.axes[mid] as usize;
+        let diff = coordinate(center, axis) - coordinate(point, axis);
+        if diff <= 0.0 || diff * diff <= radius_squared {
+            self.visit_within(lo, mid, center, radius_squared, found);
+        }
+        if diff >= 0.0 || diff * diff <= radius_squared {
+            self.visit_within(mid + 1, hi, center, radius_squared, found);
+        }
+    }
+
+    fn visit_nearest(&self, lo: usize, hi: usize, center: &Vector3, k: usize, heap: &mut BinaryHeap<Neighbor>) {
+        if hi <= lo {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let index = self.order[mid];
+        let point = &self.points[index];
+        heap.push(Neighbor { distance_squared: point.distance_squared(center), index });
+        if heap.len() > k {
+            heap.pop();
+        }
+        let axis = self.axes[mid] as usize;
+        let diff = coordinate(center, axis) - coordinate(point, axis);
+        let (near, far) = if diff <= 0.0 {
+            ((lo, mid), (mid + 1, hi))
+        } else {
+            ((mid + 1, hi), (lo, mid))
+        };
+        self.visit_nearest(near.0, near.1, center, k, heap);
+        let worst = heap.peek().map(|n| n.distance_squared).unwrap_or(f64::INFINITY);
+        if heap.len() < k || diff * diff <= worst {
+            self.visit_nearest(far.0, far.1, center, k, heap);
+        }
+    }
+}
+
+fn coordinate(point: &Vector3, axis: usize) -> f64 {
+    match axis {
+        0 => point.x,
+        1 => point.y,
+        _ => point.z,
+    }
+}
+
+/// A heap entry ordered by distance, the farthest on top
+#[derive(Debug, Clone, Copy)]
+struct Neighbor {
+    distance_squared: f64,
+    index: usize,
+}
+
+impl PartialEq for Neighbor {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Neighbor {}
+
+impl PartialOrd for Neighbor {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Neighbor {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance_squared.total_cmp(&other.distance_squared).then(self.index.cmp(&other.index))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> Vec<Vector3> {
+        let mut points = Vec::new();
+        for x in 0..6 {
+            for y in 0..5 {
+                for z in 0..4 {
+                    points.push(Vector3::new(x as f64 * 1.1, y as f64 * 0.9 + 0.05 * x as f64, z as f64 * 1.3));
+                }
+            }
+        }
+        points
+    }
+
+    #[test]
+    fn queries_match_brute_force() {
+        let points = grid();
+        let index = SpatialIndex::from_points(points.clone());
+        let center = Vector3::new(2.5, 1.7, 2.0);
+
+        let expected: Vec<usize> = (0..points.len()).filter(|i| points[*i].distance(&center) <= 2.0).collect();
+        assert_eq!(index.within(&center, 2.0), expected);
+
+        let mut by_distance: Vec<(usize, f64)> = points.iter().enumerate().map(|(i, p)| (i, p.distance(&center))).collect();
+        by_distance.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        assert_eq!(index.nearest(&center, 7), by_distance[..7].to_vec());
+
+        let pairs = index.pairs_within(1.2);
+        let brute: usize = (0..points.len())
+            .map(|i| ((i + 1)..points.len()).filter(|j| points[i].distance(&points[*j]) <= 1.2).count())
+            .sum();
+        assert_eq!(pairs.len(), brute);
+        assert!(pairs.iter().all(|(i, j, d)| i < j && *d <= 1.2));
+    }
+
+    #[test]
+    fn negative_radius_finds_nothing() {
+        let index = SpatialIndex::from_points(grid());
+        assert!(index.within(&Vector3::new(2.5, 1.7, 2.0), -2.0).is_empty());
+        assert!(index.pairs_within(-1.2).is_empty());
+    }
+}
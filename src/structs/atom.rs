@@ -5,6 +5,8 @@ use getset::{CopyGetters, Getters, MutGetters, Setters};
 
 use crate::validator;
 use crate::error::PDBError;
+use crate::geometry::{self, Vector3};
+use crate::structs::{Element, ResidueId};
 
 
 
@@ -34,7 +36,7 @@ pub struct Atom {
     chain_id: String, // "A"
 
     /// The residue sequence number
-    #[getset(get = "pub")]
+    #[getset(get = "pub", set = "pub")]
     res_seq: usize, // "67"
 
     /// The code for insertion of residues
@@ -64,16 +66,32 @@ pub struct Atom {
     #[getset(get = "pub")]
     segment_id: Option<String>,
 
-    /// The element of the Atom, can only use the standard allowed characters
-    #[getset(get = "pub")]
-    element: String, // "N"
+    /// The element of the Atom, `None` if it is not known
+    #[getset(get_copy = "pub")]
+    element: Option<Element>, // "N"
 
     /// The charge of the Atom
     #[getset(get = "pub", set = "pub")]
     charge: isize,
+
+    /// The Sybyl atom type, as used in MOL2 files
+    atom_type: Option<String>, // "C.ar"
+
+    /// The partial charge of the Atom, `None` if it is not known
+    #[getset(get_copy = "pub")]
+    partial_charge: Option<f64>, // "-0.4157"
+
+    /// The radius of the Atom (Å), as used in PQR files, `None` if it is not known
+    #[getset(get_copy = "pub")]
+    radius: Option<f64>, // "1.8240"
+
+    /// The velocity of the Atom (Å/ps), as given by simulations, `None` if it is not known
+    #[getset(get_copy = "pub")]
+    velocity: Option<Vector3>,
 }
 
 impl Atom {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         hetero: bool,
         serial_number: usize,
@@ -89,8 +107,11 @@ impl Atom {
         element: &str,
         charge: isize,
     ) -> Option<Atom> {
+        let valid_element = validator::valid_identifier(element);
+        // unknown symbols, like "X" or "Du", leave the element unknown as in the reader
+        let element = Element::from_symbol(element);
         if validator::valid_identifier(atom_name)
-        && validator::valid_identifier(element)
+        && valid_element
         && x.is_finite()
         && y.is_finite()
         && z.is_finite()
@@ -112,8 +133,12 @@ impl Atom {
                 occupancy,
                 temp_factor,
                 segment_id: None,
-                element: element.trim().to_ascii_uppercase(),
+                element,
                 charge,
+                atom_type: None,
+                partial_charge: None,
+                radius: None,
+                velocity: None,
             })
         } else {
             None
@@ -129,6 +154,80 @@ impl Atom {
         self.i_code.as_deref()
     }
 
+    /// Sets the alternate location indicator, an empty indicator removes it.
+    pub fn set_alt_location(&mut self, new_alt_location: &str) -> Result<(), PDBError> {
+        match new_alt_location.trim() {
+            "" => self.alt_location = None,
+            alt_location if validator::valid_identifier(alt_location) && alt_location.len() == 1 => self.alt_location = Some(alt_location.to_owned()),
+            _ => return Err(PDBError::InvalidValue(
+                format!(
+                "The new alternate location has invalid characters or length for atom {}\n\tinvalid value: {}",
+                self.serial_number, new_alt_location
+            ))),
+        }
+        Ok(())
+    }
+
+    /// Sets the insertion code, an empty code removes it.
+    pub fn set_i_code(&mut self, new_i_code: &str) -> Result<(), PDBError> {
+        match new_i_code.trim() {
+            "" => self.i_code = None,
+            i_code if validator::valid_identifier(i_code) && i_code.len() == 1 => self.i_code = Some(i_code.to_ascii_uppercase()),
+            _ => return Err(PDBError::InvalidValue(
+                format!(
+                "The new insertion code has invalid characters or length for atom {}\n\tinvalid value: {}",
+                self.serial_number, new_i_code
+            ))),
+        }
+        Ok(())
+    }
+
+    pub fn atom_type(&self) -> Option<&str> {
+        self.atom_type.as_deref()
+    }
+
+    /// Sets the Sybyl atom type, an empty type makes it unknown.
+    pub fn set_atom_type(&mut self, new_atom_type: &str) -> Result<(), PDBError> {
+        match new_atom_type.trim() {
+            "" => self.atom_type = None,
+            atom_type if validator::valid_identifier(atom_type) => self.atom_type = Some(atom_type.to_owned()),
+            _ => return Err(PDBError::InvalidValue(
+                format!(
+                "The new atom type has invalid characters for atom {}\n\tinvalid value: {}",
+                self.serial_number, new_atom_type
+            ))),
+        }
+        Ok(())
+    }
+
+    /// The element of the Atom or, if it is unknown, the element inferred
+    /// from the atom and residue name (see `Element::infer`)
+    pub fn inferred_element(&self) -> Option<Element> {
+        self.element.or_else(|| Element::infer(&self.atom_name, &self.res_name))
+    }
+
+    /// Whether this Atom is a hydrogen (or deuterium), judged by the element or,
+    /// if it is unknown, by the atom name
+    pub fn is_hydrogen(&self) -> bool {
+        self.inferred_element().is_some_and(|e| e.is_hydrogen())
+    }
+
+    /// Whether this Atom belongs to a water molecule
+    pub fn is_water(&self) -> bool {
+        matches!(self.res_name.as_str(), "HOH" | "WAT" | "H2O" | "DOD" | "SOL")
+    }
+
+    /// Whether this Atom is a backbone atom of a standard amino acid
+    pub fn is_backbone(&self) -> bool {
+        is_amino_acid(&self.res_name)
+            && matches!(self.atom_name.as_str(), "N" | "CA" | "C" | "O" | "OXT" | "H" | "HA" | "H1" | "H2" | "H3")
+    }
+
+    /// The identifier of the residue this Atom belongs to
+    pub fn residue_id(&self) -> ResidueId {
+        ResidueId::new(&self.chain_id, self.res_seq, self.i_code.as_deref())
+    }
+
     pub fn set_name(&mut self, new_name: &str) -> Result<(), PDBError>{
         if validator::valid_identifier(new_name) {
             self.atom_name = new_name.trim().to_ascii_uppercase();
@@ -159,6 +258,36 @@ impl Atom {
         (self.x, self.y, self.z)
     }
 
+    /// The position of the Atom as a `Vector3`
+    pub fn coords(&self) -> Vector3 {
+        Vector3::new(self.x, self.y, self.z)
+    }
+
+    /// The distance to another atom (Å)
+    pub fn distance(&self, other: &Atom) -> f64 {
+        self.coords().distance(&other.coords())
+    }
+
+    /// The squared distance to another atom (Å²), cheaper than `distance` for comparisons
+    pub fn distance_squared(&self, other: &Atom) -> f64 {
+        self.coords().distance_squared(&other.coords())
+    }
+
+    /// The angle a-b-c in degrees, with atom `b` at the vertex
+    pub fn angle(a: &Atom, b: &Atom, c: &Atom) -> f64 {
+        geometry::angle(&a.coords(), &b.coords(), &c.coords())
+    }
+
+    /// The dihedral angle a-b-c-d in degrees, in the range (-180, 180]
+    pub fn dihedral(a: &Atom, b: &Atom, c: &Atom, d: &Atom) -> f64 {
+        geometry::dihedral(&a.coords(), &b.coords(), &c.coords(), &d.coords())
+    }
+
+    /// The atomic mass (Da) of the Atom, from the element or the inferred element, 0 if unknown
+    pub fn mass(&self) -> f64 {
+        self.inferred_element().map(|e| e.mass()).unwrap_or(0.0)
+    }
+
     pub fn set_position(&mut self, new_position: (f64, f64, f64)) -> Result<(), PDBError> {
         if new_position.0.is_finite() && new_position.1.is_finite() && new_position.2.is_finite() {
             self.x = new_position.0;
@@ -239,9 +368,52 @@ impl Atom {
         }
     }
 
+    pub fn set_partial_charge(&mut self, new_partial_charge: Option<f64>) -> Result<(), PDBError> {
+        match new_partial_charge {
+            Some(charge) if !charge.is_finite() => Err(PDBError::InvalidValue(
+                format!(
+                "The value of the new partial charge is not finite for atom {}\n\tinvalid value: {}",
+                self.serial_number, charge
+            ))),
+            _ => {
+                self.partial_charge = new_partial_charge;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn set_radius(&mut self, new_radius: Option<f64>) -> Result<(), PDBError> {
+        match new_radius {
+            Some(radius) if !radius.is_finite() || radius < 0.0 => Err(PDBError::InvalidValue(
+                format!(
+                "The value of the new radius is not finite or negative for atom {}\n\tinvalid value: {}",
+                self.serial_number, radius
+            ))),
+            _ => {
+                self.radius = new_radius;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn set_velocity(&mut self, new_velocity: Option<Vector3>) -> Result<(), PDBError> {
+        match new_velocity {
+            Some(velocity) if !velocity.is_finite() => Err(PDBError::InvalidValue(
+                format!(
+                "The value of the new velocity is not finite for atom {}\n\tinvalid value: {}",
+                self.serial_number, velocity
+            ))),
+            _ => {
+                self.velocity = new_velocity;
+                Ok(())
+            }
+        }
+    }
+
+    /// Sets the element by its symbol, an empty or unknown symbol makes the element unknown.
     pub fn set_element(&mut self, new_element: &str) -> Result<(), PDBError> {
         if validator::valid_identifier(new_element) {
-            self.element = new_element.trim().to_ascii_uppercase();
+            self.element = Element::from_symbol(new_element.trim());
             Ok(())
         } else {
             Err(PDBError::InvalidValue(
@@ -252,7 +424,6 @@ impl Atom {
         }
     }
 
-
     pub fn set_chain_id(&mut self, new_id: &str) -> Result<(), PDBError> {
         if validator::valid_identifier(new_id) && new_id.len() == 1 {
             self.chain_id = new_id.trim().to_ascii_uppercase();
@@ -268,6 +439,34 @@ impl Atom {
 
 }
 
+/// The residue names of the 20 standard amino acids
+pub(crate) const AMINO_ACIDS: [&str; 20] = [
+    "ALA", "ARG", "ASN", "ASP", "CYS", "GLN", "GLU", "GLY", "HIS", "ILE",
+    "LEU", "LYS", "MET", "PHE", "PRO", "SER", "THR", "TRP", "TYR", "VAL",
+];
+
+/// Maximum C-N distance (Å) of two consecutive residues still considered linked by a peptide bond
+pub(crate) const MAX_PEPTIDE_BOND: f64 = 2.5;
+
+/// Residue names used by AMBER, CHARMM and GROMACS force fields for protonation states
+/// and disulfide bonded cysteines of the standard amino acids
+const FORCE_FIELD_AMINO_ACIDS: [&str; 14] = [
+    "HID", "HIE", "HIP", "HSD", "HSE", "HSP", "CYX", "CYM", "ASH", "GLH", "LYN", "ARN", "TYM", "HISH",
+];
+
+/// Whether `res_name` is one of the 20 standard amino acids
+pub(crate) fn is_amino_acid(res_name: &str) -> bool {
+    AMINO_ACIDS.contains(&res_name)
+}
+
+/// Whether `res_name` is a standard amino acid under its own or a force field name,
+/// including the AMBER terminal residues like "NALA" and "CHIE"
+pub(crate) fn is_protein_residue(res_name: &str) -> bool {
+    let known = |name: &str| is_amino_acid(name) || FORCE_FIELD_AMINO_ACIDS.contains(&name);
+    known(res_name)
+        || (res_name.len() == 4 && (res_name.starts_with('N') || res_name.starts_with('C')) && known(&res_name[1..]))
+}
+
 impl fmt::Display for Atom {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let atom = match self.hetero {
@@ -280,7 +479,7 @@ impl fmt::Display for Atom {
             "{{\n\t'type' : '{}',\n\t'number' : {},\n\t'element' : '{}',\n\t'residue' : '{}',\n\t'chain' : '{}',\n\t'position' : ({}, {}, {}),\n\t'occupancy' : {},\n\t'temp_factor' : {}\n}}",
             atom,
             self.serial_number,
-            self.element,
+            self.element.map(|e| e.symbol()).unwrap_or(""),
             self.res_name,
             self.chain_id,
             self.x,
@@ -294,7 +493,7 @@ impl fmt::Display for Atom {
 
 impl Clone for Atom {
     fn clone(&self) -> Self {
-        let atom = Atom::new(
+        let mut atom = Atom::new(
             self.hetero,
             self.serial_number,
             &self.atom_name,
@@ -306,10 +505,16 @@ impl Clone for Atom {
             self.z,
             self.occupancy,
             self.temp_factor,
-            &self.element,
+            self.element.map(|e| e.symbol()).unwrap_or(""),
             self.charge
         )
         .expect("Invalid Atom properties in a clone");
+        atom.alt_location = self.alt_location.clone();
+        atom.i_code = self.i_code.clone();
+        atom.atom_type = self.atom_type.clone();
+        atom.partial_charge = self.partial_charge;
+        atom.radius = self.radius;
+        atom.velocity = self.velocity;
         atom
     }
 }
@@ -331,7 +536,7 @@ impl Eq for Atom {}
 
 impl PartialOrd for Atom {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.serial_number.cmp(&other.serial_number))
+        Some(self.cmp(other))
     }
 }
 
@@ -345,7 +550,8 @@ impl Ord for Atom {
 
 #[cfg(test)]
 mod tests {
-    use super::Atom;
+    use super::{Atom, is_protein_residue};
+    use crate::structs::Element;
     //use anyhow::Result;
     
     #[test]
@@ -360,4 +566,22 @@ mod tests {
         assert!(atom.set_name("O").is_ok());
         assert!(atom.set_name("酸素").is_err());
     }
+
+    #[test]
+    fn unknown_element() {
+        let atom = Atom::new(true, 1, "CA", "CA", "A", 1, 0.0, 0.0, 0.0, 1.0, 0.0, "X", 0).unwrap();
+        assert_eq!(atom.element(), None);
+        assert_eq!(atom.inferred_element(), Some(Element::Ca));
+        assert!(Atom::new(false, 1, "CA", "ALA", "A", 1, 0.0, 0.0, 0.0, 1.0, 0.0, "酸素", 0).is_none());
+    }
+
+    #[test]
+    fn protein_residue_names() {
+        for name in ["ALA", "HIE", "CYX", "NALA", "CHIP"] {
+            assert!(is_protein_residue(name), "{}", name);
+        }
+        for name in ["LIG", "HOH", "NAG", "CA"] {
+            assert!(!is_protein_residue(name), "{}", name);
+        }
+    }
 }
@@ -0,0 +1,366 @@
+use std::fmt;
+
+use crate::structs::atom::is_amino_acid;
+
+/// A chemical element. Deuterium has its own variant as it has its own symbol in structure files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Element {
+    H,
+    D,
+    He,
+    Li,
+    Be,
+    B,
+    C,
+    N,
+    O,
+    F,
+    Ne,
+    Na,
+    Mg,
+    Al,
+    Si,
+    P,
+    S,
+    Cl,
+    Ar,
+    K,
+    Ca,
+    Sc,
+    Ti,
+    V,
+    Cr,
+    Mn,
+    Fe,
+    Co,
+    Ni,
+    Cu,
+    Zn,
+    Ga,
+    Ge,
+    As,
+    Se,
+    Br,
+    Kr,
+    Rb,
+    Sr,
+    Y,
+    Zr,
+    Nb,
+    Mo,
+    Tc,
+    Ru,
+    Rh,
+    Pd,
+    Ag,
+    Cd,
+    In,
+    Sn,
+    Sb,
+    Te,
+    I,
+    Xe,
+    Cs,
+    Ba,
+    La,
+    Ce,
+    Pr,
+    Nd,
+    Pm,
+    Sm,
+    Eu,
+    Gd,
+    Tb,
+    Dy,
+    Ho,
+    Er,
+    Tm,
+    Yb,
+    Lu,
+    Hf,
+    Ta,
+    W,
+    Re,
+    Os,
+    Ir,
+    Pt,
+    Au,
+    Hg,
+    Tl,
+    Pb,
+    Bi,
+    Po,
+    At,
+    Rn,
+    U,
+}
+
+struct ElementData {
+    element: Element,
+    symbol: &'static str,
+    atomic_number: usize,
+    /// Standard atomic weight (Da)
+    mass: f64,
+    /// Single bond covalent radius (Å), Cordero et al. 2008
+    covalent_radius: f64,
+    /// Van der Waals radius (Å), Bondi 1964 with Rowland and Taylor 1996 for H and
+    /// the common metals, Alvarez 2013 for elements missing there
+    vdw_radius: f64,
+    /// Pauling electronegativity
+    electronegativity: Option<f64>,
+}
+
+/// The element data in the order of the `Element` variants
+const TABLE: [ElementData; 88] = [
+    ElementData { element: Element::H, symbol: "H", atomic_number: 1, mass: 1.008, covalent_radius: 0.31, vdw_radius: 1.10, electronegativity: Some(2.20) },
+    ElementData { element: Element::D, symbol: "D", atomic_number: 1, mass: 2.014, covalent_radius: 0.31, vdw_radius: 1.10, electronegativity: Some(2.20) },
+    ElementData { element: Element::He, symbol: "He", atomic_number: 2, mass: 4.0026, covalent_radius: 0.28, vdw_radius: 1.40, electronegativity: None },
+    ElementData { element: Element::Li, symbol: "Li", atomic_number: 3, mass: 6.94, covalent_radius: 1.28, vdw_radius: 1.82, electronegativity: Some(0.98) },
+    ElementData { element: Element::Be, symbol: "Be", atomic_number: 4, mass: 9.0122, covalent_radius: 0.96, vdw_radius: 1.53, electronegativity: Some(1.57) },
+    ElementData { element: Element::B, symbol: "B", atomic_number: 5, mass: 10.81, covalent_radius: 0.84, vdw_radius: 1.92, electronegativity: Some(2.04) },
+    ElementData { element: Element::C, symbol: "C", atomic_number: 6, mass: 12.011, covalent_radius: 0.76, vdw_radius: 1.70, electronegativity: Some(2.55) },
+    ElementData { element: Element::N, symbol: "N", atomic_number: 7, mass: 14.007, covalent_radius: 0.71, vdw_radius: 1.55, electronegativity: Some(3.04) },
+    ElementData { element: Element::O, symbol: "O", atomic_number: 8, mass: 15.999, covalent_radius: 0.66, vdw_radius: 1.52, electronegativity: Some(3.44) },
+    ElementData { element: Element::F, symbol: "F", atomic_number: 9, mass: 18.998, covalent_radius: 0.57, vdw_radius: 1.47, electronegativity: Some(3.98) },
+    ElementData { element: Element::Ne, symbol: "Ne", atomic_number: 10, mass: 20.180, covalent_radius: 0.58, vdw_radius: 1.54, electronegativity: None },
+    ElementData { element: Element::Na, symbol: "Na", atomic_number: 11, mass: 22.990, covalent_radius: 1.66, vdw_radius: 2.27, electronegativity: Some(0.93) },
+    ElementData { element: Element::Mg, symbol: "Mg", atomic_number: 12, mass: 24.305, covalent_radius: 1.41, vdw_radius: 1.73, electronegativity: Some(1.31) },
+    ElementData { element: Element::Al, symbol: "Al", atomic_number: 13, mass: 26.982, covalent_radius: 1.21, vdw_radius: 1.84, electronegativity: Some(1.61) },
+    ElementData { element: Element::Si, symbol: "Si", atomic_number: 14, mass: 28.085, covalent_radius: 1.11, vdw_radius: 2.10, electronegativity: Some(1.90) },
+    ElementData { element: Element::P, symbol: "P", atomic_number: 15, mass: 30.974, covalent_radius: 1.07, vdw_radius: 1.80, electronegativity: Some(2.19) },
+    ElementData { element: Element::S, symbol: "S", atomic_number: 16, mass: 32.06, covalent_radius: 1.05, vdw_radius: 1.80, electronegativity: Some(2.58) },
+    ElementData { element: Element::Cl, symbol: "Cl", atomic_number: 17, mass: 35.45, covalent_radius: 1.02, vdw_radius: 1.75, electronegativity: Some(3.16) },
+    ElementData { element: Element::Ar, symbol: "Ar", atomic_number: 18, mass: 39.948, covalent_radius: 1.06, vdw_radius: 1.88, electronegativity: None },
+    ElementData { element: Element::K, symbol: "K", atomic_number: 19, mass: 39.098, covalent_radius: 2.03, vdw_radius: 2.75, electronegativity: Some(0.82) },
+    ElementData { element: Element::Ca, symbol: "Ca", atomic_number: 20, mass: 40.078, covalent_radius: 1.76, vdw_radius: 2.31, electronegativity: Some(1.00) },
+    ElementData { element: Element::Sc, symbol: "Sc", atomic_number: 21, mass: 44.956, covalent_radius: 1.70, vdw_radius: 2.15, electronegativity: Some(1.36) },
+    ElementData { element: Element::Ti, symbol: "Ti", atomic_number: 22, mass: 47.867, covalent_radius: 1.60, vdw_radius: 2.11, electronegativity: Some(1.54) },
+    ElementData { element: Element::V, symbol: "V", atomic_number: 23, mass: 50.942, covalent_radius: 1.53, vdw_radius: 2.07, electronegativity: Some(1.63) },
+    ElementData { element: Element::Cr, symbol: "Cr", atomic_number: 24, mass: 51.996, covalent_radius: 1.39, vdw_radius: 2.06, electronegativity: Some(1.66) },
+    ElementData { element: Element::Mn, symbol: "Mn", atomic_number: 25, mass: 54.938, covalent_radius: 1.39, vdw_radius: 1.97, electronegativity: Some(1.55) },
+    ElementData { element: Element::Fe, symbol: "Fe", atomic_number: 26, mass: 55.845, covalent_radius: 1.32, vdw_radius: 1.94, electronegativity: Some(1.83) },
+    ElementData { element: Element::Co, symbol: "Co", atomic_number: 27, mass: 58.933, covalent_radius: 1.26, vdw_radius: 1.92, electronegativity: Some(1.88) },
+    ElementData { element: Element::Ni, symbol: "Ni", atomic_number: 28, mass: 58.693, covalent_radius: 1.24, vdw_radius: 1.63, electronegativity: Some(1.91) },
+    ElementData { element: Element::Cu, symbol: "Cu", atomic_number: 29, mass: 63.546, covalent_radius: 1.32, vdw_radius: 1.40, electronegativity: Some(1.90) },
+    ElementData { element: Element::Zn, symbol: "Zn", atomic_number: 30, mass: 65.38, covalent_radius: 1.22, vdw_radius: 1.39, electronegativity: Some(1.65) },
+    ElementData { element: Element::Ga, symbol: "Ga", atomic_number: 31, mass: 69.723, covalent_radius: 1.22, vdw_radius: 1.87, electronegativity: Some(1.81) },
+    ElementData { element: Element::Ge, symbol: "Ge", atomic_number: 32, mass: 72.630, covalent_radius: 1.20, vdw_radius: 2.11, electronegativity: Some(2.01) },
+    ElementData { element: Element::As, symbol: "As", atomic_number: 33, mass: 74.922, covalent_radius: 1.19, vdw_radius: 1.85, electronegativity: Some(2.18) },
+    ElementData { element: Element::Se, symbol: "Se", atomic_number: 34, mass: 78.971, covalent_radius: 1.20, vdw_radius: 1.90, electronegativity: Some(2.55) },
+    ElementData { element: Element::Br, symbol: "Br", atomic_number: 35, mass: 79.904, covalent_radius: 1.20, vdw_radius: 1.85, electronegativity: Some(2.96) },
+    ElementData { element: Element::Kr, symbol: "Kr", atomic_number: 36, mass: 83.798, covalent_radius: 1.16, vdw_radius: 2.02, electronegativity: Some(3.00) },
+    ElementData { element: Element::Rb, symbol: "Rb", atomic_number: 37, mass: 85.468, covalent_radius: 2.20, vdw_radius: 3.03, electronegativity: Some(0.82) },
+    ElementData { element: Element::Sr, symbol: "Sr", atomic_number: 38, mass: 87.62, covalent_radius: 1.95, vdw_radius: 2.49, electronegativity: Some(0.95) },
+    ElementData { element: Element::Y, symbol: "Y", atomic_number: 39, mass: 88.906, covalent_radius: 1.90, vdw_radius: 2.32, electronegativity: Some(1.22) },
+    ElementData { element: Element::Zr, symbol: "Zr", atomic_number: 40, mass: 91.224, covalent_radius: 1.75, vdw_radius: 2.23, electronegativity: Some(1.33) },
+    ElementData { element: Element::Nb, symbol: "Nb", atomic_number: 41, mass: 92.906, covalent_radius: 1.64, vdw_radius: 2.18, electronegativity: Some(1.60) },
+    ElementData { element: Element::Mo, symbol: "Mo", atomic_number: 42, mass: 95.95, covalent_radius: 1.54, vdw_radius: 2.17, electronegativity: Some(2.16) },
+    ElementData { element: Element::Tc, symbol: "Tc", atomic_number: 43, mass: 98.0, covalent_radius: 1.47, vdw_radius: 2.16, electronegativity: Some(1.90) },
+    ElementData { element: Element::Ru, symbol: "Ru", atomic_number: 44, mass: 101.07, covalent_radius: 1.46, vdw_radius: 2.13, electronegativity: Some(2.20) },
+    ElementData { element: Element::Rh, symbol: "Rh", atomic_number: 45, mass: 102.91, covalent_radius: 1.42, vdw_radius: 2.10, electronegativity: Some(2.28) },
+    ElementData { element: Element::Pd, symbol: "Pd", atomic_number: 46, mass: 106.42, covalent_radius: 1.39, vdw_radius: 1.63, electronegativity: Some(2.20) },
+    ElementData { element: Element::Ag, symbol: "Ag", atomic_number: 47, mass: 107.87, covalent_radius: 1.45, vdw_radius: 1.72, electronegativity: Some(1.93) },
+    ElementData { element: Element::Cd, symbol: "Cd", atomic_number: 48, mass: 112.41, covalent_radius: 1.44, vdw_radius: 1.58, electronegativity: Some(1.69) },
+    ElementData { element: Element::In, symbol: "In", atomic_number: 49, mass: 114.82, covalent_radius: 1.42, vdw_radius: 1.93, electronegativity: Some(1.78) },
+    ElementData { element: Element::Sn, symbol: "Sn", atomic_number: 50, mass: 118.71, covalent_radius: 1.39, vdw_radius: 2.17, electronegativity: Some(1.96) },
+    ElementData { element: Element::Sb, symbol: "Sb", atomic_number: 51, mass: 121.76, covalent_radius: 1.39, vdw_radius: 2.06, electronegativity: Some(2.05) },
+    ElementData { element: Element::Te, symbol: "Te", atomic_number: 52, mass: 127.60, covalent_radius: 1.38, vdw_radius: 2.06, electronegativity: Some(2.10) },
+    ElementData { element: Element::I, symbol: "I", atomic_number: 53, mass: 126.90, covalent_radius: 1.39, vdw_radius: 1.98, electronegativity: Some(2.66) },
+    ElementData { element: Element::Xe, symbol: "Xe", atomic_number: 54, mass: 131.29, covalent_radius: 1.40, vdw_radius: 2.16, electronegativity: Some(2.60) },
+    ElementData { element: Element::Cs, symbol: "Cs", atomic_number: 55, mass: 132.91, covalent_radius: 2.44, vdw_radius: 3.43, electronegativity: Some(0.79) },
+    ElementData { element: Element::Ba, symbol: "Ba", atomic_number: 56, mass: 137.33, covalent_radius: 2.15, vdw_radius: 2.68, electronegativity: Some(0.89) },
+    ElementData { element: Element::La, symbol: "La", atomic_number: 57, mass: 138.91, covalent_radius: 2.07, vdw_radius: 2.43, electronegativity: Some(1.10) },
+    ElementData { element: Element::Ce, symbol: "Ce", atomic_number: 58, mass: 140.12, covalent_radius: 2.04, vdw_radius: 2.42, electronegativity: Some(1.12) },
+    ElementData { element: Element::Pr, symbol: "Pr", atomic_number: 59, mass: 140.91, covalent_radius: 2.03, vdw_radius: 2.40, electronegativity: Some(1.13) },
+    ElementData { element: Element::Nd, symbol: "Nd", atomic_number: 60, mass: 144.24, covalent_radius: 2.01, vdw_radius: 2.39, electronegativity: Some(1.14) },
+    ElementData { element: Element::Pm, symbol: "Pm", atomic_number: 61, mass: 145.0, covalent_radius: 1.99, vdw_radius: 2.38, electronegativity: Some(1.13) },
+    ElementData { element: Element::Sm, symbol: "Sm", atomic_number: 62, mass: 150.36, covalent_radius: 1.98, vdw_radius: 2.36, electronegativity: Some(1.17) },
+    ElementData { element: Element::Eu, symbol: "Eu", atomic_number: 63, mass: 151.96, covalent_radius: 1.98, vdw_radius: 2.35, electronegativity: Some(1.20) },
+    ElementData { element: Element::Gd, symbol: "Gd", atomic_number: 64, mass: 157.25, covalent_radius: 1.96, vdw_radius: 2.34, electronegativity: Some(1.20) },
+    ElementData { element: Element::Tb, symbol: "Tb", atomic_number: 65, mass: 158.93, covalent_radius: 1.94, vdw_radius: 2.33, electronegativity: Some(1.10) },
+    ElementData { element: Element::Dy, symbol: "Dy", atomic_number: 66, mass: 162.50, covalent_radius: 1.92, vdw_radius: 2.31, electronegativity: Some(1.22) },
+    ElementData { element: Element::Ho, symbol: "Ho", atomic_number: 67, mass: 164.93, covalent_radius: 1.92, vdw_radius: 2.30, electronegativity: Some(1.23) },
+    ElementData { element: Element::Er, symbol: "Er", atomic_number: 68, mass: 167.26, covalent_radius: 1.89, vdw_radius: 2.29, electronegativity: Some(1.24) },
+    ElementData { element: Element::Tm, symbol: "Tm", atomic_number: 69, mass: 168.93, covalent_radius: 1.90, vdw_radius: 2.27, electronegativity: Some(1.25) },
+    ElementData { element: Element::Yb, symbol: "Yb", atomic_number: 70, mass: 173.05, covalent_radius: 1.87, vdw_radius: 2.26, electronegativity: Some(1.10) },
+    ElementData { element: Element::Lu, symbol: "Lu", atomic_number: 71, mass: 174.97, covalent_radius: 1.87, vdw_radius: 2.24, electronegativity: Some(1.27) },
+    ElementData { element: Element::Hf, symbol: "Hf", atomic_number: 72, mass: 178.49, covalent_radius: 1.75, vdw_radius: 2.23, electronegativity: Some(1.30) },
+    ElementData { element: Element::Ta, symbol: "Ta", atomic_number: 73, mass: 180.95, covalent_radius: 1.70, vdw_radius: 2.22, electronegativity: Some(1.50) },
+    ElementData { element: Element::W, symbol: "W", atomic_number: 74, mass: 183.84, covalent_radius: 1.62, vdw_radius: 2.18, electronegativity: Some(2.36) },
+    ElementData { element: Element::Re, symbol: "Re", atomic_number: 75, mass: 186.21, covalent_radius: 1.51, vdw_radius: 2.16, electronegativity: Some(1.90) },
+    ElementData { element: Element::Os, symbol: "Os", atomic_number: 76, mass: 190.23, covalent_radius: 1.44, vdw_radius: 2.16, electronegativity: Some(2.20) },
+    ElementData { element: Element::Ir, symbol: "Ir", atomic_number: 77, mass: 192.22, covalent_radius: 1.41, vdw_radius: 2.13, electronegativity: Some(2.20) },
+    ElementData { element: Element::Pt, symbol: "Pt", atomic_number: 78, mass: 195.08, covalent_radius: 1.36, vdw_radius: 1.75, electronegativity: Some(2.28) },
+    ElementData { element: Element::Au, symbol: "Au", atomic_number: 79, mass: 196.97, covalent_radius: 1.36, vdw_radius: 1.66, electronegativity: Some(2.54) },
+    ElementData { element: Element::Hg, symbol: "Hg", atomic_number: 80, mass: 200.59, covalent_radius: 1.32, vdw_radius: 1.55, electronegativity: Some(2.00) },
+    ElementData { element: Element::Tl, symbol: "Tl", atomic_number: 81, mass: 204.38, covalent_radius: 1.45, vdw_radius: 1.96, electronegativity: Some(1.62) },
+    ElementData { element: Element::Pb, symbol: "Pb", atomic_number: 82, mass: 207.2, covalent_radius: 1.46, vdw_radius: 2.02, electronegativity: Some(2.33) },
+    ElementData { element: Element::Bi, symbol: "Bi", atomic_number: 83, mass: 208.98, covalent_radius: 1.48, vdw_radius: 2.07, electronegativity: Some(2.02) },
+    ElementData { element: Element::Po, symbol: "Po", atomic_number: 84, mass: 209.0, covalent_radius: 1.40, vdw_radius: 1.97, electronegativity: Some(2.00) },
+    ElementData { element: Element::At, symbol: "At", atomic_number: 85, mass: 210.0, covalent_radius: 1.50, vdw_radius: 2.02, electronegativity: Some(2.20) },
+    ElementData { element: Element::Rn, symbol: "Rn", atomic_number: 86, mass: 222.0, covalent_radius: 1.50, vdw_radius: 2.20, electronegativity: Some(2.20) },
+    ElementData { element: Element::U, symbol: "U", atomic_number: 92, mass: 238.03, covalent_radius: 1.96, vdw_radius: 1.86, electronegativity: Some(1.38) },
+];
+
+/// Residue names whose atoms only have one letter elements (H, C, N, O, P, S)
+const ORGANIC_RESIDUES: [&str; 13] = ["A", "C", "G", "U", "I", "DA", "DC", "DG", "DT", "DU", "DI", "HOH", "WAT"];
+
+/// Two letter elements recognized in atom names without column alignment. Symbols that
+/// are also common atom names of carbons or nitrogens (CA, CD, NA, ND, ...) are left out.
+const UNAMBIGUOUS_TWO_LETTERS: [&str; 12] = ["CL", "BR", "FE", "ZN", "MG", "MN", "CU", "NI", "SE", "LI", "AL", "SI"];
+
+impl Element {
+    fn data(&self) -> &'static ElementData {
+        &TABLE[*self as usize]
+    }
+
+    /// All elements in order of atomic number, deuterium directly after hydrogen
+    pub fn all() -> impl Iterator<Item = Element> {
+        TABLE.iter().map(|d| d.element)
+    }
+
+    /// The element with the given symbol, case insensitive, `None` if unknown
+    pub fn from_symbol(symbol: &str) -> Option<Element> {
+        let symbol = symbol.trim();
+        TABLE.iter().find(|d| d.symbol.eq_ignore_ascii_case(symbol)).map(|d| d.element)
+    }
+
+    /// The element with the given atomic number, hydrogen for 1
+    pub fn from_atomic_number(atomic_number: usize) -> Option<Element> {
+        TABLE.iter().find(|d| d.atomic_number == atomic_number).map(|d| d.element)
+    }
+
+    /// The symbol in standard capitalization, e.g. "Cl"
+    pub fn symbol(&self) -> &'static str {
+        self.data().symbol
+    }
+
+    pub fn atomic_number(&self) -> usize {
+        self.data().atomic_number
+    }
+
+    /// The standard atomic weight (Da)
+    pub fn mass(&self) -> f64 {
+        self.data().mass
+    }
+
+    /// The single bond covalent radius (Å)
+    pub fn covalent_radius(&self) -> f64 {
+        self.data().covalent_radius
+    }
+
+    /// The van der Waals radius (Å)
+    pub fn vdw_radius(&self) -> f64 {
+        self.data().vdw_radius
+    }
+
+    /// The Pauling electronegativity, `None` for the noble gases without one
+    pub fn electronegativity(&self) -> Option<f64> {
+        self.data().electronegativity
+    }
+
+    /// Whether this is hydrogen or deuterium
+    pub fn is_hydrogen(&self) -> bool {
+        matches!(self, Element::H | Element::D)
+    }
+
+    pub fn is_halogen(&self) -> bool {
+        matches!(self, Element::F | Element::Cl | Element::Br | Element::I | Element::At)
+    }
+
+    /// Whether this is a metal, metalloids like boron, silicon or arsenic are not
+    pub fn is_metal(&self) -> bool {
+        !matches!(
+            self,
+            Element::H | Element::D | Element::He | Element::B | Element::C | Element::N | Element::O
+                | Element::F | Element::Ne | Element::Si | Element::P | Element::S | Element::Cl
+                | Element::Ar | Element::Ge | Element::As | Element::Se | Element::Br | Element::Kr
+                | Element::Sb | Element::Te | Element::I | Element::Xe | Element::At | Element::Rn
+        )
+    }
+
+    /// Infers the element from an atom name and the name of its residue.
+    /// `atom_name` may be the untrimmed four column field of a PDB file, where a blank or a
+    /// digit in the first column means a one letter element ("CA  " is calcium, " CA " is
+    /// an alpha carbon). Without that alignment the residue decides: standard residues only
+    /// contain one letter elements, a residue named like its only atom is an ion (CA in CA),
+    /// and otherwise only two letter elements that can not be mistaken for a carbon or
+    /// nitrogen name (like CL or FE) are recognized.
+    pub fn infer(atom_name: &str, res_name: &str) -> Option<Element> {
+        let name = atom_name.trim().to_ascii_uppercase();
+        let res_name = res_name.trim().to_ascii_uppercase();
+        let letters: String = name
+            .chars()
+            .skip_while(|c| c.is_ascii_digit())
+            .take_while(|c| c.is_ascii_alphabetic())
+            .collect();
+        let one = || letters.get(..1).and_then(Element::from_symbol);
+        let two = || letters.get(..2).and_then(Element::from_symbol);
+        if letters.is_empty() {
+            return None;
+        }
+        if name == res_name {
+            if let Some(element) = Element::from_symbol(&letters) {
+                return Some(element);
+            }
+        }
+        if is_amino_acid(&res_name) || ORGANIC_RESIDUES.contains(&res_name.as_str()) {
+            return one();
+        }
+        if (res_name == "MSE" || res_name == "SEC") && letters.starts_with("SE") {
+            return Some(Element::Se);
+        }
+        let first = atom_name.chars().next().unwrap_or(' ');
+        if atom_name.len() == 4 {
+            // hydrogens with four character names start in the first column, e.g. "HG21"
+            if first == ' ' || first.is_ascii_digit() || (letters.starts_with('H') && name.len() == 4) {
+                return one();
+            }
+            return two().or_else(one);
+        }
+        match letters.get(..2) {
+            Some(symbol) if UNAMBIGUOUS_TWO_LETTERS.contains(&symbol) => two(),
+            _ => one().or_else(two),
+        }
+    }
+}
+
+impl fmt::Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_is_in_variant_order() {
+        for (index, data) in TABLE.iter().enumerate() {
+            assert_eq!(data.element as usize, index);
+        }
+        assert_eq!(Element::from_symbol("cl"), Some(Element::Cl));
+        assert_eq!(Element::from_atomic_number(26), Some(Element::Fe));
+        assert_eq!(Element::Fe.symbol(), "Fe");
+        assert!(Element::Zn.is_metal() && !Element::Se.is_metal());
+    }
+
+    #[test]
+    fn infer_from_names() {
+        assert_eq!(Element::infer(" CA ", "ALA"), Some(Element::C));
+        assert_eq!(Element::infer("CA  ", "CA"), Some(Element::Ca));
+        assert_eq!(Element::infer("CA", "CA"), Some(Element::Ca));
+        assert_eq!(Element::infer("CA", "ATP"), Some(Element::C));
+        assert_eq!(Element::infer("HG21", "ILE"), Some(Element::H));
+        assert_eq!(Element::infer("HG21", "LIG"), Some(Element::H));
+        assert_eq!(Element::infer("1HG2", "LIG"), Some(Element::H));
+        assert_eq!(Element::infer("HG  ", "HG"), Some(Element::Hg));
+        assert_eq!(Element::infer("CL1", "LIG"), Some(Element::Cl));
+        assert_eq!(Element::infer("FE", "HEM"), Some(Element::Fe));
+        assert_eq!(Element::infer("NA", "HEM"), Some(Element::N));
+        assert_eq!(Element::infer("SE", "MSE"), Some(Element::Se));
+        assert_eq!(Element::infer("O", "HOH"), Some(Element::O));
+    }
+}
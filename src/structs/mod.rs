@@ -1,8 +1,18 @@
 pub mod pdb;
 pub mod atom;
+pub mod residue_id;
+pub mod secondary_structure;
+pub mod pocket;
+pub mod element;
+pub mod unit_cell;
 //pub mod chain;
 //pub mod residue;
 
 // re-export
 pub use pdb::PDB;
-pub use atom::Atom;
\ No newline at end of file
+pub use atom::Atom;
+pub use residue_id::ResidueId;
+pub use secondary_structure::{SecondaryStructure, Helix, HelixClass, Sheet, Strand, StrandSense, Registration};
+pub use pocket::PocketCenter;
+pub use element::Element;
+pub use unit_cell::UnitCell;
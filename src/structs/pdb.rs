@@ -1,15 +1,29 @@
+use std::collections::{HashMap, HashSet};
+
 #[cfg(feature = "rayon")]
 use rayon::prelude::*;
 
-use crate::structs::atom::Atom;
+use crate::structs::atom::{Atom, MAX_PEPTIDE_BOND};
+use crate::structs::{ResidueId, SecondaryStructure, Helix, Sheet, PocketCenter, UnitCell};
 use crate::validator;
 use crate::error::PDBError;
+use crate::geometry::Vector3;
+use crate::transform::{Quaternion, Transform};
+use crate::spatial::SpatialIndex;
+
+/// Residue sequence number, insertion code and residue name of an atom
+type ResidueKey = (usize, Option<String>, String);
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct PDB {
     identifier: Option<String>,
     remarks: Vec<(usize, String)>,
+    unit_cell: Option<UnitCell>,
     atoms: Vec<Atom>,
+    conects: Vec<(usize, usize)>,
+    secondary_structure: HashMap<ResidueId, SecondaryStructure>,
+    helices: Vec<Helix>,
+    sheets: Vec<Sheet>,
 }
 
 impl PDB {
@@ -17,7 +31,12 @@ impl PDB {
         PDB {
             identifier: None,
             remarks: Vec::<(usize, String)>::new(),
+            unit_cell: None,
             atoms: Vec::<Atom>::new(),
+            conects: Vec::<(usize, usize)>::new(),
+            secondary_structure: HashMap::new(),
+            helices: Vec::<Helix>::new(),
+            sheets: Vec::<Sheet>::new(),
         }
     }
 
@@ -55,18 +74,484 @@ impl PDB {
         Ok(())
     }
 
+    /// The unit cell or periodic box, `None` if it is not known.
+    pub fn unit_cell(&self) -> Option<&UnitCell> {
+        self.unit_cell.as_ref()
+    }
+
+    pub fn set_unit_cell(&mut self, unit_cell: Option<UnitCell>) {
+        self.unit_cell = unit_cell;
+    }
+
     pub fn atoms(&self) -> impl DoubleEndedIterator<Item = &Atom> + '_ {
         self.atoms.iter()
     }
 
+    pub fn atoms_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut Atom> + '_ {
+        self.atoms.iter_mut()
+    }
+
     #[cfg(feature = "rayon")]
-    pub fn par_atoms(&self) -> impl ParallelIterator<Item = Atom> + '_ {
+    pub fn par_atoms(&self) -> impl ParallelIterator<Item = &Atom> + '_ {
         self.atoms.par_iter()
     }
 
+    #[cfg(feature = "rayon")]
+    pub fn par_atoms_mut(&mut self) -> impl ParallelIterator<Item = &mut Atom> + '_ {
+        self.atoms.par_iter_mut()
+    }
+
+    pub fn atom(&self, index: usize) -> Option<&Atom> {
+        self.atoms.get(index)
+    }
+
+    pub fn atom_mut(&mut self, index: usize) -> Option<&mut Atom> {
+        self.atoms.get_mut(index)
+    }
+
+    pub fn atom_count(&self) -> usize {
+        self.atoms.len()
+    }
+
     pub fn add_atom(&mut self, new_atom: Atom) {
         self.atoms.push(new_atom);
     }
+
+    /// Groups consecutive atoms of the same residue.
+    /// Returns the residues in order with the indices of their atoms.
+    pub fn residue_indices(&self) -> Vec<(ResidueId, Vec<usize>)> {
+        let mut residues: Vec<(ResidueId, Vec<usize>)> = Vec::new();
+        for (index, atom) in self.atoms.iter().enumerate() {
+            let id = atom.residue_id();
+            match residues.last_mut() {
+                Some((last, indices)) if *last == id => indices.push(index),
+                _ => residues.push((id, vec![index])),
+            }
+        }
+        residues
+    }
+
+    /// Removes the atom at `index` together with the CONECT records referring to it.
+    /// If it was the last atom of its residue, the secondary structure of the residue and the
+    /// helices and strands starting or ending there are removed as well.
+    /// Returns `None` if the index is out of bounds.
+    pub fn remove_atom(&mut self, index: usize) -> Option<Atom> {
+        if index >= self.atoms.len() {
+            return None;
+        }
+        let atom = self.atoms.remove(index);
+        self.drop_dangling_conects();
+        self.drop_dangling_secondary_structure();
+        Some(atom)
+    }
+
+    /// Removes the first atom with the given serial number, see `remove_atom`.
+    pub fn remove_atom_by_serial(&mut self, serial_number: usize) -> Option<Atom> {
+        let index = self.atoms.iter().position(|a| *a.serial_number() == serial_number)?;
+        self.remove_atom(index)
+    }
+
+    /// Keeps only the atoms for which `predicate` returns `true`.
+    /// CONECT records referring to a removed atom are removed as well, and so is the secondary
+    /// structure of removed residues, see `remove_atom`.
+    pub fn retain<F>(&mut self, predicate: F)
+        where F: FnMut(&Atom) -> bool
+    {
+        self.atoms.retain(predicate);
+        self.drop_dangling_conects();
+        self.drop_dangling_secondary_structure();
+    }
+
+    /// Sorts the atoms by their serial number.
+    pub fn sort(&mut self) {
+        self.atoms.sort();
+    }
+
+    /// Renumbers the atom serial numbers from 1 in the current order.
+    /// CONECT records are updated to the new serial numbers.
+    pub fn renumber_atoms(&mut self) {
+        let mut mapping = HashMap::new();
+        for (index, atom) in self.atoms.iter_mut().enumerate() {
+            mapping.entry(*atom.serial_number()).or_insert(index + 1);
+            atom.set_serial_number(index + 1);
+        }
+        self.conects = self.conects
+            .iter()
+            .filter_map(|(a, b)| Some((*mapping.get(a)?, *mapping.get(b)?)))
+            .collect();
+        self.normalize_conects();
+    }
+
+    /// Renumbers the residues of every chain consecutively, starting at `offset` in each chain.
+    /// Residues are taken in the order their atoms appear. The secondary structure, helices
+    /// and sheets are updated to the new residue numbers.
+    pub fn renumber_residues(&mut self, offset: usize) {
+        let mut counters: HashMap<String, (usize, ResidueKey)> = HashMap::new();
+        let mut mapping = HashMap::new();
+        for atom in self.atoms.iter_mut() {
+            let old_id = atom.residue_id();
+            let key = (*atom.res_seq(), atom.i_code().map(str::to_owned), atom.res_name().to_owned());
+            let number = match counters.get_mut(atom.chain_id()) {
+                Some((number, last_key)) => {
+                    if *last_key != key {
+                        *number += 1;
+                        *last_key = key;
+                    }
+                    *number
+                },
+                None => {
+                    counters.insert(atom.chain_id().to_owned(), (offset, key));
+                    offset
+                },
+            };
+            atom.set_res_seq(number);
+            mapping.entry(old_id).or_insert_with(|| atom.residue_id());
+        }
+        self.remap_secondary_structure(&mapping);
+    }
+
+    /// Renames every atom of chain `old_id` to `new_id`.
+    /// The secondary structure, helices and sheets are updated to the new chain.
+    pub fn rename_chain(&mut self, old_id: &str, new_id: &str) -> Result<(), PDBError> {
+        let old_id = old_id.trim().to_ascii_uppercase();
+        let mut mapping = HashMap::new();
+        for atom in self.atoms.iter_mut().filter(|a| *a.chain_id() == old_id) {
+            let old_residue = atom.residue_id();
+            atom.set_chain_id(new_id)?;
+            mapping.entry(old_residue).or_insert_with(|| atom.residue_id());
+        }
+        self.remap_secondary_structure(&mapping);
+        Ok(())
+    }
+
+    /// Extracts the residues with any atom within `radius` Å of `center` as a new PDB.
+    /// Residues are kept whole, the center residues themselves are not part of the pocket.
+    /// With `caps` every peptide chain cut by the selection gets an ACE or NME cap built
+    /// from the atoms of the removed neighboring residue; a gap of a single residue is
+    /// filled with that residue instead. Cap atoms get new serial numbers after the highest
+    /// one of this PDB, and an ACE cap built from the same residue as the preceding NME cap
+    /// gets the insertion code "A". Fails if `center` selects no atoms.
+    pub fn pocket(&self, center: &PocketCenter, radius: f64, caps: bool) -> Result<PDB, PDBError> {
+        let (center_atoms, points): (Vec<usize>, Vec<Vector3>) = match center {
+            PocketCenter::ResidueName(name) => {
+                let name = name.trim().to_ascii_uppercase();
+                self.atoms.iter().enumerate().filter(|(_, a)| *a.res_name() == name).map(|(i, a)| (i, a.coords())).unzip()
+            },
+            PocketCenter::Residue(id) => {
+                self.atoms.iter().enumerate().filter(|(_, a)| a.residue_id() == *id).map(|(i, a)| (i, a.coords())).unzip()
+            },
+            PocketCenter::Point(point) => (Vec::new(), vec![*point]),
+        };
+        if points.is_empty() {
+            return Err(PDBError::InvalidValue(format!("no atoms found for the pocket center {:?}", center)));
+        }
+
+        let residues = self.residue_indices();
+        let mut residue_of = vec![0; self.atoms.len()];
+        for (r, (_, indices)) in residues.iter().enumerate() {
+            for i in indices {
+                residue_of[*i] = r;
+            }
+        }
+        let mut keep = vec![false; residues.len()];
+        let index = SpatialIndex::new(self);
+        for point in points.iter() {
+            for i in index.within(point, radius) {
+                keep[residue_of[i]] = true;
+            }
+        }
+        let mut is_center = vec![false; residues.len()];
+        for i in center_atoms {
+            is_center[residue_of[i]] = true;
+            keep[residue_of[i]] = false;
+        }
+
+        let find = |r: usize, name: &str| residues[r].1.iter().map(|i| &self.atoms[*i]).find(|a| a.atom_name() == name);
+        // whether residue r is bonded to the residue after it by a peptide bond
+        let peptide_bond = |r: usize| match (find(r, "C"), residues.get(r + 1).and_then(|_| find(r + 1, "N"))) {
+            (Some(c), Some(n)) => c.distance(n) <= MAX_PEPTIDE_BOND,
+            _ => false,
+        };
+        if caps {
+            for r in 1..residues.len().saturating_sub(1) {
+                if !keep[r] && !is_center[r] && keep[r - 1] && keep[r + 1] && peptide_bond(r - 1) && peptide_bond(r) {
+                    keep[r] = true;
+                }
+            }
+        }
+
+        let mut atoms = Vec::new();
+        let mut serial_number = self.atoms.iter().map(|a| *a.serial_number()).max().unwrap_or(0);
+        let mut cap = |source: &Atom, name: &str, res_name: &str, i_code: &str| {
+            serial_number += 1;
+            let mut atom = source.clone();
+            atom.set_serial_number(serial_number);
+            atom.set_name(name).expect("valid cap atom name");
+            atom.set_residue_name(res_name).expect("valid cap residue name");
+            atom.set_i_code(i_code).expect("valid cap insertion code");
+            atom
+        };
+        // the residue the last NME cap was built from
+        let mut last_nme = None;
+        for (r, (_, indices)) in residues.iter().enumerate().filter(|(r, _)| keep[*r]) {
+            if caps && r > 0 && !keep[r - 1] && peptide_bond(r - 1) {
+                let i_code = if last_nme == Some(r - 1) { "A" } else { "" };
+                for (source, name) in [("CA", "CH3"), ("C", "C"), ("O", "O")] {
+                    if let Some(atom) = find(r - 1, source) {
+                        atoms.push(cap(atom, name, "ACE", i_code));
+                    }
+                }
+            }
+            atoms.extend(indices.iter().map(|i| self.atoms[*i].clone()));
+            if caps && r + 1 < residues.len() && !keep[r + 1] && peptide_bond(r) {
+                for (source, name) in [("N", "N"), ("H", "H"), ("CA", "CH3")] {
+                    if let Some(atom) = find(r + 1, source) {
+                        atoms.push(cap(atom, name, "NME", ""));
+                    }
+                }
+                last_nme = Some(r + 1);
+            }
+        }
+
+        let mut pocket = PDB {
+            identifier: self.identifier.clone(),
+            remarks: self.remarks.clone(),
+            unit_cell: self.unit_cell.clone(),
+            atoms,
+            conects: self.conects.clone(),
+            secondary_structure: HashMap::new(),
+            helices: Vec::new(),
+            sheets: Vec::new(),
+        };
+        pocket.drop_dangling_conects();
+        for atom in pocket.atoms.iter() {
+            if let Some(secondary_structure) = self.secondary_structure.get(&atom.residue_id()) {
+                pocket.secondary_structure.insert(atom.residue_id(), *secondary_structure);
+            }
+        }
+        Ok(pocket)
+    }
+
+    /// The CONECT records of this PDB as pairs of bonded atom serial numbers.
+    pub fn conects(&self) -> impl DoubleEndedIterator<Item = &(usize, usize)> + '_ {
+        self.conects.iter()
+    }
+
+    /// Adds a CONECT record between two atom serial numbers.
+    pub fn add_conect(&mut self, serial_a: usize, serial_b: usize) -> Result<(), PDBError> {
+        self.add_conects(std::iter::once((serial_a, serial_b)))
+    }
+
+    /// Adds CONECT records between pairs of atom serial numbers, sorting the records only once.
+    /// Nothing is added if any atom is connected to itself.
+    pub fn add_conects<I>(&mut self, pairs: I) -> Result<(), PDBError>
+        where I: IntoIterator<Item = (usize, usize)>
+    {
+        let pairs: Vec<(usize, usize)> = pairs.into_iter().collect();
+        if let Some((serial, _)) = pairs.iter().find(|(a, b)| a == b) {
+            return Err(PDBError::InvalidValue(
+                format!("an atom can not be connected to itself: {}", serial)
+            ))
+        }
+        self.conects.extend(pairs);
+        self.normalize_conects();
+        Ok(())
+    }
+
+    /// The secondary structure assigned to a residue, `None` if nothing is assigned.
+    pub fn secondary_structure(&self, residue: &ResidueId) -> Option<SecondaryStructure> {
+        self.secondary_structure.get(residue).copied()
+    }
+
+    pub fn set_secondary_structure(&mut self, residue: ResidueId, secondary_structure: SecondaryStructure) {
+        self.secondary_structure.insert(residue, secondary_structure);
+    }
+
+    pub fn helices(&self) -> impl DoubleEndedIterator<Item = &Helix> + '_ {
+        self.helices.iter()
+    }
+
+    pub fn add_helix(&mut self, helix: Helix) {
+        self.helices.push(helix);
+    }
+
+    pub fn sheets(&self) -> impl DoubleEndedIterator<Item = &Sheet> + '_ {
+        self.sheets.iter()
+    }
+
+    pub fn add_sheet(&mut self, sheet: Sheet) {
+        self.sheets.push(sheet);
+    }
+
+    /// Removes the per-residue assignment and all helices and sheets.
+    pub fn clear_secondary_structure(&mut self) {
+        self.secondary_structure.clear();
+        self.helices.clear();
+        self.sheets.clear();
+    }
+
+    /// The geometric center of all atoms, `None` if there are no atoms.
+    pub fn centroid(&self) -> Option<Vector3> {
+        if self.atoms.is_empty() {
+            return None;
+        }
+        let sum = self.atoms.iter().fold(Vector3::zero(), |acc, a| acc + a.coords());
+        Some(sum / self.atoms.len() as f64)
+    }
+
+    /// The mass-weighted center of all atoms, `None` if there are no atoms
+    /// or none of them has a known mass.
+    pub fn center_of_mass(&self) -> Option<Vector3> {
+        let (sum, total) = self.atoms.iter().fold((Vector3::zero(), 0.0), |(sum, total), a| {
+            let mass = a.mass();
+            (sum + a.coords() * mass, total + mass)
+        });
+        if total > 0.0 {
+            Some(sum / total)
+        } else {
+            None
+        }
+    }
+
+    /// The axis-aligned bounding box of all atoms as (minimum, maximum) corners.
+    pub fn bounding_box(&self) -> Option<(Vector3, Vector3)> {
+        let first = self.atoms.first()?.coords();
+        Some(self.atoms.iter().fold((first, first), |(min, max), a| {
+            let p = a.coords();
+            (
+                Vector3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+                Vector3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+            )
+        }))
+    }
+
+    /// The mass-weighted radius of gyration (Å) around the center of mass.
+    pub fn radius_of_gyration(&self) -> Option<f64> {
+        let center = self.center_of_mass()?;
+        let (sum, total) = self.atoms.iter().fold((0.0, 0.0), |(sum, total), a| {
+            let mass = a.mass();
+            (sum + mass * a.coords().distance_squared(&center), total + mass)
+        });
+        Some((sum / total).sqrt())
+    }
+
+    /// Applies an affine transform to every atom position.
+    /// Nothing is changed if any of the new positions would not be finite.
+    pub fn apply_transform(&mut self, transform: &Transform) -> Result<(), PDBError> {
+        let positions: Vec<Vector3> = self.atoms.iter().map(|a| transform.apply(&a.coords())).collect();
+        if let Some(index) = positions.iter().position(|p| !p.is_finite()) {
+            return Err(PDBError::InvalidValue(format!(
+                "the transform moves atom {} to a non finite position: {}",
+                self.atoms[index].serial_number(), positions[index]
+            )))
+        }
+        for (atom, position) in self.atoms.iter_mut().zip(positions) {
+            atom.set_position(position.to_tuple())?;
+        }
+        Ok(())
+    }
+
+    /// Applies a 4x4 homogeneous matrix (row major) to every atom position.
+    pub fn apply_matrix(&mut self, matrix: &[[f64; 4]; 4]) -> Result<(), PDBError> {
+        self.apply_transform(&Transform::from_matrix4(matrix)?)
+    }
+
+    pub fn translate(&mut self, translation: Vector3) -> Result<(), PDBError> {
+        self.apply_transform(&Transform::from_translation(translation))
+    }
+
+    /// Rotates all atoms by `angle` degrees around `axis`.
+    /// The axis passes through `center`, or through the origin if `center` is `None`.
+    pub fn rotate_axis_angle(&mut self, axis: Vector3, angle: f64, center: Option<Vector3>) -> Result<(), PDBError> {
+        let rotation = Transform::from_axis_angle(&axis, angle)?;
+        self.apply_transform(&around(&rotation, center))
+    }
+
+    /// Rotates all atoms by a quaternion around `center`, or around the origin if `center` is `None`.
+    pub fn rotate_quaternion(&mut self, rotation: &Quaternion, center: Option<Vector3>) -> Result<(), PDBError> {
+        self.apply_transform(&around(&Transform::from_quaternion(rotation), center))
+    }
+
+    /// Moves the structure so that its centroid is at the origin.
+    /// Returns the applied translation.
+    pub fn center_at_origin(&mut self) -> Result<Vector3, PDBError> {
+        let translation = -self.centroid().unwrap_or_default();
+        self.translate(translation)?;
+        Ok(translation)
+    }
+
+    fn normalize_conects(&mut self) {
+        for pair in self.conects.iter_mut() {
+            *pair = (pair.0.min(pair.1), pair.0.max(pair.1));
+        }
+        self.conects.sort_unstable();
+        self.conects.dedup();
+    }
+
+    /// Moves the secondary structure of residues to their new ids, residues missing from
+    /// `mapping` keep their id.
+    fn remap_secondary_structure(&mut self, mapping: &HashMap<ResidueId, ResidueId>) {
+        let map = |id: &mut ResidueId| {
+            if let Some(new_id) = mapping.get(id) {
+                *id = new_id.clone();
+            }
+        };
+        self.secondary_structure = self.secondary_structure
+            .drain()
+            .map(|(mut id, secondary_structure)| {
+                map(&mut id);
+                (id, secondary_structure)
+            })
+            .collect();
+        for helix in self.helices.iter_mut() {
+            map(&mut helix.start);
+            map(&mut helix.end);
+        }
+        for strand in self.sheets.iter_mut().flat_map(|s| s.strands.iter_mut()) {
+            map(&mut strand.start);
+            map(&mut strand.end);
+            if let Some(registration) = strand.registration.as_mut() {
+                map(&mut registration.current);
+                map(&mut registration.previous);
+            }
+        }
+    }
+
+    /// Removes the secondary structure of residues without atoms and the helices and strands
+    /// starting or ending at such a residue.
+    fn drop_dangling_secondary_structure(&mut self) {
+        let residues: HashSet<ResidueId> = self.atoms.iter().map(|a| a.residue_id()).collect();
+        self.secondary_structure.retain(|id, _| residues.contains(id));
+        self.helices.retain(|h| residues.contains(&h.start) && residues.contains(&h.end));
+        for sheet in self.sheets.iter_mut() {
+            sheet.strands.retain(|s| residues.contains(&s.start) && residues.contains(&s.end));
+        }
+        self.sheets.retain(|s| !s.strands.is_empty());
+    }
+
+    fn drop_dangling_conects(&mut self) {
+        let serials: HashSet<usize> = self.atoms
+            .iter()
+            .map(|a| *a.serial_number())
+            .collect();
+        self.conects.retain(|(a, b)| serials.contains(a) && serials.contains(b));
+    }
+}
+
+/// `transform` applied with `center` as the origin
+fn around(transform: &Transform, center: Option<Vector3>) -> Transform {
+    match center {
+        Some(center) => Transform::from_translation(-center)
+            .then(transform)
+            .then(&Transform::from_translation(center)),
+        None => *transform,
+    }
+}
+
+impl Default for PDB {
+    fn default() -> Self {
+        PDB::new()
+    }
 }
 
 
@@ -75,3 +560,170 @@ const REMARK_TYPES: [usize; 42] = [
     300, 350, 375, 400, 450, 465, 470, 475, 480, 500, 525, 600, 610, 615, 620, 630, 650, 700, 800, 900,
     999,
 ];
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn atom(serial_number: usize, chain_id: &str, res_seq: usize) -> Atom {
+        Atom::new(false, serial_number, "CA", "ALA", chain_id, res_seq, 0.0, 0.0, 0.0, 1.0, 0.0, "C", 0).unwrap()
+    }
+
+    #[test]
+    fn renumber_atoms_updates_conects() {
+        let mut pdb = PDB::new();
+        pdb.add_atom(atom(10, "A", 5));
+        pdb.add_atom(atom(20, "A", 5));
+        pdb.add_atom(atom(30, "A", 6));
+        pdb.add_conect(10, 30).unwrap();
+        pdb.add_conect(20, 30).unwrap();
+
+        assert!(pdb.remove_atom_by_serial(20).is_some());
+        pdb.renumber_atoms();
+        assert_eq!(pdb.atoms().map(|a| *a.serial_number()).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(pdb.conects().collect::<Vec<_>>(), vec![&(1, 2)]);
+    }
+
+    #[test]
+    fn add_conects_sorts_and_dedups() {
+        let mut pdb = PDB::new();
+        pdb.add_conects(vec![(3, 1), (1, 2), (1, 3)]).unwrap();
+        assert_eq!(pdb.conects().collect::<Vec<_>>(), vec![&(1, 2), &(1, 3)]);
+        assert!(pdb.add_conects(vec![(4, 5), (6, 6)]).is_err());
+        assert_eq!(pdb.conects().count(), 2);
+    }
+
+    #[test]
+    fn renumber_residues_per_chain() {
+        let mut pdb = PDB::new();
+        pdb.add_atom(atom(1, "A", 5));
+        pdb.add_atom(atom(2, "A", 5));
+        pdb.add_atom(atom(3, "A", 9));
+        pdb.add_atom(atom(4, "B", 3));
+        pdb.renumber_residues(1);
+        assert_eq!(pdb.atoms().map(|a| *a.res_seq()).collect::<Vec<_>>(), vec![1, 1, 2, 1]);
+
+        pdb.rename_chain("B", "C").unwrap();
+        assert_eq!(pdb.atom(3).unwrap().chain_id(), "C");
+    }
+
+    #[test]
+    fn secondary_structure_follows_residues() {
+        use crate::structs::HelixClass;
+        let mut pdb = PDB::new();
+        pdb.add_atom(atom(1, "A", 5));
+        pdb.add_atom(atom(2, "A", 9));
+        pdb.add_atom(atom(3, "A", 12));
+        for res_seq in [5, 9] {
+            pdb.set_secondary_structure(ResidueId::new("A", res_seq, None), SecondaryStructure::AlphaHelix);
+        }
+        pdb.add_helix(Helix {
+            serial_number: 1,
+            id: "1".to_owned(),
+            start: ResidueId::new("A", 5, None),
+            start_res_name: "ALA".to_owned(),
+            end: ResidueId::new("A", 9, None),
+            end_res_name: "ALA".to_owned(),
+            class: HelixClass::RightHandedAlpha,
+            comment: String::new(),
+            length: 5,
+        });
+
+        pdb.renumber_residues(1);
+        pdb.rename_chain("A", "B").unwrap();
+        assert_eq!(pdb.secondary_structure(&ResidueId::new("B", 2, None)), Some(SecondaryStructure::AlphaHelix));
+        assert_eq!(pdb.secondary_structure(&ResidueId::new("A", 9, None)), None);
+        let helix = pdb.helices().next().unwrap();
+        assert_eq!((helix.start.clone(), helix.end.clone()), (ResidueId::new("B", 1, None), ResidueId::new("B", 2, None)));
+
+        pdb.remove_atom(1);
+        assert_eq!(pdb.secondary_structure(&ResidueId::new("B", 2, None)), None);
+        assert_eq!(pdb.secondary_structure(&ResidueId::new("B", 1, None)), Some(SecondaryStructure::AlphaHelix));
+        assert_eq!(pdb.helices().count(), 0);
+    }
+
+    #[test]
+    fn centroid_and_radius_of_gyration() {
+        let mut pdb = PDB::new();
+        assert!(pdb.centroid().is_none());
+        let mut a = atom(1, "A", 1);
+        a.set_position((-1.0, 0.0, 0.0)).unwrap();
+        let mut b = atom(2, "A", 1);
+        b.set_position((1.0, 2.0, 0.0)).unwrap();
+        pdb.add_atom(a);
+        pdb.add_atom(b);
+
+        assert_eq!(pdb.centroid().unwrap(), Vector3::new(0.0, 1.0, 0.0));
+        assert_eq!(pdb.center_of_mass().unwrap(), Vector3::new(0.0, 1.0, 0.0));
+        let (min, max) = pdb.bounding_box().unwrap();
+        assert_eq!((min, max), (Vector3::new(-1.0, 0.0, 0.0), Vector3::new(1.0, 2.0, 0.0)));
+        assert!((pdb.radius_of_gyration().unwrap() - 2.0_f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rotate_around_center() {
+        let mut pdb = PDB::new();
+        let mut a = atom(1, "A", 1);
+        a.set_position((2.0, 1.0, 0.0)).unwrap();
+        pdb.add_atom(a);
+        pdb.rotate_axis_angle(Vector3::new(0.0, 0.0, 1.0), 180.0, Some(Vector3::new(1.0, 1.0, 0.0))).unwrap();
+        assert!(pdb.atom(0).unwrap().coords().distance(&Vector3::new(0.0, 1.0, 0.0)) < 1e-12);
+
+        let translation = pdb.center_at_origin().unwrap();
+        assert!(translation.distance(&Vector3::new(0.0, -1.0, 0.0)) < 1e-12);
+        assert!(pdb.apply_matrix(&[[f64::NAN, 0.0, 0.0, 0.0], [0.0; 4], [0.0; 4], [0.0, 0.0, 0.0, 1.0]]).is_err());
+        assert!(pdb.atom(0).unwrap().coords().norm() < 1e-12);
+    }
+
+    #[test]
+    fn pocket_with_caps() {
+        let mut pdb = crate::testing::peptide(&[("ALA", -120.0, 130.0); 7]);
+        pdb.add_atom(Atom::new(true, 100, "ZN", "ZN", "A", 100, 0.0, 0.0, 0.0, 1.0, 0.0, "ZN", 0).unwrap());
+        let ca = pdb.atoms().find(|a| *a.res_seq() == 3 && a.atom_name() == "CA").unwrap().coords();
+        let zinc = PocketCenter::ResidueName("zn".to_owned());
+        assert!(pdb.pocket(&PocketCenter::ResidueName("HEM".to_owned()), 5.0, false).is_err());
+
+        let pocket = pdb.pocket(&PocketCenter::Point(ca), 0.5, false).unwrap();
+        assert_eq!(pocket.atom_count(), 5);
+        assert!(pocket.atoms().all(|a| *a.res_seq() == 3));
+
+        let capped = pdb.pocket(&PocketCenter::Point(ca), 0.5, true).unwrap();
+        let names: Vec<(&str, &str)> = capped.atoms().map(|a| (a.res_name().as_str(), a.atom_name().as_str())).collect();
+        assert_eq!(&names[..3], &[("ACE", "CH3"), ("ACE", "C"), ("ACE", "O")]);
+        assert_eq!(&names[8..], &[("NME", "N"), ("NME", "CH3")]);
+        let mut serials: Vec<usize> = capped.atoms().map(|a| *a.serial_number()).collect();
+        serials.sort_unstable();
+        serials.dedup();
+        assert_eq!(serials.len(), capped.atom_count());
+
+        let around_zinc = pdb.pocket(&zinc, 0.5, false).unwrap();
+        assert_eq!(around_zinc.atom_count(), 5);
+        assert!(around_zinc.atoms().all(|a| *a.res_seq() == 1));
+    }
+
+    #[test]
+    fn pocket_fills_single_residue_gaps() {
+        let pdb = crate::testing::peptide(&[
+            ("ALA", -120.0, 130.0), ("GLY", -120.0, 130.0), ("ALA", -120.0, 130.0), ("ALA", -120.0, 130.0),
+            ("ALA", -120.0, 130.0), ("GLY", -120.0, 130.0), ("ALA", -120.0, 130.0),
+        ]);
+        let glycines = PocketCenter::ResidueName("GLY".to_owned());
+        let residues = |pocket: &PDB| {
+            let mut residues: Vec<(usize, String)> = pocket.atoms().map(|a| (*a.res_seq(), a.res_name().to_owned())).collect();
+            residues.dedup();
+            residues
+        };
+        let pocket = pdb.pocket(&glycines, 1.4, false).unwrap();
+        assert_eq!(residues(&pocket).iter().map(|r| r.0).collect::<Vec<usize>>(), vec![1, 3, 5, 7]);
+
+        let capped = pdb.pocket(&glycines, 1.4, true).unwrap();
+        let expected = [(1, "ALA"), (2, "NME"), (2, "ACE"), (3, "ALA"), (4, "ALA"), (5, "ALA"), (6, "NME"), (6, "ACE"), (7, "ALA")];
+        assert_eq!(residues(&capped), expected.iter().map(|(s, n)| (*s, n.to_string())).collect::<Vec<_>>());
+        assert_eq!(capped.residue_indices().len(), expected.len());
+        let mut serials: Vec<usize> = capped.atoms().map(|a| *a.serial_number()).collect();
+        serials.sort_unstable();
+        serials.dedup();
+        assert_eq!(serials.len(), capped.atom_count());
+    }
+}
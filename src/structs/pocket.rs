@@ -0,0 +1,13 @@
+use crate::structs::ResidueId;
+use crate::geometry::Vector3;
+
+/// The center of a binding pocket
+#[derive(Debug, Clone, PartialEq)]
+pub enum PocketCenter {
+    /// All residues with this residue name, e.g. a ligand code
+    ResidueName(String),
+    /// A single residue
+    Residue(ResidueId),
+    /// A coordinate (Å)
+    Point(Vector3),
+}
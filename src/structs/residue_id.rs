@@ -0,0 +1,25 @@
+use std::fmt;
+
+/// Identifies a residue within a PDB by its chain, sequence number and insertion code
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ResidueId {
+    pub chain_id: String,
+    pub res_seq: usize,
+    pub i_code: Option<String>,
+}
+
+impl ResidueId {
+    pub fn new(chain_id: &str, res_seq: usize, i_code: Option<&str>) -> ResidueId {
+        ResidueId {
+            chain_id: chain_id.trim().to_ascii_uppercase(),
+            res_seq,
+            i_code: i_code.map(|c| c.trim().to_ascii_uppercase()).filter(|c| !c.is_empty()),
+        }
+    }
+}
+
+impl fmt::Display for ResidueId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}{}", self.chain_id, self.res_seq, self.i_code.as_deref().unwrap_or(""))
+    }
+}
@@ -0,0 +1,190 @@
+use std::fmt;
+
+use crate::structs::ResidueId;
+
+/// The secondary structure of a residue in the DSSP nomenclature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SecondaryStructure {
+    /// H: alpha helix (4-helix)
+    AlphaHelix,
+    /// G: 3-10 helix (3-helix)
+    ThreeTenHelix,
+    /// I: pi helix (5-helix)
+    PiHelix,
+    /// E: extended strand in a beta ladder
+    Strand,
+    /// B: residue in an isolated beta bridge
+    Bridge,
+    /// T: hydrogen bonded turn
+    Turn,
+    /// S: bend
+    Bend,
+    /// no assignment
+    Coil,
+}
+
+impl SecondaryStructure {
+    /// The one letter DSSP code, a space for coil.
+    pub fn code(&self) -> char {
+        match self {
+            SecondaryStructure::AlphaHelix => 'H',
+            SecondaryStructure::ThreeTenHelix => 'G',
+            SecondaryStructure::PiHelix => 'I',
+            SecondaryStructure::Strand => 'E',
+            SecondaryStructure::Bridge => 'B',
+            SecondaryStructure::Turn => 'T',
+            SecondaryStructure::Bend => 'S',
+            SecondaryStructure::Coil => ' ',
+        }
+    }
+
+    pub fn from_code(code: char) -> Option<SecondaryStructure> {
+        match code {
+            'H' => Some(SecondaryStructure::AlphaHelix),
+            'G' => Some(SecondaryStructure::ThreeTenHelix),
+            'I' => Some(SecondaryStructure::PiHelix),
+            'E' => Some(SecondaryStructure::Strand),
+            'B' => Some(SecondaryStructure::Bridge),
+            'T' => Some(SecondaryStructure::Turn),
+            'S' => Some(SecondaryStructure::Bend),
+            ' ' | '-' | 'C' => Some(SecondaryStructure::Coil),
+            _ => None,
+        }
+    }
+
+    pub fn is_helix(&self) -> bool {
+        matches!(self, SecondaryStructure::AlphaHelix | SecondaryStructure::ThreeTenHelix | SecondaryStructure::PiHelix)
+    }
+}
+
+impl fmt::Display for SecondaryStructure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+/// The helix classes of the HELIX record
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HelixClass {
+    RightHandedAlpha,
+    RightHandedOmega,
+    RightHandedPi,
+    RightHandedGamma,
+    RightHanded310,
+    LeftHandedAlpha,
+    LeftHandedOmega,
+    LeftHandedGamma,
+    Ribbon27,
+    Polyproline,
+}
+
+impl HelixClass {
+    /// The class number used in the HELIX record (1 - 10)
+    pub fn number(&self) -> usize {
+        match self {
+            HelixClass::RightHandedAlpha => 1,
+            HelixClass::RightHandedOmega => 2,
+            HelixClass::RightHandedPi => 3,
+            HelixClass::RightHandedGamma => 4,
+            HelixClass::RightHanded310 => 5,
+            HelixClass::LeftHandedAlpha => 6,
+            HelixClass::LeftHandedOmega => 7,
+            HelixClass::LeftHandedGamma => 8,
+            HelixClass::Ribbon27 => 9,
+            HelixClass::Polyproline => 10,
+        }
+    }
+
+    pub fn from_number(number: usize) -> Option<HelixClass> {
+        match number {
+            1 => Some(HelixClass::RightHandedAlpha),
+            2 => Some(HelixClass::RightHandedOmega),
+            3 => Some(HelixClass::RightHandedPi),
+            4 => Some(HelixClass::RightHandedGamma),
+            5 => Some(HelixClass::RightHanded310),
+            6 => Some(HelixClass::LeftHandedAlpha),
+            7 => Some(HelixClass::LeftHandedOmega),
+            8 => Some(HelixClass::LeftHandedGamma),
+            9 => Some(HelixClass::Ribbon27),
+            10 => Some(HelixClass::Polyproline),
+            _ => None,
+        }
+    }
+}
+
+/// A helix as described by a HELIX record
+#[derive(Debug, Clone, PartialEq)]
+pub struct Helix {
+    pub serial_number: usize,
+    /// The helix identifier (up to 3 characters)
+    pub id: String,
+    pub start: ResidueId,
+    pub start_res_name: String,
+    pub end: ResidueId,
+    pub end_res_name: String,
+    pub class: HelixClass,
+    pub comment: String,
+    /// The number of residues in the helix
+    pub length: usize,
+}
+
+/// The direction of a strand relative to the previous strand of its sheet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StrandSense {
+    /// The first strand of a sheet
+    First,
+    Parallel,
+    Antiparallel,
+}
+
+impl StrandSense {
+    /// The sense as written in the SHEET record (0, 1 or -1)
+    pub fn number(&self) -> isize {
+        match self {
+            StrandSense::First => 0,
+            StrandSense::Parallel => 1,
+            StrandSense::Antiparallel => -1,
+        }
+    }
+
+    pub fn from_number(number: isize) -> Option<StrandSense> {
+        match number {
+            0 => Some(StrandSense::First),
+            1 => Some(StrandSense::Parallel),
+            -1 => Some(StrandSense::Antiparallel),
+            _ => None,
+        }
+    }
+}
+
+/// The registration of a strand: a hydrogen bonded pair of atoms
+/// in the current strand and in the previous strand of the sheet
+#[derive(Debug, Clone, PartialEq)]
+pub struct Registration {
+    pub current_atom: String,
+    pub current_res_name: String,
+    pub current: ResidueId,
+    pub previous_atom: String,
+    pub previous_res_name: String,
+    pub previous: ResidueId,
+}
+
+/// A strand of a sheet as described by a SHEET record
+#[derive(Debug, Clone, PartialEq)]
+pub struct Strand {
+    pub start: ResidueId,
+    pub start_res_name: String,
+    pub end: ResidueId,
+    pub end_res_name: String,
+    pub sense: StrandSense,
+    /// The registration to the previous strand, `None` for the first strand
+    pub registration: Option<Registration>,
+}
+
+/// A beta sheet, the strands are in the order of the SHEET records
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sheet {
+    /// The sheet identifier (up to 3 characters)
+    pub id: String,
+    pub strands: Vec<Strand>,
+}
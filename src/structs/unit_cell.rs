@@ -0,0 +1,88 @@
+use crate::geometry::Vector3;
+
+/// The unit cell of a crystal or the periodic box of a simulation,
+/// as given by a CRYST1 record
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitCell {
+    /// The length of the a axis (Å)
+    pub a: f64,
+    /// The length of the b axis (Å)
+    pub b: f64,
+    /// The length of the c axis (Å)
+    pub c: f64,
+    /// The angle between b and c (degrees)
+    pub alpha: f64,
+    /// The angle between a and c (degrees)
+    pub beta: f64,
+    /// The angle between a and b (degrees)
+    pub gamma: f64,
+    /// The Hermann-Mauguin space group symbol, e.g. "P 21 21 21"
+    pub space_group: Option<String>,
+    /// The number of polymeric chains in the unit cell (Z)
+    pub z: Option<usize>,
+}
+
+impl UnitCell {
+    pub fn new(a: f64, b: f64, c: f64, alpha: f64, beta: f64, gamma: f64) -> UnitCell {
+        UnitCell { a, b, c, alpha, beta, gamma, space_group: None, z: None }
+    }
+
+    /// A rectangular box with the given edge lengths (Å)
+    pub fn rectangular(a: f64, b: f64, c: f64) -> UnitCell {
+        UnitCell::new(a, b, c, 90.0, 90.0, 90.0)
+    }
+
+    /// The unit cell spanned by three box vectors (Å)
+    pub fn from_vectors(vectors: &[Vector3; 3]) -> UnitCell {
+        let angle = |u: &Vector3, v: &Vector3| {
+            (u.dot(v) / (u.norm() * v.norm())).clamp(-1.0, 1.0).acos().to_degrees()
+        };
+        UnitCell::new(
+            vectors[0].norm(),
+            vectors[1].norm(),
+            vectors[2].norm(),
+            angle(&vectors[1], &vectors[2]),
+            angle(&vectors[0], &vectors[2]),
+            angle(&vectors[0], &vectors[1]),
+        )
+    }
+
+    /// The box vectors (Å) with a along x and b in the xy plane, the convention of GROMACS
+    pub fn vectors(&self) -> [Vector3; 3] {
+        let (alpha, beta, gamma) = (self.alpha.to_radians(), self.beta.to_radians(), self.gamma.to_radians());
+        let cx = self.c * beta.cos();
+        let cy = self.c * (alpha.cos() - beta.cos() * gamma.cos()) / gamma.sin();
+        [
+            Vector3::new(self.a, 0.0, 0.0),
+            Vector3::new(self.b * gamma.cos(), self.b * gamma.sin(), 0.0),
+            Vector3::new(cx, cy, (self.c * self.c - cx * cx - cy * cy).max(0.0).sqrt()),
+        ]
+    }
+
+    /// Whether all angles are right angles
+    pub fn is_rectangular(&self) -> bool {
+        [self.alpha, self.beta, self.gamma].iter().all(|angle| (angle - 90.0).abs() < 1e-3)
+    }
+
+    /// The volume of the unit cell (Å³)
+    pub fn volume(&self) -> f64 {
+        let [a, b, c] = self.vectors();
+        a.dot(&b.cross(&c)).abs()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_cell_vectors() {
+        let cell = UnitCell::new(50.0, 60.0, 70.0, 80.0, 95.0, 110.0);
+        let again = UnitCell::from_vectors(&cell.vectors());
+        for (x, y) in [(cell.a, again.a), (cell.b, again.b), (cell.c, again.c), (cell.alpha, again.alpha), (cell.beta, again.beta), (cell.gamma, again.gamma)] {
+            assert!((x - y).abs() < 1e-9, "{} != {}", x, y);
+        }
+        assert!((UnitCell::rectangular(10.0, 20.0, 30.0).volume() - 6000.0).abs() < 1e-9);
+    }
+}
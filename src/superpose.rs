@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+
+use crate::structs::{PDB, Atom};
+use crate::geometry::{self, Vector3};
+use crate::transform::{Quaternion, Transform};
+use crate::error::PDBError;
+
+/// How atoms of the mobile structure are matched to atoms of the reference
+#[derive(Debug, Clone, PartialEq)]
+pub enum AtomPairing {
+    /// Atoms with the same serial number
+    Serial,
+    /// Atoms with the same chain id, residue sequence number, insertion code and atom name
+    ResidueAtom,
+    /// Explicit (mobile serial number, reference serial number) pairs
+    Mapping(Vec<(usize, usize)>),
+}
+
+/// The result of a superposition of a mobile structure onto a reference
+#[derive(Debug, Clone, PartialEq)]
+pub struct Superposition {
+    /// The transform moving the mobile atoms onto the reference atoms
+    pub transform: Transform,
+    /// The RMSD (Å) of the paired atoms before superposition
+    pub rmsd_before: f64,
+    /// The RMSD (Å) of the paired atoms after superposition
+    pub rmsd_after: f64,
+    /// The number of paired atoms used
+    pub pairs: usize,
+}
+
+/// Superposes `mobile` onto `reference` with the quaternion (Kabsch) method.
+/// Only atoms for which `selection` returns `true` are paired by `pairing`.
+/// If `apply` is `true` the transform is applied to all atoms of `mobile`.
+pub fn superpose<F>(
+    mobile: &mut PDB,
+    reference: &PDB,
+    selection: F,
+    pairing: &AtomPairing,
+    apply: bool,
+) -> Result<Superposition, PDBError>
+    where F: Fn(&Atom) -> bool
+{
+    let pairs = pair_atoms(mobile, reference, selection, pairing);
+    let (mobile_coords, reference_coords): (Vec<Vector3>, Vec<Vector3>) = pairs
+        .iter()
+        .map(|(m, r)| (mobile.atom(*m).unwrap().coords(), reference.atom(*r).unwrap().coords()))
+        .unzip();
+
+    let transform = kabsch(&mobile_coords, &reference_coords).ok_or_else(|| PDBError::InvalidValue(
+        format!("at least 3 paired atoms are needed for a superposition, found {}", pairs.len())
+    ))?;
+    let moved: Vec<Vector3> = mobile_coords.iter().map(|p| transform.apply(p)).collect();
+    // kabsch only succeeds for at least 3 pairs, so both are defined
+    let result = Superposition {
+        transform,
+        rmsd_before: rmsd(&mobile_coords, &reference_coords).unwrap_or_default(),
+        rmsd_after: rmsd(&moved, &reference_coords).unwrap_or_default(),
+        pairs: pairs.len(),
+    };
+    if apply {
+        mobile.apply_transform(&transform)?;
+    }
+    Ok(result)
+}
+
+/// Pairs the selected atoms of two structures, returning (mobile index, reference index) pairs.
+pub fn pair_atoms<F>(mobile: &PDB, reference: &PDB, selection: F, pairing: &AtomPairing) -> Vec<(usize, usize)>
+    where F: Fn(&Atom) -> bool
+{
+    let selected = |pdb: &PDB| -> Vec<usize> {
+        pdb.atoms().enumerate().filter(|(_, a)| selection(a)).map(|(i, _)| i).collect()
+    };
+    let mobile_atoms = selected(mobile);
+    let reference_atoms = selected(reference);
+
+    match pairing {
+        AtomPairing::Serial => {
+            let index: HashMap<usize, usize> = reference_atoms
+                .iter()
+                .rev()
+                .map(|i| (*reference.atom(*i).unwrap().serial_number(), *i))
+                .collect();
+            mobile_atoms
+                .iter()
+                .filter_map(|i| Some((*i, *index.get(mobile.atom(*i).unwrap().serial_number())?)))
+                .collect()
+        },
+        AtomPairing::ResidueAtom => {
+            let key = |a: &Atom| (a.chain_id().to_owned(), *a.res_seq(), a.i_code().map(str::to_owned), a.atom_name().to_owned());
+            let index: HashMap<_, usize> = reference_atoms
+                .iter()
+                .rev()
+                .map(|i| (key(reference.atom(*i).unwrap()), *i))
+                .collect();
+            mobile_atoms
+                .iter()
+                .filter_map(|i| Some((*i, *index.get(&key(mobile.atom(*i).unwrap()))?)))
+                .collect()
+        },
+        AtomPairing::Mapping(mapping) => {
+            let by_serial = |pdb: &PDB, atoms: &[usize]| -> HashMap<usize, usize> {
+                atoms.iter().rev().map(|i| (*pdb.atom(*i).unwrap().serial_number(), *i)).collect()
+            };
+            let mobile_index = by_serial(mobile, &mobile_atoms);
+            let reference_index = by_serial(reference, &reference_atoms);
+            mapping
+                .iter()
+                .filter_map(|(m, r)| Some((*mobile_index.get(m)?, *reference_index.get(r)?)))
+                .collect()
+        },
+    }
+}
+
+/// The optimal rigid-body transform moving `mobile` onto `reference` (Horn's quaternion method).
+/// Returns `None` if the slices differ in length or contain less than 3 points.
+pub fn kabsch(mobile: &[Vector3], reference: &[Vector3]) -> Option<Transform> {
+    kabsch_weighted(mobile, reference, &vec![1.0; mobile.len()])
+}
+
+/// `kabsch` with a weight for each point pair.
+pub fn kabsch_weighted(mobile: &[Vector3], reference: &[Vector3], weights: &[f64]) -> Option<Transform> {
+    if mobile.len() != reference.len() || mobile.len() != weights.len() || mobile.len() < 3 {
+        return None;
+    }
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let center = |coords: &[Vector3]| {
+        coords.iter().zip(weights).fold(Vector3::zero(), |acc, (p, w)| acc + *p * *w) / total
+    };
+    let mobile_center = center(mobile);
+    let reference_center = center(reference);
+
+    // correlation matrix s[i][j] = sum w * m_i * r_j
+    let mut s = [[0.0; 3]; 3];
+    for ((m, r), w) in mobile.iter().zip(reference).zip(weights) {
+        let m = (*m - mobile_center).to_array();
+        let r = (*r - reference_center).to_array();
+        for i in 0..3 {
+            for j in 0..3 {
+                s[i][j] += w * m[i] * r[j];
+            }
+        }
+    }
+    let [[sxx, sxy, sxz], [syx, syy, syz], [szx, szy, szz]] = s;
+    let n = [
+        [sxx + syy + szz, syz - szy, szx - sxz, sxy - syx],
+        [syz - szy, sxx - syy - szz, sxy + syx, szx + sxz],
+        [szx - sxz, sxy + syx, -sxx + syy - szz, syz + szy],
+        [sxy - syx, szx + sxz, syz + szy, -sxx - syy + szz],
+    ];
+    let (_, vectors) = geometry::symmetric_eigen(&n);
+    let rotation = Quaternion::new(vectors[0][0], vectors[1][0], vectors[2][0], vectors[3][0])?;
+
+    Some(Transform::from_translation(-mobile_center)
+        .then(&Transform::from_quaternion(&rotation))
+        .then(&Transform::from_translation(reference_center)))
+}
+
+/// The root mean square deviation of two coordinate lists.
+/// Returns `None` if the lists differ in length or are empty.
+pub fn rmsd(a: &[Vector3], b: &[Vector3]) -> Option<f64> {
+    if a.len() != b.len() || a.is_empty() {
+        return None;
+    }
+    let sum: f64 = a.iter().zip(b).map(|(p, q)| p.distance_squared(q)).sum();
+    Some((sum / a.len() as f64).sqrt())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(positions: &[(f64, f64, f64)]) -> PDB {
+        let mut pdb = PDB::new();
+        for (i, p) in positions.iter().enumerate() {
+            pdb.add_atom(Atom::new(false, i + 1, "CA", "GLY", "A", i + 1, p.0, p.1, p.2, 1.0, 0.0, "C", 0).unwrap());
+        }
+        pdb
+    }
+
+    #[test]
+    fn superpose_recovers_rigid_motion() {
+        let reference = model(&[(0.0, 0.0, 0.0), (1.5, 0.0, 0.0), (1.5, 1.5, 0.0), (0.0, 1.5, 2.0), (-1.0, 0.5, 1.0)]);
+        let mut mobile = reference.clone();
+        mobile.rotate_axis_angle(Vector3::new(1.0, 2.0, 3.0), 73.0, None).unwrap();
+        mobile.translate(Vector3::new(4.0, -2.0, 7.0)).unwrap();
+
+        let result = superpose(&mut mobile, &reference, |_| true, &AtomPairing::ResidueAtom, true).unwrap();
+        assert_eq!(result.pairs, 5);
+        assert!(result.rmsd_before > 1.0);
+        assert!(result.rmsd_after < 1e-9);
+        for (m, r) in mobile.atoms().zip(reference.atoms()) {
+            assert!(m.distance(r) < 1e-9);
+        }
+    }
+
+    #[test]
+    fn superpose_needs_three_pairs() {
+        let reference = model(&[(0.0, 0.0, 0.0), (1.5, 0.0, 0.0), (1.5, 1.5, 0.0)]);
+        let mut mobile = reference.clone();
+        let mapping = AtomPairing::Mapping(vec![(1, 1), (2, 2)]);
+        assert!(superpose(&mut mobile, &reference, |_| true, &mapping, false).is_err());
+    }
+
+    #[test]
+    fn rmsd_needs_equal_lengths() {
+        let origin = Vector3::new(0.0, 0.0, 0.0);
+        assert_eq!(rmsd(&[origin], &[Vector3::new(0.0, 3.0, 4.0)]), Some(5.0));
+        assert_eq!(rmsd(&[origin], &[]), None);
+        assert_eq!(rmsd(&[], &[]), None);
+    }
+}
@@ -0,0 +1,55 @@
+//! Structures with ideal geometry shared by the unit tests
+
+use crate::structs::{PDB, Atom};
+use crate::structs::atom::is_amino_acid;
+use crate::geometry::{place_atom, Vector3};
+
+/// An atom of chain "A" at `position`, a hetero atom unless `res_name` is a standard amino acid
+pub(crate) fn atom(serial: usize, name: &str, res_name: &str, res_seq: usize, position: (f64, f64, f64), element: &str) -> Atom {
+    let (x, y, z) = position;
+    Atom::new(!is_amino_acid(res_name), serial, name, res_name, "A", res_seq, x, y, z, 1.0, 0.0, element, 0).unwrap()
+}
+
+/// Builds chain "A" of a peptide with ideal backbone geometry from
+/// (residue name, phi, psi) in degrees, with trans peptide bonds.
+/// Every residue but glycine gets a CB atom with L chirality.
+pub(crate) fn peptide(residues: &[(&str, f64, f64)]) -> PDB {
+    let mut pdb = PDB::new();
+    let mut n = Vector3::zero();
+    let mut ca = Vector3::new(1.458, 0.0, 0.0);
+    let mut c = place_atom(&Vector3::new(0.0, 1.0, 0.0), &n, &ca, 1.525, 111.2, -60.0);
+    let mut serial = 1;
+    for (i, (res_name, phi, psi)) in residues.iter().enumerate() {
+        if i > 0 {
+            let next_n = place_atom(&n, &ca, &c, 1.329, 116.2, residues[i - 1].2);
+            let next_ca = place_atom(&ca, &c, &next_n, 1.458, 121.7, 180.0);
+            c = place_atom(&c, &next_n, &next_ca, 1.525, 111.2, *phi);
+            n = next_n;
+            ca = next_ca;
+        }
+        let o = place_atom(&n, &ca, &c, 1.231, 120.5, psi + 180.0);
+        let mut atoms = vec![("N", n, "N"), ("CA", ca, "C"), ("C", c, "C"), ("O", o, "O")];
+        if *res_name != "GLY" {
+            atoms.push(("CB", place_atom(&c, &n, &ca, 1.53, 110.5, -122.5), "C"));
+        }
+        for (name, p, element) in atoms {
+            pdb.add_atom(Atom::new(false, serial, name, res_name, "A", i + 1, p.x, p.y, p.z, 1.0, 0.0, element, 0).unwrap());
+            serial += 1;
+        }
+    }
+    pdb
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peptide_has_l_chirality() {
+        let pdb = peptide(&[("ALA", -60.0, -45.0), ("ALA", -60.0, -45.0)]);
+        let find = |name: &str| pdb.atoms().find(|a| a.atom_name() == name).unwrap().coords();
+        let (n, ca, c, cb) = (find("N"), find("CA"), find("C"), find("CB"));
+        assert!((n - ca).dot(&(c - ca).cross(&(cb - ca))) > 0.0);
+    }
+}
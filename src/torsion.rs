@@ -0,0 +1,259 @@
+use std::fmt;
+
+use crate::structs::{PDB, Atom, ResidueId};
+use crate::structs::atom::MAX_PEPTIDE_BOND;
+
+/// Maximum deviation (degrees) of omega from 0 for a cis peptide
+const CIS_TOLERANCE: f64 = 30.0;
+
+/// The Ramachandran distributions residues are classified with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RamachandranClass {
+    General,
+    Glycine,
+    Proline,
+    /// A residue preceding a proline
+    PrePro,
+}
+
+/// The region of the Ramachandran plot a residue falls in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RamachandranRegion {
+    Favored,
+    Allowed,
+    Outlier,
+}
+
+impl fmt::Display for RamachandranRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RamachandranRegion::Favored => "favored",
+            RamachandranRegion::Allowed => "allowed",
+            RamachandranRegion::Outlier => "outlier",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The backbone and side-chain torsion angles of a residue (degrees)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResidueTorsions {
+    pub residue: ResidueId,
+    pub res_name: String,
+    /// C(i-1)-N-CA-C
+    pub phi: Option<f64>,
+    /// N-CA-C-N(i+1)
+    pub psi: Option<f64>,
+    /// CA(i-1)-C(i-1)-N-CA, the peptide bond preceding this residue
+    pub omega: Option<f64>,
+    /// chi1 to chi4, `None` where the residue has no such angle or atoms are missing
+    pub chi: [Option<f64>; 4],
+    /// Whether the peptide bond preceding this residue is cis
+    pub cis_peptide: bool,
+    pub ramachandran_class: RamachandranClass,
+    /// `None` if phi or psi is undefined
+    pub ramachandran: Option<RamachandranRegion>,
+}
+
+/// Computes the torsion angles of every residue that has N, CA and C atoms.
+/// Residues are consecutive if they are in the same chain and their C-N distance is a peptide bond.
+pub fn torsions(pdb: &PDB) -> Vec<ResidueTorsions> {
+    let residues: Vec<(ResidueId, Vec<&Atom>)> = pdb
+        .residue_indices()
+        .into_iter()
+        .map(|(id, indices)| (id, indices.into_iter().map(|i| pdb.atom(i).unwrap()).collect::<Vec<&Atom>>()))
+        .filter(|(_, atoms)| ["N", "CA", "C"].iter().all(|name| find(atoms, name).is_some()))
+        .collect();
+    let bonded = |a: usize, b: usize| -> bool {
+        residues[a].0.chain_id == residues[b].0.chain_id
+            && find(&residues[a].1, "C").unwrap().distance(find(&residues[b].1, "N").unwrap()) <= MAX_PEPTIDE_BOND
+    };
+
+    let mut result = Vec::new();
+    for (i, (id, atoms)) in residues.iter().enumerate() {
+        let res_name = atoms[0].res_name().to_owned();
+        let (n, ca, c) = (find(atoms, "N").unwrap(), find(atoms, "CA").unwrap(), find(atoms, "C").unwrap());
+        let previous = if i > 0 && bonded(i - 1, i) { Some(&residues[i - 1].1) } else { None };
+        let next = if i + 1 < residues.len() && bonded(i, i + 1) { Some(&residues[i + 1].1) } else { None };
+
+        let phi = previous.map(|p| Atom::dihedral(find(p, "C").unwrap(), n, ca, c));
+        let psi = next.map(|p| Atom::dihedral(n, ca, c, find(p, "N").unwrap()));
+        let omega = previous.map(|p| Atom::dihedral(find(p, "CA").unwrap(), find(p, "C").unwrap(), n, ca));
+        let mut chi = [None; 4];
+        for (k, names) in chi_atoms(&res_name).iter().enumerate() {
+            let atoms: Option<Vec<&Atom>> = names.iter().map(|name| find(atoms, name)).collect();
+            chi[k] = atoms.map(|a| Atom::dihedral(a[0], a[1], a[2], a[3]));
+        }
+
+        let ramachandran_class = match (res_name.as_str(), next) {
+            ("GLY", _) => RamachandranClass::Glycine,
+            ("PRO", _) => RamachandranClass::Proline,
+            (_, Some(next)) if next[0].res_name() == "PRO" => RamachandranClass::PrePro,
+            _ => RamachandranClass::General,
+        };
+        result.push(ResidueTorsions {
+            residue: id.clone(),
+            res_name,
+            phi,
+            psi,
+            omega,
+            chi,
+            cis_peptide: omega.map(|w| w.abs() < CIS_TOLERANCE).unwrap_or(false),
+            ramachandran_class,
+            ramachandran: match (phi, psi) {
+                (Some(phi), Some(psi)) => Some(ramachandran_region(ramachandran_class, phi, psi)),
+                _ => None,
+            },
+        });
+    }
+    result
+}
+
+/// Classifies a (phi, psi) pair in degrees.
+/// The regions are polygons traced from the contours of Lovell et al. (2003),
+/// so borderline residues can differ from MolProbity.
+pub fn ramachandran_region(class: RamachandranClass, phi: f64, psi: f64) -> RamachandranRegion {
+    let (favored, allowed) = match class {
+        RamachandranClass::General => (GENERAL_FAVORED, GENERAL_ALLOWED),
+        RamachandranClass::Glycine => (GLYCINE_FAVORED, GLYCINE_ALLOWED),
+        RamachandranClass::Proline => (PROLINE_FAVORED, PROLINE_ALLOWED),
+        RamachandranClass::PrePro => (PRE_PRO_FAVORED, GENERAL_ALLOWED),
+    };
+    let inside = |regions: &[Region]| {
+        let mirrored = class == RamachandranClass::Glycine;
+        regions.iter().any(|region| contains(region, phi, psi) || (mirrored && contains(region, -phi, -psi)))
+    };
+    if inside(favored) {
+        RamachandranRegion::Favored
+    } else if inside(allowed) {
+        RamachandranRegion::Allowed
+    } else {
+        RamachandranRegion::Outlier
+    }
+}
+
+/// Whether (phi, psi) lies inside the polygon, by counting the crossed edges
+fn contains(region: Region, phi: f64, psi: f64) -> bool {
+    let mut inside = false;
+    let mut j = region.len() - 1;
+    for i in 0..region.len() {
+        let (phi_i, psi_i) = region[i];
+        let (phi_j, psi_j) = region[j];
+        if (psi_i > psi) != (psi_j > psi) && phi < phi_i + (psi - psi_i) * (phi_j - phi_i) / (psi_j - psi_i) {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+/// A polygon of (phi, psi) vertices in degrees
+type Region = &'static [(f64, f64)];
+
+const GENERAL_FAVORED: &[Region] = &[
+    // beta and polyproline
+    &[(-180.0, 180.0), (-45.0, 180.0), (-45.0, 150.0), (-55.0, 125.0), (-70.0, 110.0), (-100.0, 100.0), (-140.0, 100.0), (-180.0, 105.0)],
+    &[(-180.0, -180.0), (-45.0, -180.0), (-60.0, -170.0), (-180.0, -165.0)],
+    // right-handed alpha
+    &[(-45.0, -75.0), (-38.0, -50.0), (-38.0, -25.0), (-50.0, -10.0), (-65.0, 5.0), (-90.0, 20.0), (-105.0, 10.0), (-115.0, -15.0), (-130.0, -40.0), (-120.0, -60.0), (-90.0, -75.0)],
+    // left-handed alpha
+    &[(45.0, 15.0), (60.0, 5.0), (72.0, 20.0), (78.0, 45.0), (70.0, 70.0), (55.0, 68.0), (45.0, 45.0)],
+];
+const GENERAL_ALLOWED: &[Region] = &[
+    // beta, bridge and alpha
+    &[(-180.0, 180.0), (-38.0, 180.0), (-38.0, 120.0), (-50.0, 80.0), (-45.0, 40.0), (-30.0, 0.0), (-28.0, -45.0), (-38.0, -80.0), (-70.0, -100.0), (-110.0, -95.0), (-150.0, -75.0), (-180.0, -50.0)],
+    &[(-180.0, -180.0), (-38.0, -180.0), (-50.0, -160.0), (-90.0, -150.0), (-180.0, -140.0)],
+    // left-handed alpha
+    &[(30.0, -5.0), (45.0, -30.0), (70.0, -30.0), (95.0, 0.0), (105.0, 40.0), (100.0, 85.0), (75.0, 100.0), (50.0, 90.0), (35.0, 50.0)],
+    // epsilon
+    &[(50.0, 140.0), (100.0, 140.0), (100.0, 180.0), (50.0, 180.0)],
+    &[(50.0, -180.0), (100.0, -180.0), (100.0, -150.0), (60.0, -150.0)],
+];
+/// Glycine regions are also checked mirrored through the origin
+const GLYCINE_FAVORED: &[Region] = &[
+    &[(-180.0, 180.0), (-50.0, 180.0), (-50.0, 120.0), (-180.0, 120.0)],
+    &[(-180.0, -180.0), (-50.0, -180.0), (-50.0, -150.0), (-180.0, -150.0)],
+    &[(-45.0, -70.0), (-40.0, -40.0), (-50.0, 0.0), (-75.0, 15.0), (-100.0, 5.0), (-110.0, -30.0), (-95.0, -65.0)],
+];
+const GLYCINE_ALLOWED: &[Region] = &[
+    &[(-180.0, 180.0), (-50.0, 180.0), (-50.0, 130.0), (-60.0, 80.0), (-45.0, 30.0), (-30.0, -20.0), (-35.0, -60.0), (-60.0, -90.0), (-110.0, -100.0), (-180.0, -100.0)],
+    &[(-180.0, -180.0), (-50.0, -180.0), (-60.0, -150.0), (-180.0, -130.0)],
+];
+const PROLINE_FAVORED: &[Region] = &[
+    &[(-95.0, -60.0), (-45.0, -60.0), (-45.0, -10.0), (-95.0, -10.0)],
+    &[(-95.0, 100.0), (-45.0, 100.0), (-45.0, 180.0), (-95.0, 180.0)],
+];
+const PROLINE_ALLOWED: &[Region] = &[
+    &[(-110.0, -90.0), (-40.0, -90.0), (-35.0, -40.0), (-35.0, 40.0), (-50.0, 60.0), (-35.0, 100.0), (-35.0, 180.0), (-110.0, 180.0)],
+    &[(-110.0, -180.0), (-35.0, -180.0), (-35.0, -160.0), (-110.0, -160.0)],
+];
+const PRE_PRO_FAVORED: &[Region] = &[
+    &[(-180.0, 180.0), (-45.0, 180.0), (-45.0, 140.0), (-60.0, 120.0), (-100.0, 110.0), (-180.0, 110.0)],
+    &[(-110.0, -60.0), (-45.0, -60.0), (-45.0, -15.0), (-110.0, -15.0)],
+];
+
+/// The atom names defining chi1 to chi4 of a standard amino acid.
+pub fn chi_atoms(res_name: &str) -> &'static [[&'static str; 4]] {
+    match res_name {
+        "ARG" => &[["N", "CA", "CB", "CG"], ["CA", "CB", "CG", "CD"], ["CB", "CG", "CD", "NE"], ["CG", "CD", "NE", "CZ"]],
+        "ASN" => &[["N", "CA", "CB", "CG"], ["CA", "CB", "CG", "OD1"]],
+        "ASP" => &[["N", "CA", "CB", "CG"], ["CA", "CB", "CG", "OD1"]],
+        "CYS" => &[["N", "CA", "CB", "SG"]],
+        "GLN" => &[["N", "CA", "CB", "CG"], ["CA", "CB", "CG", "CD"], ["CB", "CG", "CD", "OE1"]],
+        "GLU" => &[["N", "CA", "CB", "CG"], ["CA", "CB", "CG", "CD"], ["CB", "CG", "CD", "OE1"]],
+        "HIS" => &[["N", "CA", "CB", "CG"], ["CA", "CB", "CG", "ND1"]],
+        "ILE" => &[["N", "CA", "CB", "CG1"], ["CA", "CB", "CG1", "CD1"]],
+        "LEU" => &[["N", "CA", "CB", "CG"], ["CA", "CB", "CG", "CD1"]],
+        "LYS" => &[["N", "CA", "CB", "CG"], ["CA", "CB", "CG", "CD"], ["CB", "CG", "CD", "CE"], ["CG", "CD", "CE", "NZ"]],
+        "MET" => &[["N", "CA", "CB", "CG"], ["CA", "CB", "CG", "SD"], ["CB", "CG", "SD", "CE"]],
+        "PHE" => &[["N", "CA", "CB", "CG"], ["CA", "CB", "CG", "CD1"]],
+        "PRO" => &[["N", "CA", "CB", "CG"], ["CA", "CB", "CG", "CD"]],
+        "SER" => &[["N", "CA", "CB", "OG"]],
+        "THR" => &[["N", "CA", "CB", "OG1"]],
+        "TRP" => &[["N", "CA", "CB", "CG"], ["CA", "CB", "CG", "CD1"]],
+        "TYR" => &[["N", "CA", "CB", "CG"], ["CA", "CB", "CG", "CD1"]],
+        "VAL" => &[["N", "CA", "CB", "CG1"]],
+        _ => &[],
+    }
+}
+
+fn find<'a>(atoms: &[&'a Atom], name: &str) -> Option<&'a Atom> {
+    atoms.iter().copied().find(|a| a.atom_name() == name)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::peptide;
+
+    #[test]
+    fn backbone_torsions_of_ideal_peptide() {
+        let pdb = peptide(&[("ALA", -57.0, -47.0), ("GLY", -120.0, 130.0), ("ALA", 60.0, -120.0), ("PRO", -65.0, 140.0)]);
+        let result = torsions(&pdb);
+        assert_eq!(result.len(), 4);
+        assert!(result[0].phi.is_none());
+        assert!((result[0].psi.unwrap() + 47.0).abs() < 1e-6);
+        assert!((result[1].phi.unwrap() + 120.0).abs() < 1e-6);
+        assert!((result[1].omega.unwrap().abs() - 180.0).abs() < 1e-6);
+        assert!(!result[1].cis_peptide);
+        assert_eq!(result[1].ramachandran, Some(RamachandranRegion::Favored));
+        assert_eq!(result[2].ramachandran_class, RamachandranClass::PrePro);
+        assert_eq!(result[2].ramachandran, Some(RamachandranRegion::Outlier));
+        assert_eq!(result[3].ramachandran_class, RamachandranClass::Proline);
+        assert!(result[3].psi.is_none());
+        assert!(result[0].chi[0].is_none());
+    }
+
+    #[test]
+    fn regions() {
+        assert_eq!(ramachandran_region(RamachandranClass::General, -63.0, -41.0), RamachandranRegion::Favored);
+        assert_eq!(ramachandran_region(RamachandranClass::General, -90.0, 60.0), RamachandranRegion::Allowed);
+        assert_eq!(ramachandran_region(RamachandranClass::General, 60.0, -120.0), RamachandranRegion::Outlier);
+        assert_eq!(ramachandran_region(RamachandranClass::Glycine, 80.0, 0.0), RamachandranRegion::Favored);
+        assert_eq!(ramachandran_region(RamachandranClass::General, -120.0, -120.0), RamachandranRegion::Outlier);
+        assert_eq!(ramachandran_region(RamachandranClass::General, -60.0, -130.0), RamachandranRegion::Outlier);
+        assert_eq!(ramachandran_region(RamachandranClass::Glycine, -90.0, -130.0), RamachandranRegion::Outlier);
+        assert_eq!(ramachandran_region(RamachandranClass::Proline, -65.0, 140.0), RamachandranRegion::Favored);
+    }
+}
@@ -0,0 +1,174 @@
+use std::ops::Mul;
+
+use crate::geometry::Vector3;
+use crate::error::PDBError;
+
+/// A unit quaternion describing a rotation, `w` is the scalar part
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    /// Creates a quaternion and normalizes it to unit length.
+    /// Returns `None` for a zero or non-finite quaternion.
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Option<Quaternion> {
+        let norm = (w * w + x * x + y * y + z * z).sqrt();
+        if norm > 0.0 && norm.is_finite() {
+            Some(Quaternion { w: w / norm, x: x / norm, y: y / norm, z: z / norm })
+        } else {
+            None
+        }
+    }
+
+    pub fn identity() -> Quaternion {
+        Quaternion { w: 1.0, x: 0.0, y: 0.0, z: 0.0 }
+    }
+
+    /// The rotation by `angle` degrees around `axis` (right-hand rule).
+    pub fn from_axis_angle(axis: &Vector3, angle: f64) -> Option<Quaternion> {
+        let axis = axis.normalize()?;
+        let half = angle.to_radians() / 2.0;
+        let s = half.sin();
+        Quaternion::new(half.cos(), axis.x * s, axis.y * s, axis.z * s)
+    }
+
+    pub fn conjugate(&self) -> Quaternion {
+        Quaternion { w: self.w, x: -self.x, y: -self.y, z: -self.z }
+    }
+
+    /// The equivalent 3x3 rotation matrix (row major).
+    pub fn to_rotation_matrix(&self) -> [[f64; 3]; 3] {
+        let Quaternion { w, x, y, z } = *self;
+        [
+            [w * w + x * x - y * y - z * z, 2.0 * (x * y - w * z), 2.0 * (x * z + w * y)],
+            [2.0 * (x * y + w * z), w * w - x * x + y * y - z * z, 2.0 * (y * z - w * x)],
+            [2.0 * (x * z - w * y), 2.0 * (y * z + w * x), w * w - x * x - y * y + z * z],
+        ]
+    }
+
+    pub fn rotate(&self, v: &Vector3) -> Vector3 {
+        Transform::from_quaternion(self).apply(v)
+    }
+}
+
+/// An affine transformation `x' = R x + t` of atom positions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    /// The linear part (row major), a rotation matrix for rigid-body transforms
+    pub rotation: [[f64; 3]; 3],
+    /// The translation applied after `rotation`
+    pub translation: Vector3,
+}
+
+impl Transform {
+    pub fn identity() -> Transform {
+        Transform {
+            rotation: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            translation: Vector3::zero(),
+        }
+    }
+
+    pub fn from_translation(translation: Vector3) -> Transform {
+        Transform { translation, ..Transform::identity() }
+    }
+
+    pub fn from_quaternion(rotation: &Quaternion) -> Transform {
+        Transform { rotation: rotation.to_rotation_matrix(), ..Transform::identity() }
+    }
+
+    /// The rotation by `angle` degrees around `axis` passing through the origin.
+    pub fn from_axis_angle(axis: &Vector3, angle: f64) -> Result<Transform, PDBError> {
+        let rotation = Quaternion::from_axis_angle(axis, angle).ok_or_else(|| PDBError::InvalidValue(
+            format!("the rotation axis must be finite and non-zero: {}", axis)
+        ))?;
+        Ok(Transform::from_quaternion(&rotation))
+    }
+
+    /// Creates a transform from a 4x4 homogeneous matrix (row major).
+    /// The last row has to be (0, 0, 0, 1).
+    pub fn from_matrix4(matrix: &[[f64; 4]; 4]) -> Result<Transform, PDBError> {
+        if matrix[3] != [0.0, 0.0, 0.0, 1.0] {
+            return Err(PDBError::InvalidValue(
+                format!("the last row of an affine matrix has to be [0, 0, 0, 1]: {:?}", matrix[3])
+            ))
+        }
+        let mut rotation = [[0.0; 3]; 3];
+        for (row, values) in rotation.iter_mut().zip(matrix.iter()) {
+            row.copy_from_slice(&values[..3]);
+        }
+        Ok(Transform {
+            rotation,
+            translation: Vector3::new(matrix[0][3], matrix[1][3], matrix[2][3]),
+        })
+    }
+
+    pub fn to_matrix4(&self) -> [[f64; 4]; 4] {
+        let t = self.translation.to_array();
+        let mut matrix = [[0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 1.0]];
+        for i in 0..3 {
+            matrix[i][..3].copy_from_slice(&self.rotation[i]);
+            matrix[i][3] = t[i];
+        }
+        matrix
+    }
+
+    pub fn apply(&self, v: &Vector3) -> Vector3 {
+        let r = &self.rotation;
+        Vector3::new(
+            r[0][0] * v.x + r[0][1] * v.y + r[0][2] * v.z,
+            r[1][0] * v.x + r[1][1] * v.y + r[1][2] * v.z,
+            r[2][0] * v.x + r[2][1] * v.y + r[2][2] * v.z,
+        ) + self.translation
+    }
+
+    /// The transform applying `self` first and `other` afterwards.
+    pub fn then(&self, other: &Transform) -> Transform {
+        *other * *self
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+impl Mul for Transform {
+    type Output = Transform;
+    /// Composition, `(a * b).apply(x) == a.apply(b.apply(x))`
+    fn mul(self, other: Transform) -> Transform {
+        let mut rotation = [[0.0; 3]; 3];
+        for (i, row) in rotation.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..3).map(|k| self.rotation[i][k] * other.rotation[k][j]).sum();
+            }
+        }
+        let translation = self.apply(&other.translation);
+        Transform { rotation, translation }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn axis_angle_rotation() {
+        let t = Transform::from_axis_angle(&Vector3::new(0.0, 0.0, 2.0), 90.0).unwrap();
+        let v = t.apply(&Vector3::new(1.0, 0.0, 0.0));
+        assert!(v.distance(&Vector3::new(0.0, 1.0, 0.0)) < 1e-12);
+        assert!(Transform::from_axis_angle(&Vector3::zero(), 90.0).is_err());
+    }
+
+    #[test]
+    fn matrix4_round_trip() {
+        let t = Transform::from_axis_angle(&Vector3::new(1.0, 1.0, 0.0), 30.0).unwrap()
+            .then(&Transform::from_translation(Vector3::new(1.0, 2.0, 3.0)));
+        assert_eq!(Transform::from_matrix4(&t.to_matrix4()).unwrap(), t);
+    }
+}
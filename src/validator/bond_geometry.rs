@@ -0,0 +1,263 @@
+use crate::structs::{PDB, Atom, ResidueId};
+use crate::structs::atom::is_amino_acid;
+use super::peptide_bond;
+
+/// An ideal bond length (Å) between two atoms with its standard deviation.
+/// Atom names starting with '+' belong to the next residue.
+type IdealBond = (&'static str, &'static str, f64, f64);
+/// An ideal bond angle (degrees) between three atoms with its standard deviation
+type IdealAngle = (&'static str, &'static str, &'static str, f64, f64);
+
+/// Whether a geometry term is a bond length or a bond angle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GeometryKind {
+    BondLength,
+    BondAngle,
+}
+
+/// A bond length or angle deviating from its ideal value
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeometryOutlier {
+    pub kind: GeometryKind,
+    /// The atom names, names starting with '+' belong to the next residue
+    pub atoms: Vec<String>,
+    /// The observed length (Å) or angle (degrees)
+    pub value: f64,
+    pub ideal: f64,
+    pub sigma: f64,
+    /// (value - ideal) / sigma
+    pub z_score: f64,
+}
+
+/// The covalent geometry of a standard residue compared to the ideal values
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResidueGeometry {
+    pub residue: ResidueId,
+    pub res_name: String,
+    /// The number of bond lengths checked
+    pub bonds: usize,
+    /// The number of bond angles checked
+    pub angles: usize,
+    /// The root mean square Z-score of the bond lengths, `None` if none was checked
+    pub bond_rmsz: Option<f64>,
+    /// The root mean square Z-score of the bond angles, `None` if none was checked
+    pub angle_rmsz: Option<f64>,
+    pub outliers: Vec<GeometryOutlier>,
+}
+
+/// Compares the bond lengths and angles of the standard amino acids to the ideal
+/// values of Engh & Huber (1991). Terms with an absolute Z-score above `z_cutoff`
+/// are reported as outliers, MolProbity uses 4. The peptide bond and the angles
+/// around it are checked with the residue before it. Terms with missing atoms are skipped.
+pub fn validate_geometry(pdb: &PDB, z_cutoff: f64) -> Vec<ResidueGeometry> {
+    let residues = pdb.residue_indices();
+    let atoms: Vec<&Atom> = pdb.atoms().collect();
+    let find = |r: usize, name: &str| residues[r].1.iter().map(|i| atoms[*i]).find(|a| a.atom_name() == name);
+
+    let mut result = Vec::new();
+    for (r, (residue, _)) in residues.iter().enumerate() {
+        let res_name = atoms[residues[r].1[0]].res_name().as_str();
+        if !is_amino_acid(res_name) {
+            continue;
+        }
+        let next_linked = match (find(r, "C"), residues.get(r + 1).and_then(|_| find(r + 1, "N"))) {
+            (Some(c), Some(n)) => peptide_bond(c, n),
+            _ => false,
+        };
+        let lookup = |name: &str| match name.strip_prefix('+') {
+            Some(name) if next_linked => find(r + 1, name),
+            Some(_) => None,
+            None => find(r, name),
+        };
+
+        let mut outliers = Vec::new();
+        let mut check = |kind, names: &[&str], value: f64, ideal: f64, sigma: f64| {
+            let z_score = (value - ideal) / sigma;
+            if z_score.abs() > z_cutoff {
+                outliers.push(GeometryOutlier {
+                    kind,
+                    atoms: names.iter().map(|n| n.to_string()).collect(),
+                    value,
+                    ideal,
+                    sigma,
+                    z_score,
+                });
+            }
+            z_score * z_score
+        };
+
+        let mut bond_z = Vec::new();
+        for (a, b, ideal, sigma) in ideal_bonds(res_name) {
+            if let (Some(x), Some(y)) = (lookup(a), lookup(b)) {
+                bond_z.push(check(GeometryKind::BondLength, &[a, b], x.distance(y), ideal, sigma));
+            }
+        }
+        let mut angle_z = Vec::new();
+        for (a, b, c, ideal, sigma) in ideal_angles(res_name) {
+            if let (Some(x), Some(y), Some(z)) = (lookup(a), lookup(b), lookup(c)) {
+                angle_z.push(check(GeometryKind::BondAngle, &[a, b, c], Atom::angle(x, y, z), ideal, sigma));
+            }
+        }
+        let rmsz = |squares: &[f64]| (!squares.is_empty()).then(|| (squares.iter().sum::<f64>() / squares.len() as f64).sqrt());
+
+        result.push(ResidueGeometry {
+            residue: residue.clone(),
+            res_name: res_name.to_owned(),
+            bonds: bond_z.len(),
+            angles: angle_z.len(),
+            bond_rmsz: rmsz(&bond_z),
+            angle_rmsz: rmsz(&angle_z),
+            outliers,
+        });
+    }
+    result
+}
+
+/// The ideal bond lengths of a standard amino acid (Engh & Huber 1991)
+fn ideal_bonds(res_name: &str) -> Vec<IdealBond> {
+    let mut bonds: Vec<IdealBond> = match res_name {
+        "GLY" => vec![("N", "CA", 1.456, 0.015), ("CA", "C", 1.514, 0.016), ("C", "O", 1.232, 0.016), ("C", "+N", 1.326, 0.018)],
+        "PRO" => vec![("N", "CA", 1.466, 0.015), ("CA", "C", 1.525, 0.021), ("C", "O", 1.231, 0.020), ("C", "+N", 1.329, 0.014)],
+        _ => vec![("N", "CA", 1.458, 0.019), ("CA", "C", 1.525, 0.021), ("C", "O", 1.231, 0.020), ("C", "+N", 1.329, 0.014)],
+    };
+    match res_name {
+        "GLY" => (),
+        "ALA" => bonds.push(("CA", "CB", 1.521, 0.033)),
+        "ILE" | "THR" | "VAL" => bonds.push(("CA", "CB", 1.540, 0.027)),
+        _ => bonds.push(("CA", "CB", 1.530, 0.020)),
+    }
+    let side_chain: &[IdealBond] = match res_name {
+        "ARG" => &[
+            ("CB", "CG", 1.520, 0.030), ("CG", "CD", 1.520, 0.030), ("CD", "NE", 1.460, 0.018),
+            ("NE", "CZ", 1.329, 0.014), ("CZ", "NH1", 1.326, 0.018), ("CZ", "NH2", 1.326, 0.018),
+        ],
+        "ASN" => &[("CB", "CG", 1.516, 0.025), ("CG", "OD1", 1.231, 0.020), ("CG", "ND2", 1.328, 0.021)],
+        "ASP" => &[("CB", "CG", 1.516, 0.025), ("CG", "OD1", 1.249, 0.019), ("CG", "OD2", 1.249, 0.019)],
+        "CYS" => &[("CB", "SG", 1.808, 0.033)],
+        "GLN" => &[
+            ("CB", "CG", 1.520, 0.030), ("CG", "CD", 1.516, 0.025), ("CD", "OE1", 1.231, 0.020), ("CD", "NE2", 1.328, 0.021),
+        ],
+        "GLU" => &[
+            ("CB", "CG", 1.520, 0.030), ("CG", "CD", 1.516, 0.025), ("CD", "OE1", 1.249, 0.019), ("CD", "OE2", 1.249, 0.019),
+        ],
+        "HIS" => &[
+            ("CB", "CG", 1.497, 0.014), ("CG", "ND1", 1.378, 0.011), ("ND1", "CE1", 1.321, 0.010),
+            ("CE1", "NE2", 1.321, 0.010), ("NE2", "CD2", 1.374, 0.011), ("CD2", "CG", 1.354, 0.011),
+        ],
+        "ILE" => &[("CB", "CG1", 1.530, 0.020), ("CB", "CG2", 1.521, 0.033), ("CG1", "CD1", 1.513, 0.039)],
+        "LEU" => &[("CB", "CG", 1.530, 0.020), ("CG", "CD1", 1.521, 0.033), ("CG", "CD2", 1.521, 0.033)],
+        "LYS" => &[("CB", "CG", 1.520, 0.030), ("CG", "CD", 1.520, 0.030), ("CD", "CE", 1.520, 0.030), ("CE", "NZ", 1.489, 0.030)],
+        "MET" => &[("CB", "CG", 1.520, 0.030), ("CG", "SD", 1.803, 0.034), ("SD", "CE", 1.791, 0.059)],
+        "PHE" => &[
+            ("CB", "CG", 1.502, 0.023), ("CG", "CD1", 1.384, 0.021), ("CG", "CD2", 1.384, 0.021), ("CD1", "CE1", 1.382, 0.030),
+            ("CD2", "CE2", 1.382, 0.030), ("CE1", "CZ", 1.382, 0.030), ("CE2", "CZ", 1.382, 0.030),
+        ],
+        "PRO" => &[("CB", "CG", 1.492, 0.050), ("CG", "CD", 1.503, 0.034), ("CD", "N", 1.473, 0.014)],
+        "SER" => &[("CB", "OG", 1.417, 0.020)],
+        "THR" => &[("CB", "OG1", 1.433, 0.016), ("CB", "CG2", 1.521, 0.033)],
+        "TRP" => &[
+            ("CB", "CG", 1.498, 0.031), ("CG", "CD1", 1.365, 0.025), ("CD1", "NE1", 1.374, 0.021), ("NE1", "CE2", 1.370, 0.011),
+            ("CE2", "CD2", 1.409, 0.012), ("CD2", "CG", 1.433, 0.018), ("CE2", "CZ2", 1.394, 0.021), ("CZ2", "CH2", 1.368, 0.019),
+            ("CH2", "CZ3", 1.400, 0.025), ("CZ3", "CE3", 1.382, 0.030), ("CE3", "CD2", 1.398, 0.021),
+        ],
+        "TYR" => &[
+            ("CB", "CG", 1.512, 0.015), ("CG", "CD1", 1.389, 0.015), ("CG", "CD2", 1.389, 0.015), ("CD1", "CE1", 1.382, 0.030),
+            ("CD2", "CE2", 1.382, 0.030), ("CE1", "CZ", 1.382, 0.030), ("CE2", "CZ", 1.382, 0.030), ("CZ", "OH", 1.376, 0.021),
+        ],
+        "VAL" => &[("CB", "CG1", 1.521, 0.033), ("CB", "CG2", 1.521, 0.033)],
+        _ => &[],
+    };
+    bonds.extend_from_slice(side_chain);
+    bonds
+}
+
+/// The ideal bond angles of a standard amino acid (Engh & Huber 1991)
+fn ideal_angles(res_name: &str) -> Vec<IdealAngle> {
+    let mut angles: Vec<IdealAngle> = match res_name {
+        "GLY" => vec![
+            ("N", "CA", "C", 112.5, 2.9), ("CA", "C", "O", 120.8, 2.1), ("CA", "C", "+N", 116.4, 2.1),
+            ("O", "C", "+N", 123.0, 1.6), ("C", "+N", "+CA", 121.7, 1.8),
+        ],
+        "PRO" => vec![
+            ("N", "CA", "C", 111.8, 2.5), ("CA", "C", "O", 120.1, 2.1), ("CA", "C", "+N", 116.2, 2.0),
+            ("O", "C", "+N", 123.0, 1.6), ("C", "+N", "+CA", 121.7, 1.8),
+        ],
+        _ => vec![
+            ("N", "CA", "C", 111.2, 2.8), ("CA", "C", "O", 120.1, 2.1), ("CA", "C", "+N", 116.2, 2.0),
+            ("O", "C", "+N", 123.0, 1.6), ("C", "+N", "+CA", 121.7, 1.8),
+        ],
+    };
+    match res_name {
+        "GLY" => (),
+        "ALA" => angles.extend_from_slice(&[("N", "CA", "CB", 110.4, 1.5), ("CB", "CA", "C", 110.5, 1.5)]),
+        "ILE" | "THR" | "VAL" => angles.extend_from_slice(&[("N", "CA", "CB", 111.5, 1.7), ("CB", "CA", "C", 109.1, 2.2)]),
+        "PRO" => angles.extend_from_slice(&[("N", "CA", "CB", 103.0, 1.1), ("CB", "CA", "C", 110.1, 1.9)]),
+        _ => angles.extend_from_slice(&[("N", "CA", "CB", 110.5, 1.7), ("CB", "CA", "C", 110.1, 1.9)]),
+    }
+    let side_chain: &[IdealAngle] = match res_name {
+        "ARG" => &[
+            ("CA", "CB", "CG", 114.1, 2.0), ("CB", "CG", "CD", 111.3, 2.3), ("CG", "CD", "NE", 112.0, 2.2),
+            ("CD", "NE", "CZ", 124.2, 1.5), ("NE", "CZ", "NH1", 120.0, 1.9), ("NE", "CZ", "NH2", 120.0, 1.9),
+        ],
+        "ASN" => &[("CA", "CB", "CG", 112.6, 1.0), ("CB", "CG", "OD1", 120.8, 2.0), ("CB", "CG", "ND2", 116.4, 1.5)],
+        "ASP" => &[("CA", "CB", "CG", 112.6, 1.0), ("CB", "CG", "OD1", 118.4, 2.3), ("CB", "CG", "OD2", 118.4, 2.3)],
+        "CYS" => &[("CA", "CB", "SG", 114.4, 2.3)],
+        "GLN" => &[
+            ("CA", "CB", "CG", 114.1, 2.0), ("CB", "CG", "CD", 112.6, 1.7), ("CG", "CD", "OE1", 120.8, 2.0), ("CG", "CD", "NE2", 116.4, 1.5),
+        ],
+        "GLU" => &[
+            ("CA", "CB", "CG", 114.1, 2.0), ("CB", "CG", "CD", 112.6, 1.7), ("CG", "CD", "OE1", 118.4, 2.3), ("CG", "CD", "OE2", 118.4, 2.3),
+        ],
+        "HIS" => &[("CA", "CB", "CG", 113.8, 1.0), ("CB", "CG", "ND1", 122.7, 1.5), ("CB", "CG", "CD2", 131.2, 1.3)],
+        "ILE" => &[
+            ("CA", "CB", "CG1", 110.4, 1.7), ("CA", "CB", "CG2", 110.5, 1.5), ("CG1", "CB", "CG2", 110.7, 3.0), ("CB", "CG1", "CD1", 113.8, 2.1),
+        ],
+        "LEU" => &[("CA", "CB", "CG", 116.3, 3.5), ("CB", "CG", "CD1", 110.7, 3.0), ("CB", "CG", "CD2", 110.7, 3.0)],
+        "LYS" => &[
+            ("CA", "CB", "CG", 114.1, 2.0), ("CB", "CG", "CD", 111.3, 2.3), ("CG", "CD", "CE", 111.3, 2.3), ("CD", "CE", "NZ", 111.9, 3.2),
+        ],
+        "MET" => &[("CA", "CB", "CG", 114.1, 2.0), ("CB", "CG", "SD", 112.7, 3.0), ("CG", "SD", "CE", 100.9, 2.2)],
+        "PHE" => &[
+            ("CA", "CB", "CG", 113.8, 1.0), ("CB", "CG", "CD1", 120.7, 1.7), ("CB", "CG", "CD2", 120.7, 1.7), ("CD1", "CG", "CD2", 118.6, 1.5),
+        ],
+        "PRO" => &[("CA", "CB", "CG", 104.5, 1.9), ("CB", "CG", "CD", 106.1, 2.8), ("CG", "CD", "N", 103.2, 1.5), ("CA", "N", "CD", 112.0, 1.4)],
+        "SER" => &[("CA", "CB", "OG", 111.1, 2.0)],
+        "THR" => &[("CA", "CB", "OG1", 109.6, 1.5), ("CA", "CB", "CG2", 110.5, 1.5), ("OG1", "CB", "CG2", 109.3, 2.0)],
+        "TRP" => &[("CA", "CB", "CG", 113.6, 1.9), ("CB", "CG", "CD1", 126.9, 1.5), ("CB", "CG", "CD2", 126.8, 1.4)],
+        "TYR" => &[
+            ("CA", "CB", "CG", 113.9, 1.8), ("CB", "CG", "CD1", 120.8, 1.5), ("CB", "CG", "CD2", 120.8, 1.5), ("CE1", "CZ", "OH", 120.1, 2.7),
+        ],
+        "VAL" => &[("CA", "CB", "CG1", 110.5, 1.5), ("CA", "CB", "CG2", 110.5, 1.5), ("CG1", "CB", "CG2", 110.8, 2.2)],
+        _ => &[],
+    };
+    angles.extend_from_slice(side_chain);
+    angles
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::peptide;
+
+    #[test]
+    fn ideal_peptide_and_stretched_bond() {
+        let mut pdb = peptide(&[("ALA", -60.0, -45.0), ("ALA", -60.0, -45.0), ("ALA", -60.0, -45.0)]);
+        let report = validate_geometry(&pdb, 4.0);
+        assert_eq!(report.len(), 3);
+        assert!(report.iter().all(|r| r.outliers.is_empty()), "{:?}", report);
+        assert_eq!((report[0].bonds, report[0].angles), (5, 7));
+        assert_eq!((report[2].bonds, report[2].angles), (4, 4));
+
+        // stretch CA-CB of the second residue by 0.2 Å
+        let ca = pdb.atoms().find(|a| *a.res_seq() == 2 && a.atom_name() == "CA").unwrap().coords();
+        let cb = pdb.atoms_mut().find(|a| *a.res_seq() == 2 && a.atom_name() == "CB").unwrap();
+        let position = cb.coords() + (cb.coords() - ca).normalize().unwrap() * 0.2;
+        cb.set_position(position.to_tuple()).unwrap();
+        let report = validate_geometry(&pdb, 4.0);
+        let outliers = &report[1].outliers;
+        assert_eq!(outliers.len(), 1);
+        assert_eq!((outliers[0].kind, outliers[0].atoms.clone()), (GeometryKind::BondLength, vec!["CA".to_owned(), "CB".to_owned()]));
+        assert!((outliers[0].z_score - (1.53 + 0.2 - 1.521) / 0.033).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,42 @@
+//! Validation of PDB content, from the allowed characters to the covalent geometry of residues
+
+mod bond_geometry;
+mod stereochemistry;
+
+pub use bond_geometry::{validate_geometry, GeometryKind, GeometryOutlier, ResidueGeometry};
+pub use stereochemistry::{validate_stereochemistry, ResidueStereochemistry};
+
+use crate::structs::Atom;
+use crate::structs::atom::{is_amino_acid, MAX_PEPTIDE_BOND};
+
+/// Checks if a char is allowed in a PDB file.
+/// The char has to be ASCII graphic or a space.
+/// Returns `true` if the char is valid.
+pub fn check_char(c: char) -> bool {
+    (c as u32) < 127 && (c as u32) > 31
+}
+
+/// Checks a string using `check_char`.
+/// Returns `true` if the text is valid.
+pub fn valid_identifier(text: &str) -> bool {
+    for c in text.chars() {
+        if !check_char(c) {
+            return false;
+        }
+    }
+    true
+}
+
+pub fn prepare_identifier(text: &str) -> Option<String> {
+    if valid_identifier(text) && !text.trim().is_empty() {
+        Some(text.trim().to_ascii_uppercase())
+    } else {
+        None
+    }
+}
+
+/// Whether the carbonyl carbon `c` and the nitrogen `n` of the following
+/// amino acid are linked by a peptide bond, a longer distance is a chain break.
+fn peptide_bond(c: &Atom, n: &Atom) -> bool {
+    c.chain_id() == n.chain_id() && is_amino_acid(n.res_name()) && c.distance(n) <= MAX_PEPTIDE_BOND
+}
@@ -0,0 +1,146 @@
+use crate::structs::{PDB, Atom, ResidueId};
+use crate::structs::atom::is_amino_acid;
+use super::peptide_bond;
+
+/// The chirality checks and the peptide bond planarity of a standard residue
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResidueStereochemistry {
+    pub residue: ResidueId,
+    pub res_name: String,
+    /// The chiral volume (Å³) N·(C×CB) around CA, positive for an L amino acid.
+    /// `None` for glycine or if an atom is missing.
+    pub ca_volume: Option<f64>,
+    /// The chiral volume (Å³) around CB of threonine (OG1·(CA×CG2), negative for 2S,3R)
+    /// and isoleucine (CA·(CG1×CG2), positive for 2S,3S), `None` for other residues.
+    pub cb_volume: Option<f64>,
+    /// The omega angle (degrees) of the peptide bond to the next residue
+    pub omega: Option<f64>,
+    /// The CA atom has D chirality
+    pub d_amino_acid: bool,
+    /// The CB atom of threonine or isoleucine has the wrong chirality
+    pub wrong_cb_chirality: bool,
+    /// The peptide bond to the next residue is cis (omega within 30 degrees of 0)
+    pub cis_peptide: bool,
+    /// The peptide bond to the next residue deviates from planarity by more than the cutoff
+    pub twisted_peptide: bool,
+}
+
+impl ResidueStereochemistry {
+    /// Whether any of the checks failed. A cis peptide bond is only an error if
+    /// the next residue is not a proline, which is left to the caller.
+    pub fn has_errors(&self) -> bool {
+        self.d_amino_acid || self.wrong_cb_chirality || self.twisted_peptide
+    }
+}
+
+/// Checks the CA chirality of the standard amino acids, the CB chirality of threonine
+/// and isoleucine and the planarity of the peptide bonds. A peptide bond is twisted if
+/// omega deviates more than `max_omega_deviation` degrees from both 180 (trans) and 0 (cis),
+/// MolProbity uses 30.
+pub fn validate_stereochemistry(pdb: &PDB, max_omega_deviation: f64) -> Vec<ResidueStereochemistry> {
+    let residues = pdb.residue_indices();
+    let atoms: Vec<&Atom> = pdb.atoms().collect();
+    let find = |r: usize, name: &str| residues[r].1.iter().map(|i| atoms[*i]).find(|a| a.atom_name() == name);
+    let volume = |center: &Atom, a: &Atom, b: &Atom, c: &Atom| {
+        let center = center.coords();
+        (a.coords() - center).dot(&(b.coords() - center).cross(&(c.coords() - center)))
+    };
+
+    let mut result = Vec::new();
+    for (r, (residue, _)) in residues.iter().enumerate() {
+        let res_name = atoms[residues[r].1[0]].res_name().as_str();
+        if !is_amino_acid(res_name) {
+            continue;
+        }
+        let get = |names: &[&str]| names.iter().map(|n| find(r, n)).collect::<Option<Vec<&Atom>>>();
+        let ca_volume = get(&["CA", "N", "C", "CB"]).map(|a| volume(a[0], a[1], a[2], a[3]));
+        let cb_volume = match res_name {
+            "THR" => get(&["CB", "OG1", "CA", "CG2"]),
+            "ILE" => get(&["CB", "CA", "CG1", "CG2"]),
+            _ => None,
+        }
+        .map(|a| volume(a[0], a[1], a[2], a[3]));
+        let next = |name: &str| residues.get(r + 1).and_then(|_| find(r + 1, name));
+        let omega = match (find(r, "CA"), find(r, "C"), next("N"), next("CA")) {
+            (Some(ca), Some(c), Some(n), Some(next_ca)) if peptide_bond(c, n) => Some(Atom::dihedral(ca, c, n, next_ca)),
+            _ => None,
+        };
+        let cis_peptide = omega.map(|omega| omega.abs() <= 30.0).unwrap_or(false);
+        let twisted_peptide = omega
+            .map(|omega| omega.abs().min(180.0 - omega.abs()) > max_omega_deviation)
+            .unwrap_or(false);
+
+        result.push(ResidueStereochemistry {
+            residue: residue.clone(),
+            res_name: res_name.to_owned(),
+            d_amino_acid: ca_volume.map(|v| v < 0.0).unwrap_or(false),
+            wrong_cb_chirality: match (res_name, cb_volume) {
+                ("THR", Some(v)) => v > 0.0,
+                ("ILE", Some(v)) => v < 0.0,
+                _ => false,
+            },
+            ca_volume,
+            cb_volume,
+            omega,
+            cis_peptide,
+            twisted_peptide,
+        });
+    }
+    result
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::place_atom;
+    use crate::testing::peptide;
+
+    /// A peptide with the CG1 and CG2 atoms of isoleucine `res_seq` inserted after its CB,
+    /// CG2 at chi1 - 120 degrees unless `swap`
+    fn with_isoleucine_branches(pdb: &PDB, res_seq: usize, swap: bool) -> PDB {
+        let find = |name: &str| pdb.atoms().find(|a| *a.res_seq() == res_seq && a.atom_name() == name).unwrap().coords();
+        let (n, ca, cb) = (find("N"), find("CA"), find("CB"));
+        let cg1 = place_atom(&n, &ca, &cb, 1.53, 110.4, -60.0);
+        let cg2 = place_atom(&n, &ca, &cb, 1.52, 110.5, 180.0);
+        let (cg1, cg2) = if swap { (cg2, cg1) } else { (cg1, cg2) };
+        let mut result = PDB::new();
+        for atom in pdb.atoms() {
+            result.add_atom(atom.clone());
+            if *atom.res_seq() == res_seq && atom.atom_name() == "CB" {
+                for (serial, name, p) in [(100, "CG1", cg1), (101, "CG2", cg2)] {
+                    result.add_atom(Atom::new(false, serial, name, "ILE", "A", res_seq, p.x, p.y, p.z, 1.0, 0.0, "C", 0).unwrap());
+                }
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn chirality_and_mirror_image() {
+        let pdb = peptide(&[("ALA", -60.0, -45.0), ("ILE", -60.0, -45.0), ("ALA", -60.0, -45.0)]);
+        let pdb = with_isoleucine_branches(&pdb, 2, false);
+        let report = validate_stereochemistry(&pdb, 30.0);
+        assert_eq!(report.len(), 3);
+        assert!(report.iter().all(|r| !r.has_errors()), "{:?}", report);
+        assert!((report[0].omega.unwrap().abs() - 180.0).abs() < 1e-6);
+        assert!(report[2].omega.is_none());
+
+        let mut mirrored = pdb.clone();
+        for atom in mirrored.atoms_mut() {
+            let x = -*atom.x();
+            atom.set_x(x).unwrap();
+        }
+        let report = validate_stereochemistry(&mirrored, 30.0);
+        assert!(report.iter().all(|r| r.d_amino_acid));
+        assert!(report[1].wrong_cb_chirality);
+    }
+
+    #[test]
+    fn swapped_isoleucine_branches() {
+        let pdb = with_isoleucine_branches(&peptide(&[("ILE", -60.0, -45.0)]), 1, true);
+        let report = validate_stereochemistry(&pdb, 30.0);
+        assert!(!report[0].d_amino_acid);
+        assert!(report[0].wrong_cb_chirality);
+    }
+}